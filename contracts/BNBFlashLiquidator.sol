@@ -22,6 +22,10 @@ interface IVToken {
     function redeem(uint redeemTokens) external returns (uint);
 }
 
+interface IVBNB {
+    function liquidateBorrow(address borrower, address vTokenCollateral) external payable;
+}
+
 interface IERC20 {
     function transfer(address to, uint amount) external returns (bool);
     function approve(address spender, uint amount) external returns (bool);
@@ -101,16 +105,26 @@ contract BNBFlashLiquidator {
         
         LiquidationParams memory liqParams = abi.decode(params, (LiquidationParams));
         
-        // 1. Approve vToken to spend debt
-        IERC20(asset).approve(liqParams.vTokenBorrowed, amount);
-        
-        // 2. Execute liquidation
-        uint result = IVToken(liqParams.vTokenBorrowed).liquidateBorrow(
-            liqParams.borrower,
-            amount,
-            liqParams.vTokenCollateral
-        );
-        require(result == 0, "Liquidation failed");
+        if (liqParams.vTokenBorrowed == vBNB) {
+            // 1-2. Native market: unwrap the flash-loaned WBNB and repay in BNB
+            require(asset == WBNB, "vBNB repay needs WBNB");
+            IWBNB(WBNB).withdraw(amount);
+            IVBNB(vBNB).liquidateBorrow{value: amount}(
+                liqParams.borrower,
+                liqParams.vTokenCollateral
+            );
+        } else {
+            // 1. Approve vToken to spend debt
+            IERC20(asset).approve(liqParams.vTokenBorrowed, amount);
+            
+            // 2. Execute liquidation
+            uint result = IVToken(liqParams.vTokenBorrowed).liquidateBorrow(
+                liqParams.borrower,
+                amount,
+                liqParams.vTokenCollateral
+            );
+            require(result == 0, "Liquidation failed");
+        }
         
         // 3. Redeem seized vTokens
         uint vTokenBalance = IVToken(liqParams.vTokenCollateral).balanceOf(address(this));
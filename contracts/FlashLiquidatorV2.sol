@@ -26,6 +26,25 @@ interface IPoolAddressesProvider {
     function getPool() external view returns (address);
 }
 
+// Flash loan lenders other than the Aave pool
+interface IBalancerVault {
+    function flashLoan(
+        address recipient,
+        address[] memory tokens,
+        uint256[] memory amounts,
+        bytes memory userData
+    ) external;
+}
+
+interface IMorpho {
+    function flashLoan(address token, uint256 assets, bytes calldata data) external;
+}
+
+interface IUniswapV3Pool {
+    function token0() external view returns (address);
+    function flash(address recipient, uint256 amount0, uint256 amount1, bytes calldata data) external;
+}
+
 // Uniswap V3 Router
 interface ISwapRouterV3 {
     struct ExactInputSingleParams {
@@ -40,6 +59,16 @@ interface ISwapRouterV3 {
     }
     
     function exactInputSingle(ExactInputSingleParams calldata params) external payable returns (uint256 amountOut);
+    
+    struct ExactInputParams {
+        bytes path;
+        address recipient;
+        uint256 deadline;
+        uint256 amountIn;
+        uint256 amountOutMinimum;
+    }
+    
+    function exactInput(ExactInputParams calldata params) external payable returns (uint256 amountOut);
 }
 
 // Uniswap V2 / Sushiswap Router
@@ -88,8 +117,43 @@ contract FlashLiquidatorV2 {
         uint256 minProfit;
     }
     
+    // How seized collateral is sold, chosen off-chain from the best quote
+    enum RouteKind {
+        BestDex,    // contract picks a DEX itself (_swapWithBestDex)
+        UniswapV3,  // exactInput along `data` (packed token/fee path)
+        Aggregator  // call `target` with `data` (1inch / Paraswap calldata)
+    }
+    
+    struct SwapRoute {
+        RouteKind kind;
+        address target;  // UniswapV3: router override (0 = uniswapV3Router)
+        bytes data;
+    }
+    
+    // Where the debt asset is flash borrowed from, picked off-chain by fee
+    enum FlashKind {
+        AavePool,   // POOL.flashLoanSimple (premium set by Aave governance)
+        Balancer,   // Vault.flashLoan (protocol flash fee, currently zero)
+        UniswapV3,  // pool.flash (pool fee tier); must not be on the swap route
+        Morpho      // Morpho Blue flashLoan (free)
+    }
+    
+    struct FlashSource {
+        FlashKind kind;
+        address lender;  // ignored for AavePool
+    }
+    
+    // One position of a batched liquidation
+    struct BatchItem {
+        address user;
+        uint256 debtToCover;
+    }
+    
     LiquidationParams private liquidationParams;
     
+    // Lender of the flash loan in progress, the only allowed callback caller
+    address private activeLender;
+    
     event LiquidationExecuted(
         address indexed user,
         address collateralAsset,
@@ -100,6 +164,10 @@ contract FlashLiquidatorV2 {
         string dexUsed
     );
     
+    // Per-position results of executeBatchLiquidation
+    event BatchItemLiquidated(address indexed user, uint256 debtRepaid, uint256 collateralSeized);
+    event BatchItemFailed(address indexed user, bytes reason);
+    
     event ProfitWithdrawn(address token, uint256 amount);
     event SlippageUpdated(uint256 newSlippageBps);
     event DexUpdated(string dex, address router);
@@ -188,6 +256,142 @@ contract FlashLiquidatorV2 {
         POOL.flashLoanSimple(address(this), debtAsset, debtToCover, "", 0);
     }
     
+    // V2 entry point: borrows from the given lender and sells collateral
+    // along an off-chain quoted route
+    function executeLiquidationWithRoute(
+        address collateralAsset,
+        address debtAsset,
+        address user,
+        uint256 debtToCover,
+        uint256 minProfit,
+        SwapRoute calldata route,
+        FlashSource calldata flash
+    ) external onlyOwner {
+        liquidationParams = LiquidationParams({
+            collateralAsset: collateralAsset,
+            debtAsset: debtAsset,
+            user: user,
+            debtToCover: debtToCover,
+            minProfit: minProfit
+        });
+        
+        _flashBorrow(flash, debtAsset, debtToCover, abi.encode(route));
+    }
+    
+    // Batched V2 entry point: liquidates several positions of one
+    // (collateral, debt) pair with a single flash loan and a single swap.
+    // Items that revert (e.g. already liquidated) are skipped; minProfit is
+    // scaled down to the debt actually repaid.
+    function executeBatchLiquidation(
+        address collateralAsset,
+        address debtAsset,
+        BatchItem[] calldata items,
+        uint256 minProfit,
+        SwapRoute calldata route,
+        FlashSource calldata flash
+    ) external onlyOwner {
+        require(items.length > 0, "Empty batch");
+        require(collateralAsset != debtAsset, "Same-asset batch");
+        
+        uint256 totalDebt;
+        for (uint256 i = 0; i < items.length; i++) {
+            totalDebt += items[i].debtToCover;
+        }
+        
+        // user == 0 marks a batch; the items travel with the route
+        liquidationParams = LiquidationParams({
+            collateralAsset: collateralAsset,
+            debtAsset: debtAsset,
+            user: address(0),
+            debtToCover: totalDebt,
+            minProfit: minProfit
+        });
+        
+        _flashBorrow(flash, debtAsset, totalDebt, abi.encode(route, items));
+    }
+    
+    function _flashBorrow(
+        FlashSource calldata flash,
+        address asset,
+        uint256 amount,
+        bytes memory data
+    ) internal {
+        if (flash.kind == FlashKind.AavePool) {
+            POOL.flashLoanSimple(address(this), asset, amount, data, 0);
+            return;
+        }
+        
+        activeLender = flash.lender;
+        if (flash.kind == FlashKind.Balancer) {
+            address[] memory tokens = new address[](1);
+            uint256[] memory amounts = new uint256[](1);
+            tokens[0] = asset;
+            amounts[0] = amount;
+            IBalancerVault(flash.lender).flashLoan(address(this), tokens, amounts, data);
+        } else if (flash.kind == FlashKind.Morpho) {
+            IMorpho(flash.lender).flashLoan(asset, amount, data);
+        } else {
+            bool isToken0 = IUniswapV3Pool(flash.lender).token0() == asset;
+            IUniswapV3Pool(flash.lender).flash(
+                address(this),
+                isToken0 ? amount : 0,
+                isToken0 ? 0 : amount,
+                data
+            );
+        }
+        activeLender = address(0);
+    }
+    
+    // Self-funded: liquidate with debt asset this contract already holds, no
+    // flash loan. Seized collateral is kept as aTokens when receiveAToken,
+    // otherwise sold back into the debt asset along `route`.
+    function liquidateFromInventory(
+        address collateralAsset,
+        address debtAsset,
+        address user,
+        uint256 debtToCover,
+        uint256 minProfit,
+        bool receiveAToken,
+        SwapRoute calldata route
+    ) external onlyOwner {
+        uint256 debtBefore = IERC20(debtAsset).balanceOf(address(this));
+        uint256 colBefore = IERC20(collateralAsset).balanceOf(address(this));
+        
+        IERC20(debtAsset).approve(address(POOL), debtToCover);
+        POOL.liquidationCall(collateralAsset, debtAsset, user, debtToCover, receiveAToken);
+        
+        if (receiveAToken) {
+            emit LiquidationExecuted(user, collateralAsset, debtAsset, debtToCover, 0, 0, "aToken");
+            return;
+        }
+        
+        uint256 colReceived = IERC20(collateralAsset).balanceOf(address(this)) - colBefore;
+        require(colReceived > 0, "No collateral received");
+        
+        string memory dexUsed = "none";
+        if (collateralAsset != debtAsset) {
+            uint256 debtSpent = debtBefore - IERC20(debtAsset).balanceOf(address(this));
+            if (route.kind == RouteKind.BestDex) {
+                dexUsed = _swapWithBestDex(collateralAsset, debtAsset, colReceived, debtSpent);
+            } else {
+                dexUsed = _swapAlongRoute(route, collateralAsset, colReceived, debtSpent + minProfit);
+            }
+        }
+        
+        uint256 debtAfter = IERC20(debtAsset).balanceOf(address(this));
+        require(debtAfter >= debtBefore + minProfit, "Below minimum profit");
+        
+        emit LiquidationExecuted(
+            user,
+            collateralAsset,
+            debtAsset,
+            debtToCover,
+            colReceived,
+            debtAfter - debtBefore,
+            dexUsed
+        );
+    }
+    
     // Legacy function for compatibility
     function executeLiquidation(
         address collateralAsset,
@@ -207,20 +411,76 @@ contract FlashLiquidatorV2 {
     }
     
     // ============================================================
-    // FLASH LOAN CALLBACK
+    // FLASH LOAN CALLBACKS
     // ============================================================
     
+    // Aave V3
     function executeOperation(
         address asset,
         uint256 amount,
         uint256 premium,
         address initiator,
-        bytes calldata
+        bytes calldata params
     ) external returns (bool) {
         require(msg.sender == address(POOL), "Caller must be pool");
         require(initiator == address(this), "Invalid initiator");
         
+        uint256 amountOwed = _liquidateAndSwap(asset, amount, premium, params);
+        
+        // Pool pulls the repayment
+        IERC20(asset).approve(address(POOL), amountOwed);
+        return true;
+    }
+    
+    // Balancer V2
+    function receiveFlashLoan(
+        address[] memory tokens,
+        uint256[] memory amounts,
+        uint256[] memory feeAmounts,
+        bytes memory userData
+    ) external {
+        require(msg.sender == activeLender, "Caller must be lender");
+        
+        uint256 amountOwed = _liquidateAndSwap(tokens[0], amounts[0], feeAmounts[0], userData);
+        
+        // Vault expects the repayment transferred back
+        IERC20(tokens[0]).transfer(msg.sender, amountOwed);
+    }
+    
+    // Morpho Blue
+    function onMorphoFlashLoan(uint256 assets, bytes calldata data) external {
+        require(msg.sender == activeLender, "Caller must be lender");
+        
+        address asset = liquidationParams.debtAsset;
+        uint256 amountOwed = _liquidateAndSwap(asset, assets, 0, data);
+        
+        // Morpho pulls the repayment
+        IERC20(asset).approve(msg.sender, amountOwed);
+    }
+    
+    // Uniswap V3 pool flash
+    function uniswapV3FlashCallback(uint256 fee0, uint256 fee1, bytes calldata data) external {
+        require(msg.sender == activeLender, "Caller must be lender");
+        
+        LiquidationParams memory lp = liquidationParams;
+        uint256 amountOwed = _liquidateAndSwap(lp.debtAsset, lp.debtToCover, fee0 + fee1, data);
+        
+        // Pool checks its balance after the callback
+        IERC20(lp.debtAsset).transfer(msg.sender, amountOwed);
+    }
+    
+    // Liquidate with the borrowed debt asset and sell the collateral;
+    // returns what the lender is owed
+    function _liquidateAndSwap(
+        address asset,
+        uint256 amount,
+        uint256 premium,
+        bytes memory params
+    ) internal returns (uint256) {
         LiquidationParams memory lp = liquidationParams;
+        if (lp.user == address(0)) {
+            return _liquidateBatchAndSwap(asset, amount, premium, params);
+        }
         
         // 1. Approve debt asset for liquidation
         IERC20(asset).approve(address(POOL), amount);
@@ -247,16 +507,21 @@ contract FlashLiquidatorV2 {
         // 6. Calculate minimum output with slippage protection
         uint256 minOutput = amountOwed * (10000 + maxSlippageBps) / 10000;
         
-        // 7. Convert collateral to debt asset using best DEX
+        // 7. Convert collateral to debt asset along the given route, or the best DEX
         string memory dexUsed = "none";
         if (lp.collateralAsset != lp.debtAsset) {
-            dexUsed = _swapWithBestDex(lp.collateralAsset, lp.debtAsset, colReceived, amountOwed);
+            SwapRoute memory route;
+            if (params.length > 0) {
+                route = abi.decode(params, (SwapRoute));
+            }
+            if (route.kind == RouteKind.BestDex) {
+                dexUsed = _swapWithBestDex(lp.collateralAsset, lp.debtAsset, colReceived, amountOwed);
+            } else {
+                dexUsed = _swapAlongRoute(route, lp.collateralAsset, colReceived, amountOwed + lp.minProfit);
+            }
         }
         
-        // 8. Approve repayment
-        IERC20(asset).approve(address(POOL), amountOwed);
-        
-        // 9. Calculate and verify profit
+        // 8. Calculate and verify profit (the caller repays the lender)
         uint256 finalBalance = IERC20(asset).balanceOf(address(this));
         require(finalBalance >= amountOwed, "Insufficient balance to repay");
         
@@ -273,7 +538,73 @@ contract FlashLiquidatorV2 {
             dexUsed
         );
         
-        return true;
+        return amountOwed;
+    }
+    
+    // _liquidateAndSwap for executeBatchLiquidation: each item is liquidated
+    // on its own, then all seized collateral is sold at once
+    function _liquidateBatchAndSwap(
+        address asset,
+        uint256 amount,
+        uint256 premium,
+        bytes memory params
+    ) internal returns (uint256) {
+        LiquidationParams memory lp = liquidationParams;
+        (SwapRoute memory route, BatchItem[] memory items) = abi.decode(params, (SwapRoute, BatchItem[]));
+        
+        IERC20(asset).approve(address(POOL), amount);
+        uint256 colBefore = IERC20(lp.collateralAsset).balanceOf(address(this));
+        uint256 debtRepaid;
+        
+        for (uint256 i = 0; i < items.length; i++) {
+            uint256 debtBefore = IERC20(asset).balanceOf(address(this));
+            uint256 itemColBefore = IERC20(lp.collateralAsset).balanceOf(address(this));
+            
+            try POOL.liquidationCall(lp.collateralAsset, lp.debtAsset, items[i].user, items[i].debtToCover, false) {
+                uint256 repaid = debtBefore - IERC20(asset).balanceOf(address(this));
+                uint256 seized = IERC20(lp.collateralAsset).balanceOf(address(this)) - itemColBefore;
+                debtRepaid += repaid;
+                emit BatchItemLiquidated(items[i].user, repaid, seized);
+            } catch (bytes memory reason) {
+                emit BatchItemFailed(items[i].user, reason);
+            }
+        }
+        IERC20(asset).approve(address(POOL), 0);
+        
+        uint256 colReceived = IERC20(lp.collateralAsset).balanceOf(address(this)) - colBefore;
+        require(colReceived > 0, "No collateral received");
+        
+        // Unused borrowed debt is still held, so the swap only has to cover
+        // what was repaid plus the premium
+        uint256 amountOwed = amount + premium;
+        uint256 swapNeeded = debtRepaid + premium;
+        uint256 minProfit = lp.minProfit * debtRepaid / lp.debtToCover;
+        
+        // Aggregator calldata is fixed to the full batch's collateral
+        string memory dexUsed;
+        if (route.kind == RouteKind.BestDex || (route.kind == RouteKind.Aggregator && debtRepaid < lp.debtToCover)) {
+            dexUsed = _swapWithBestDex(lp.collateralAsset, lp.debtAsset, colReceived, swapNeeded);
+        } else {
+            dexUsed = _swapAlongRoute(route, lp.collateralAsset, colReceived, swapNeeded + minProfit);
+        }
+        
+        uint256 finalBalance = IERC20(asset).balanceOf(address(this));
+        require(finalBalance >= amountOwed, "Insufficient balance to repay");
+        
+        uint256 profit = finalBalance - amountOwed;
+        require(profit >= minProfit, "Below minimum profit");
+        
+        emit LiquidationExecuted(
+            address(0),
+            lp.collateralAsset,
+            lp.debtAsset,
+            debtRepaid,
+            colReceived,
+            profit,
+            dexUsed
+        );
+        
+        return amountOwed;
     }
     
     // ============================================================
@@ -322,6 +653,40 @@ contract FlashLiquidatorV2 {
         revert("All swaps failed");
     }
     
+    function _swapAlongRoute(
+        SwapRoute memory route,
+        address tokenIn,
+        uint256 amountIn,
+        uint256 minAmountOut
+    ) internal returns (string memory dexUsed) {
+        if (route.kind == RouteKind.UniswapV3) {
+            address router = route.target == address(0) ? uniswapV3Router : route.target;
+            IERC20(tokenIn).approve(router, amountIn);
+            ISwapRouterV3(router).exactInput(
+                ISwapRouterV3.ExactInputParams({
+                    path: route.data,
+                    recipient: address(this),
+                    deadline: block.timestamp + 300,
+                    amountIn: amountIn,
+                    amountOutMinimum: minAmountOut
+                })
+            );
+            return "UniswapV3Route";
+        }
+        
+        // Aggregator calldata carries its own amount and slippage; the
+        // final balance check enforces amountOwed + minProfit
+        IERC20(tokenIn).approve(route.target, amountIn);
+        (bool ok, bytes memory result) = route.target.call(route.data);
+        if (!ok) {
+            assembly {
+                revert(add(result, 32), mload(result))
+            }
+        }
+        IERC20(tokenIn).approve(route.target, 0);
+        return "Aggregator";
+    }
+    
     // ============================================================
     // DEX QUOTE FUNCTIONS
     // ============================================================
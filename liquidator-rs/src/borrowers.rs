@@ -1,6 +1,7 @@
 //! Borrower discovery from on-chain events.
 //!
-//! Discovers borrowers by scanning Borrow events from Aave pools.
+//! Discovers borrowers per lending market (Aave pool, Compound Comet, Venus
+//! Comptroller) and keeps one set per (chain, protocol, market) source.
 
 use ethers::providers::{Provider, Http, Middleware};
 use ethers::types::{Address, Filter, H256};
@@ -8,31 +9,92 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use tokio::fs;
-use tracing::{info, warn, debug, error};
+use tracing::{info, warn, debug};
+
+use crate::config::ChainConfig;
+use crate::protocols::compound::discover_compound_borrowers;
+use crate::protocols::venus::discover_venus_borrowers;
+use crate::types::Protocol;
 
 /// Aave Borrow event signature
 /// Borrow(address indexed reserve, address user, address indexed onBehalfOf, uint256 amount, uint8 interestRateMode, uint256 borrowRate, uint16 indexed referralCode)
 pub const AAVE_BORROW_TOPIC: &str = "0xb3d084820fb1a9decffb176436bd02558d15fac9b0ddfed8c465bc7359d7dce0";
 
+/// Current on-disk borrowers.json format version
+pub const BORROWER_FILE_VERSION: u32 = 2;
+
 /// V7.5 borrower entry format
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BorrowerEntry {
     pub user: String,
 }
 
-/// Raw V7.5 format from disk
+/// Raw V7.5 format from disk (chain -> Aave borrowers)
 pub type RawBorrowerStore = HashMap<String, Vec<BorrowerEntry>>;
 
-/// Normalized borrower storage
+/// A lending market borrowers are discovered from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BorrowerSource {
+    pub chain: String,
+    pub protocol: Protocol,
+    /// Aave pool, Compound Comet or Venus Comptroller
+    pub market: Address,
+}
+
+impl BorrowerSource {
+    pub fn new(chain: &str, protocol: Protocol, market: Address) -> Self {
+        Self {
+            chain: chain.to_lowercase(),
+            protocol,
+            market,
+        }
+    }
+}
+
+impl std::fmt::Display for BorrowerSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}/{:?}", self.chain, self.protocol, self.market)
+    }
+}
+
+/// Borrowers of one source plus incremental discovery progress
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BorrowerSet {
+    pub borrowers: Vec<Address>,
+    pub last_scanned_block: u64,
+}
+
+/// One source as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredBorrowerSet {
+    #[serde(flatten)]
+    source: BorrowerSource,
+    #[serde(flatten)]
+    set: BorrowerSet,
+}
+
+/// Versioned on-disk format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredBorrowerFile {
+    version: u32,
+    sources: Vec<StoredBorrowerSet>,
+}
+
+/// Normalized borrower storage keyed by (chain, protocol, market)
 #[derive(Debug, Clone, Default)]
 pub struct BorrowerStore {
-    pub aave: HashMap<String, Vec<Address>>,
-    pub last_scanned_block: HashMap<String, u64>,
+    pub sets: HashMap<BorrowerSource, BorrowerSet>,
 }
 
 impl BorrowerStore {
-    /// Load from V7.5 format file
-    pub async fn load(path: &Path) -> Self {
+    /// Load from disk, migrating the V7.5 format if needed.
+    ///
+    /// V7.5 files only hold Aave borrowers per chain; `legacy_aave_source`
+    /// maps a chain name to the Aave source those borrowers belong to.
+    pub async fn load<F>(path: &Path, legacy_aave_source: F) -> Self
+    where
+        F: Fn(&str) -> Option<BorrowerSource>,
+    {
         let mut store = Self::default();
         
         let content = match fs::read_to_string(path).await {
@@ -43,7 +105,21 @@ impl BorrowerStore {
             }
         };
         
-        // Parse V7.5 format
+        if let Ok(file) = serde_json::from_str::<StoredBorrowerFile>(&content) {
+            if file.version > BORROWER_FILE_VERSION {
+                warn!("borrowers.json version {} is newer than supported {}", 
+                    file.version, BORROWER_FILE_VERSION);
+            }
+            for stored in file.sources {
+                let mut set = stored.set;
+                let mut seen = HashSet::new();
+                set.borrowers.retain(|b| seen.insert(*b));
+                store.sets.insert(stored.source, set);
+            }
+            return store;
+        }
+        
+        // Fall back to V7.5 format
         let raw: RawBorrowerStore = match serde_json::from_str(&content) {
             Ok(r) => r,
             Err(e) => {
@@ -52,7 +128,7 @@ impl BorrowerStore {
             }
         };
         
-        // Convert to normalized format
+        let mut migrated = 0;
         for (chain, entries) in raw {
             let chain_lower = chain.to_lowercase();
             let chain_normalized = normalize_chain_name(&chain_lower);
@@ -62,57 +138,84 @@ impl BorrowerStore {
                 .filter_map(|entry| entry.user.parse().ok())
                 .collect();
             
-            if !addresses.is_empty() {
-                store.aave.insert(chain_normalized.to_string(), addresses);
+            if addresses.is_empty() {
+                continue;
+            }
+            
+            match legacy_aave_source(chain_normalized) {
+                Some(source) => {
+                    migrated += store.add_borrowers(&source, addresses);
+                }
+                None => {
+                    warn!("{}: Dropping {} V7.5 borrowers - no Aave market configured", 
+                        chain_normalized, addresses.len());
+                }
             }
         }
         
+        info!("📦 Migrated {} borrowers from V7.5 format", migrated);
         store
     }
     
-    /// Save to disk
+    /// Save to disk in the current versioned format
     pub async fn save(&self, path: &Path) -> anyhow::Result<()> {
         // Ensure data directory exists
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await.ok();
         }
         
-        // Convert to V7.5 format
-        let mut raw: RawBorrowerStore = HashMap::new();
-        for (chain, addresses) in &self.aave {
-            let entries: Vec<BorrowerEntry> = addresses
-                .iter()
-                .map(|a| BorrowerEntry { user: format!("{:?}", a) })
-                .collect();
-            
-            // Capitalize chain name for V7.5 compatibility
-            let chain_cap = capitalize_chain_name(chain);
-            raw.insert(chain_cap, entries);
-        }
+        let mut sources: Vec<StoredBorrowerSet> = self.sets
+            .iter()
+            .map(|(source, set)| StoredBorrowerSet {
+                source: source.clone(),
+                set: set.clone(),
+            })
+            .collect();
+        // Stable ordering keeps diffs of the file readable
+        sources.sort_by(|a, b| {
+            (&a.source.chain, a.source.protocol.to_string(), a.source.market)
+                .cmp(&(&b.source.chain, b.source.protocol.to_string(), b.source.market))
+        });
         
-        let content = serde_json::to_string_pretty(&raw)?;
+        let file = StoredBorrowerFile {
+            version: BORROWER_FILE_VERSION,
+            sources,
+        };
+        
+        let content = serde_json::to_string_pretty(&file)?;
         fs::write(path, content).await?;
         
         Ok(())
     }
     
-    /// Get borrowers for a chain
-    pub fn get_aave_borrowers(&self, chain: &str) -> Vec<Address> {
-        let chain_lower = chain.to_lowercase();
-        self.aave.get(&chain_lower).cloned().unwrap_or_default()
+    /// Get borrowers for a single source
+    pub fn get_borrowers(&self, source: &BorrowerSource) -> Vec<Address> {
+        self.sets.get(source).map(|s| s.borrowers.clone()).unwrap_or_default()
     }
     
-    /// Add discovered borrowers
-    pub fn add_borrowers(&mut self, chain: &str, new_borrowers: Vec<Address>) -> usize {
+    /// Get borrowers of a protocol across all its markets on a chain
+    pub fn get_protocol_borrowers(&self, chain: &str, protocol: Protocol) -> Vec<Address> {
         let chain_lower = chain.to_lowercase();
-        let entry = self.aave.entry(chain_lower.clone()).or_default();
+        let mut seen = HashSet::new();
         
-        let existing: HashSet<Address> = entry.iter().cloned().collect();
+        self.sets
+            .iter()
+            .filter(|(source, _)| source.chain == chain_lower && source.protocol == protocol)
+            .flat_map(|(_, set)| set.borrowers.iter().copied())
+            .filter(|b| seen.insert(*b))
+            .collect()
+    }
+    
+    /// Add discovered borrowers to a source
+    pub fn add_borrowers(&mut self, source: &BorrowerSource, new_borrowers: Vec<Address>) -> usize {
+        let entry = self.sets.entry(source.clone()).or_default();
+        
+        let mut existing: HashSet<Address> = entry.borrowers.iter().cloned().collect();
         let mut added = 0;
         
         for borrower in new_borrowers {
-            if !existing.contains(&borrower) {
-                entry.push(borrower);
+            if existing.insert(borrower) {
+                entry.borrowers.push(borrower);
                 added += 1;
             }
         }
@@ -120,10 +223,51 @@ impl BorrowerStore {
         added
     }
     
+    /// Last block discovery has covered for a source (0 if never scanned)
+    pub fn last_scanned_block(&self, source: &BorrowerSource) -> u64 {
+        self.sets.get(source).map(|s| s.last_scanned_block).unwrap_or(0)
+    }
+    
+    /// Record discovery progress for a source
+    pub fn set_last_scanned_block(&mut self, source: &BorrowerSource, block: u64) {
+        self.sets.entry(source.clone()).or_default().last_scanned_block = block;
+    }
+    
+    /// Count borrowers per source on a chain
+    pub fn chain_counts(&self, chain: &str) -> Vec<(BorrowerSource, usize)> {
+        let chain_lower = chain.to_lowercase();
+        let mut counts: Vec<(BorrowerSource, usize)> = self.sets
+            .iter()
+            .filter(|(source, _)| source.chain == chain_lower)
+            .map(|(source, set)| (source.clone(), set.borrowers.len()))
+            .collect();
+        counts.sort_by_key(|(source, _)| (source.protocol.to_string(), source.market));
+        counts
+    }
+    
     /// Get total count
     pub fn total_count(&self) -> usize {
-        self.aave.values().map(|v| v.len()).sum()
+        self.sets.values().map(|s| s.borrowers.len()).sum()
+    }
+}
+
+/// Borrower sources for every lending market deployed on a chain
+pub fn chain_sources(config: &ChainConfig) -> Vec<BorrowerSource> {
+    let mut sources = Vec::new();
+    
+    for market in &config.aave_markets {
+        sources.push(BorrowerSource::new(&config.name, Protocol::Aave, market.pool));
     }
+    
+    for market in &config.comet_markets {
+        sources.push(BorrowerSource::new(&config.name, Protocol::Compound, market.address));
+    }
+    
+    if let Some(venus) = &config.venus {
+        sources.push(BorrowerSource::new(&config.name, Protocol::Venus, venus.comptroller));
+    }
+    
+    sources
 }
 
 /// Discover borrowers from Aave Borrow events
@@ -187,78 +331,66 @@ pub async fn discover_aave_borrowers(
     Ok(borrowers)
 }
 
-/// Run initial borrower discovery for all chains
-pub async fn discover_all_borrowers(
-    providers: &HashMap<String, (Provider<Http>, Address)>,
-    store: &mut BorrowerStore,
-    blocks_back: u64,
-) -> anyhow::Result<()> {
-    info!("🔍 Starting borrower discovery ({} blocks back)...", blocks_back);
-    
-    for (chain, (provider, pool_address)) in providers {
-        let current_block = match provider.get_block_number().await {
-            Ok(b) => b.as_u64(),
-            Err(e) => {
-                error!("{}: Failed to get block number - {}", chain, e);
-                continue;
-            }
-        };
-        
-        let from_block = current_block.saturating_sub(blocks_back);
-        
-        match discover_aave_borrowers(
-            provider,
-            *pool_address,
-            chain,
-            from_block,
-            Some(current_block),
-        ).await {
-            Ok(borrowers) => {
-                let added = store.add_borrowers(chain, borrowers);
-                info!("{}: Added {} new borrowers (total: {})", 
-                    chain, added, store.get_aave_borrowers(chain).len());
-                store.last_scanned_block.insert(chain.clone(), current_block);
-            }
-            Err(e) => {
-                error!("{}: Discovery failed - {}", chain, e);
-            }
+/// Discover borrowers of a single source over a block range
+pub async fn discover_source_borrowers(
+    provider: &Provider<Http>,
+    source: &BorrowerSource,
+    from_block: u64,
+    to_block: u64,
+) -> anyhow::Result<Vec<Address>> {
+    match source.protocol {
+        Protocol::Aave => {
+            discover_aave_borrowers(provider, source.market, &source.chain, from_block, Some(to_block)).await
+        }
+        Protocol::Compound => {
+            discover_compound_borrowers(provider, source.market, &source.chain, from_block, Some(to_block)).await
+        }
+        Protocol::Venus => {
+            discover_venus_borrowers(provider, source.market, from_block, Some(to_block)).await
         }
     }
-    
-    info!("✅ Discovery complete: {} total borrowers", store.total_count());
-    Ok(())
 }
 
-/// Incremental borrower discovery (since last scan)
+/// Incremental borrower discovery for one source (since its last scan).
+///
+/// Sources that were never scanned start `initial_blocks` behind the head.
 pub async fn update_borrowers(
     provider: &Provider<Http>,
-    pool_address: Address,
-    chain: &str,
+    source: &BorrowerSource,
     store: &mut BorrowerStore,
+    initial_blocks: u64,
 ) -> anyhow::Result<usize> {
     let current_block = provider.get_block_number().await?.as_u64();
-    let last_block = store.last_scanned_block.get(chain).copied().unwrap_or(0);
+    let last_block = store.last_scanned_block(source);
     
     if last_block >= current_block {
         return Ok(0);
     }
     
-    let borrowers = discover_aave_borrowers(
+    let from_block = if last_block == 0 {
+        current_block.saturating_sub(initial_blocks)
+    } else {
+        last_block + 1
+    };
+    
+    debug!("{}: Discovering borrowers from block {} to {}", source, from_block, current_block);
+    
+    let borrowers = discover_source_borrowers(
         provider,
-        pool_address,
-        chain,
-        last_block + 1,
-        Some(current_block),
+        source,
+        from_block,
+        current_block,
     ).await?;
     
-    let added = store.add_borrowers(chain, borrowers);
-    store.last_scanned_block.insert(chain.to_string(), current_block);
+    let added = store.add_borrowers(source, borrowers);
+    store.set_last_scanned_block(source, current_block);
     
     Ok(added)
 }
 
 fn normalize_chain_name(chain: &str) -> &str {
     match chain {
+        "ethereum" | "eth" | "mainnet" => "ethereum",
         "base" => "base",
         "polygon" => "polygon",
         "arbitrum" => "arbitrum",
@@ -267,11 +399,3 @@ fn normalize_chain_name(chain: &str) -> &str {
         other => other,
     }
 }
-
-fn capitalize_chain_name(chain: &str) -> String {
-    let mut chars = chain.chars();
-    match chars.next() {
-        None => String::new(),
-        Some(c) => c.to_uppercase().collect::<String>() + chars.as_str(),
-    }
-}
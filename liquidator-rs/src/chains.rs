@@ -9,7 +9,7 @@ use ethers::prelude::*;
 use ethers::providers::{Provider, Http, Middleware};
 use ethers::signers::LocalWallet;
 use ethers::types::Address;
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 use parking_lot::RwLock;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, AtomicU64, Ordering};
@@ -18,11 +18,30 @@ use tokio::time::interval;
 use tracing::{info, warn, error, debug};
 
 use crate::config::{Config, ChainConfig};
+use crate::flashloan::FlashLenders;
+use crate::nonce::{self, NonceManager};
+use crate::relays::RelaySet;
+use crate::revert::SimulationRevert;
 use crate::types::*;
 
-/// Multicall3 address (same on all chains)
+/// Canonical Multicall3 address (zkSync Era has its own)
 pub const MULTICALL3: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
 
+/// How long a streamed native token price is trusted for gas costs
+const NATIVE_PRICE_MAX_AGE_MS: u64 = 5 * 60 * 1000;
+
+/// Oracle updates unmined for longer than this are not worth backrunning
+const ORACLE_UPDATE_MAX_AGE: Duration = Duration::from_secs(30);
+
+// Chainlink aggregator, read directly when the streamed price is stale
+abigen!(
+    IChainlinkAggregator,
+    r#"[
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+        function decimals() external view returns (uint8)
+    ]"#
+);
+
 /// Single RPC endpoint with health tracking
 pub struct RpcEndpoint {
     pub url: String,
@@ -232,7 +251,15 @@ pub struct ChainState {
     pub multi_rpc: MultiRpcProvider,
     pub wallet: LocalWallet,
     pub prices: DashMap<Address, PriceData>,
-    pub nonce: RwLock<u64>,
+    pub nonces: NonceManager,
+    /// Pending Chainlink updates by feed, for backrun bundles
+    pub oracle_updates: DashMap<Address, PendingOracleUpdate>,
+    /// Private relays and builders with their inclusion record
+    pub relays: RelaySet,
+    /// Flash loan lenders the chain's liquidators can borrow from
+    pub flash: FlashLenders,
+    /// (market, user) of liquidations sent and not yet mined
+    pub in_flight: DashSet<(Address, Address)>,
 }
 
 impl ChainState {
@@ -257,12 +284,19 @@ impl ChainState {
         info!("  ✅ {}: Connected ({} RPCs, nonce: {})", 
             config.name, multi_rpc.endpoints.len(), nonce);
         
+        let relays = RelaySet::new(&config.relays);
+        let flash = FlashLenders::new(&config);
+        
         Ok(Self {
             config,
             multi_rpc,
             wallet,
             prices: DashMap::new(),
-            nonce: RwLock::new(nonce.as_u64()),
+            nonces: NonceManager::new(nonce.as_u64()),
+            oracle_updates: DashMap::new(),
+            relays,
+            flash,
+            in_flight: DashSet::new(),
         })
     }
     
@@ -278,24 +312,6 @@ impl ChainState {
         self.multi_rpc.get_provider()
     }
     
-    /// Get and increment nonce atomically
-    pub fn next_nonce(&self) -> u64 {
-        let mut nonce = self.nonce.write();
-        let current = *nonce;
-        *nonce += 1;
-        current
-    }
-    
-    /// Reset nonce from chain
-    pub async fn sync_nonce(&self) -> anyhow::Result<()> {
-        let on_chain_nonce = self.provider()
-            .get_transaction_count(self.wallet.address(), None)
-            .await?;
-        let mut nonce = self.nonce.write();
-        *nonce = on_chain_nonce.as_u64();
-        Ok(())
-    }
-    
     /// Get cached price or fetch
     pub fn get_price(&self, token: &Address, max_age_ms: u64) -> Option<f64> {
         if let Some(price_data) = self.prices.get(token) {
@@ -306,11 +322,56 @@ impl ChainState {
         None
     }
     
+    /// USD price of the native token for gas costs: the streamed price of
+    /// the wrapped native token, then its Chainlink feed read on-chain, then
+    /// `native_price_fallback`
+    pub async fn native_price_usd(&self) -> f64 {
+        let wrapped = match self.config.wrapped_native {
+            Some(w) => w,
+            None => return self.config.native_price_fallback,
+        };
+        
+        if let Some(price) = self.get_price(&wrapped, NATIVE_PRICE_MAX_AGE_MS) {
+            return price;
+        }
+        
+        let feed = match self.config.chainlink_feeds.iter().find(|f| f.token == wrapped) {
+            Some(f) => f.feed,
+            None => return self.config.native_price_fallback,
+        };
+        
+        let aggregator = IChainlinkAggregator::new(feed, Arc::new(self.provider().clone()));
+        match aggregator.latest_round_data().call().await {
+            Ok((_, answer, _, _, _)) if answer > I256::zero() => {
+                let decimals = aggregator.decimals().call().await.unwrap_or(8);
+                let price = answer.as_u128() as f64 / 10_f64.powi(decimals as i32);
+                self.set_price(wrapped, price);
+                price
+            }
+            _ => {
+                debug!("{}: native price feed unavailable, using fallback", self.config.name);
+                self.config.native_price_fallback
+            }
+        }
+    }
+    
     /// Update price cache
     pub fn set_price(&self, token: Address, price: f64) {
         self.prices.insert(token, PriceData::new(price));
     }
     
+    /// Fresh pending oracle updates for the feeds pricing `tokens`, oldest first
+    pub fn pending_oracle_updates(&self, tokens: &[Address]) -> Vec<PendingOracleUpdate> {
+        let mut updates: Vec<PendingOracleUpdate> = self.config.chainlink_feeds
+            .iter()
+            .filter(|f| tokens.contains(&f.token))
+            .filter_map(|f| self.oracle_updates.get(&f.feed).map(|u| u.clone()))
+            .filter(|u| u.seen_at.elapsed() < ORACLE_UPDATE_MAX_AGE)
+            .collect();
+        updates.sort_by_key(|u| u.seen_at);
+        updates
+    }
+    
     /// Record RPC success
     pub fn record_rpc_success(&self, latency_ms: u64) {
         self.multi_rpc.record_success(latency_ms);
@@ -365,6 +426,12 @@ impl ChainManager {
         self.chains.get(name).map(|c| c.clone())
     }
     
+    /// Whether a liquidation of `pos` was sent and hasn't been mined yet
+    pub fn is_in_flight(&self, pos: &Position) -> bool {
+        self.get_chain(&pos.chain)
+            .is_some_and(|chain| chain.in_flight.contains(&(pos.market, pos.user)))
+    }
+    
     /// Start all monitoring loops
     pub async fn start_monitoring(&self) {
         info!("Starting monitoring loops...");
@@ -381,6 +448,9 @@ impl ChainManager {
         // RPC health check every 30 seconds
         let mut health_interval = interval(Duration::from_secs(30));
         
+        // Pending transaction sweep every 15 seconds
+        let mut pending_interval = interval(Duration::from_secs(15));
+        
         loop {
             if *self.shutdown.read() {
                 break;
@@ -399,6 +469,9 @@ impl ChainManager {
                 _ = health_interval.tick() => {
                     self.check_rpc_health().await;
                 }
+                _ = pending_interval.tick() => {
+                    self.manage_pending_txs().await;
+                }
             }
         }
     }
@@ -411,6 +484,16 @@ impl ChainManager {
         }
     }
     
+    /// Replace stuck transactions and repair nonce gaps on all chains
+    async fn manage_pending_txs(&self) {
+        for chain_ref in self.chains.iter() {
+            let chain = chain_ref.value();
+            if let Err(e) = nonce::manage_pending(chain).await {
+                warn!("{}: pending tx sweep failed: {}", chain.config.name, e);
+            }
+        }
+    }
+    
     /// Scan all chains for liquidatable positions
     async fn background_scan(&self) {
         let mut stats = self.stats.write();
@@ -469,12 +552,13 @@ impl ChainManager {
         let cb_status = if cb.is_open() { " [CIRCUIT OPEN]" } else { "" };
         
         info!(
-            "Events: {} | Checks: {} | Attempted: {} | Success: {} | Failed: {} | Skipped: {} | Competitor: {}{}",
+            "Events: {} | Checks: {} | Attempted: {} | Success: {} | Failed: {} | Reverted: {} | Skipped: {} | Competitor: {}{}",
             stats.events,
             stats.checks,
             stats.attempted,
             stats.liquidations,
             stats.failed,
+            stats.simulation_reverts,
             stats.skipped_unprofitable,
             stats.competitor_beats,
             cb_status
@@ -498,6 +582,16 @@ impl ChainManager {
         self.stats.write().failed += 1;
     }
     
+    /// Record an execution error; simulation reverts never reached the chain,
+    /// so they're counted separately and don't trip the circuit breaker
+    pub fn record_execution_error(&self, error: &anyhow::Error) {
+        if error.downcast_ref::<SimulationRevert>().is_some() {
+            self.stats.write().simulation_reverts += 1;
+        } else {
+            self.record_failure();
+        }
+    }
+    
     /// Graceful shutdown
     pub async fn shutdown(&self) {
         *self.shutdown.write() = true;
@@ -507,7 +601,14 @@ impl ChainManager {
         info!("   Liquidations: {}", stats.liquidations);
         info!("   Attempted: {}", stats.attempted);
         info!("   Failed: {}", stats.failed);
+        info!("   Simulation Reverts: {}", stats.simulation_reverts);
         info!("   Competitor Beats: {}", stats.competitor_beats);
+        info!("   Close Factor (50% / HF<0.95 / small): {} / {} / {}",
+            stats.close_factor_default, stats.close_factor_low_hf, stats.close_factor_small_position);
+        info!("   Batches: {} ({} items failed on-chain)", stats.batches, stats.batch_item_failures);
+        info!("   Cancelled: {}", stats.cancelled);
+        info!("   Dropped Scans: {}", stats.dropped_scans);
+        info!("   Receipt Timeouts: {}", stats.receipt_timeouts);
     }
     
     /// Get health status including RPC status
@@ -520,6 +621,8 @@ impl ChainManager {
             serde_json::json!({
                 "name": chain_ref.key(),
                 "rpcs": chain.multi_rpc.status(),
+                "pending_txs": chain.nonces.pending_count(),
+                "relays": chain.relays.status(),
             })
         }).collect();
         
@@ -532,8 +635,14 @@ impl ChainManager {
                 "liquidations": stats.liquidations,
                 "attempted": stats.attempted,
                 "failed": stats.failed,
+                "simulation_reverts": stats.simulation_reverts,
                 "skipped_unprofitable": stats.skipped_unprofitable,
                 "competitor_beats": stats.competitor_beats,
+                "close_factor": {
+                    "default": stats.close_factor_default,
+                    "low_hf": stats.close_factor_low_hf,
+                    "small_position": stats.close_factor_small_position,
+                },
             },
             "circuit_breaker": {
                 "is_open": cb.is_open(),
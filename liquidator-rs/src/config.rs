@@ -1,8 +1,20 @@
 //! Configuration management for the liquidator bot.
 
+use ethers::types::Address;
 use serde::Deserialize;
+use std::collections::HashSet;
 use std::env;
 use thiserror::Error;
+use tracing::info;
+
+use crate::chains::MULTICALL3;
+use crate::types::Protocol;
+
+/// Default chain registry path (relative to the working directory)
+const DEFAULT_CHAINS_CONFIG: &str = "config/chains.toml";
+
+/// Chain registry compiled into the binary, used when no file is on disk
+const EMBEDDED_CHAINS_CONFIG: &str = include_str!("../config/chains.toml");
 
 #[derive(Error, Debug)]
 pub enum ConfigError {
@@ -21,12 +33,257 @@ pub struct ChainConfig {
     pub name: String,
     pub rpc_url: String,
     pub ws_url: Option<String>,
-    pub pool_address: String,
-    pub data_provider: String,
-    pub liquidator_address: Option<String>,
+    /// Aave V3 markets on the chain, core pool first
+    pub aave_markets: Vec<AaveDeployment>,
+    /// Entry point the chain's Aave flash liquidators expose
+    pub aave_liquidator_abi: LiquidatorAbi,
+    /// Venus deployment, if the chain has one
+    pub venus: Option<VenusDeployment>,
+    pub compound_liquidator_address: Option<String>,
+    pub venus_liquidator_address: Option<String>,
     pub chain_id: u64,
+    /// Gas assumed for a flash liquidation when `eth_estimateGas` can't be used
     pub gas_limit: u64,
+    /// Send EIP-1559 transactions with a bid priority fee instead of legacy gas price
+    pub eip1559: bool,
     pub native_price_fallback: f64,
+    /// Wrapped native token, priced by its Chainlink feed for gas costs
+    pub wrapped_native: Option<Address>,
+    /// How the chain charges for L1 data, if it's a rollup
+    pub l1_fee: Option<L1FeeModel>,
+    /// Private route for liquidations above `mev_threshold_usd`
+    pub mev: MevSubmission,
+    /// Bundle behind Chainlink updates seen in the mempool (bundle route only)
+    pub backrun_oracle_updates: bool,
+    /// Private relays and builders that MEV-protected liquidations fan out to
+    pub relays: Vec<RelayEndpoint>,
+    /// Uniswap V3 QuoterV2 for on-chain swap quotes
+    pub quoter_address: Option<Address>,
+    /// Multicall3 deployment used for batched reads
+    pub multicall: Address,
+    /// Tokens tried as the middle hop of two-hop swap routes
+    pub intermediate_tokens: Vec<Address>,
+    /// Flash loan lenders besides the Aave pools (v2 liquidators only)
+    pub flash_loans: FlashLoanLenders,
+    pub comet_markets: Vec<CometMarket>,
+    pub chainlink_feeds: Vec<PriceFeed>,
+}
+
+impl ChainConfig {
+    /// Lending protocols deployed on this chain
+    pub fn protocols(&self) -> Vec<Protocol> {
+        let mut protocols = Vec::new();
+        if !self.aave_markets.is_empty() {
+            protocols.push(Protocol::Aave);
+        }
+        if !self.comet_markets.is_empty() {
+            protocols.push(Protocol::Compound);
+        }
+        if self.venus.is_some() {
+            protocols.push(Protocol::Venus);
+        }
+        protocols
+    }
+    
+    /// Aave market whose pool is `pool`
+    pub fn aave_market(&self, pool: Address) -> Option<&AaveDeployment> {
+        self.aave_markets.iter().find(|market| market.pool == pool)
+    }
+    
+    /// Flash liquidator of the Aave market whose pool is `pool`
+    pub fn aave_liquidator(&self, pool: Address) -> Option<Address> {
+        self.aave_market(pool)?.liquidator_address.as_ref()?.parse().ok()
+    }
+}
+
+/// Aave V3 pool and its data provider
+#[derive(Clone, Debug, Deserialize)]
+pub struct AaveDeployment {
+    /// Market name ("Core", "Lido", ...), used in logs and env var names
+    #[serde(default = "default_aave_market_name")]
+    pub name: String,
+    pub pool: Address,
+    /// Read from the pool's `PoolAddressesProvider` when not set
+    #[serde(default)]
+    pub data_provider: Option<Address>,
+    /// Flash liquidator bound to this pool, from `{CHAIN}_AAVE_{MARKET}_LIQUIDATOR`
+    #[serde(skip)]
+    pub liquidator_address: Option<String>,
+}
+
+fn default_aave_market_name() -> String {
+    "Core".to_string()
+}
+
+fn default_multicall() -> Address {
+    MULTICALL3.parse().expect("valid Multicall3 address")
+}
+
+/// Aave flash liquidator entry point, from `{CHAIN}_AAVE_LIQUIDATOR_ABI`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LiquidatorAbi {
+    /// `executeLiquidation(user, collateral, debt, debtToCover)`; the
+    /// contract picks its own swap
+    #[default]
+    V1,
+    /// `executeLiquidationWithRoute` (contracts/FlashLiquidatorV2.sol), which
+    /// executes the quoted swap route behind a `minProfit` guard
+    V2,
+}
+
+/// Flash loan lenders on a chain, used when cheaper than the Aave pool
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct FlashLoanLenders {
+    /// Balancer V2 vault
+    #[serde(default)]
+    pub balancer_vault: Option<Address>,
+    /// Morpho Blue
+    #[serde(default)]
+    pub morpho: Option<Address>,
+    /// Uniswap V3 factory, to find pools holding the debt asset
+    #[serde(default)]
+    pub uniswap_v3_factory: Option<Address>,
+}
+
+/// Venus Comptroller (Compound V2 style)
+#[derive(Clone, Debug, Deserialize)]
+pub struct VenusDeployment {
+    pub comptroller: Address,
+}
+
+/// Compound V3 Comet market
+#[derive(Clone, Debug, Deserialize)]
+pub struct CometMarket {
+    pub name: String,
+    pub base_token: String,
+    pub address: Address,
+}
+
+/// Rollup L1 data fee model
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum L1FeeModel {
+    /// OP-stack `GasPriceOracle.getL1Fee` (Base, Optimism, Metis)
+    OpStack,
+    /// Scroll `L1GasPriceOracle.getL1Fee`
+    Scroll,
+    /// Arbitrum `NodeInterface.gasEstimateL1Component`
+    Arbitrum,
+    /// Linea's `linea_estimateGas` priority fee, which covers L1 data
+    Linea,
+    /// zkSync Era pubdata, charged in L2 gas by `eth_estimateGas`
+    #[serde(rename = "zksync")]
+    ZkSync,
+}
+
+/// Private submission route for MEV-protected liquidations
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MevSubmission {
+    /// Single private transaction through the chain's `private_tx` relays
+    #[default]
+    Protect,
+    /// `eth_sendBundle` to the chain's `bundle` relays over several target blocks
+    Bundle,
+}
+
+impl MevSubmission {
+    /// Relays this route submits through
+    pub fn relay_kind(&self) -> RelayKind {
+        match self {
+            MevSubmission::Protect => RelayKind::PrivateTx,
+            MevSubmission::Bundle => RelayKind::Bundle,
+        }
+    }
+}
+
+/// What a relay endpoint accepts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayKind {
+    /// Private `eth_sendRawTransaction` (Flashbots Protect, MEV Blocker, 48Club)
+    PrivateTx,
+    /// `eth_sendBundle` builder or relay (Flashbots, Titan, beaverbuild, bloXroute)
+    Bundle,
+}
+
+/// Private orderflow relay or block builder endpoint
+#[derive(Clone, Debug, Deserialize)]
+pub struct RelayEndpoint {
+    pub name: String,
+    pub url: String,
+    pub kind: RelayKind,
+    /// Sign requests with the Flashbots `X-Flashbots-Signature` header
+    #[serde(default)]
+    pub flashbots_auth: bool,
+    /// Fee recipient of the builder's blocks, to credit inclusions to it
+    #[serde(default)]
+    pub builder: Option<Address>,
+    /// `Authorization` header from `{CHAIN}_{RELAY}_AUTH`
+    #[serde(skip)]
+    pub auth: Option<String>,
+}
+
+/// Chainlink price feed for a token
+#[derive(Clone, Debug, Deserialize)]
+pub struct PriceFeed {
+    pub token: Address,
+    pub feed: Address,
+}
+
+/// Chain entry in the config file (public on-chain data only)
+#[derive(Clone, Debug, Deserialize)]
+struct ChainFileEntry {
+    name: String,
+    chain_id: u64,
+    gas_limit: u64,
+    #[serde(default)]
+    eip1559: bool,
+    native_price_fallback: f64,
+    #[serde(default)]
+    wrapped_native: Option<Address>,
+    #[serde(default)]
+    l1_fee: Option<L1FeeModel>,
+    #[serde(default)]
+    mev: MevSubmission,
+    #[serde(default)]
+    backrun_oracle_updates: bool,
+    #[serde(default)]
+    relays: Vec<RelayEndpoint>,
+    #[serde(default)]
+    aave: Option<AaveDeployment>,
+    #[serde(default)]
+    aave_markets: Vec<AaveDeployment>,
+    #[serde(default)]
+    venus: Option<VenusDeployment>,
+    #[serde(default)]
+    quoter: Option<Address>,
+    #[serde(default = "default_multicall")]
+    multicall: Address,
+    #[serde(default)]
+    intermediate_tokens: Vec<Address>,
+    #[serde(default)]
+    flash_loans: FlashLoanLenders,
+    #[serde(default)]
+    comet_markets: Vec<CometMarket>,
+    #[serde(default)]
+    chainlink_feeds: Vec<PriceFeed>,
+}
+
+/// Bot-wide thresholds in the config file
+#[derive(Clone, Debug, Default, Deserialize)]
+struct ThresholdsFile {
+    min_profit_usd: Option<f64>,
+    mev_threshold_usd: Option<f64>,
+    price_cache_ms: Option<u64>,
+    gas_profit_share: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    thresholds: ThresholdsFile,
+    chains: Vec<ChainFileEntry>,
 }
 
 #[derive(Clone, Debug)]
@@ -38,6 +295,12 @@ pub struct Config {
     pub min_profit_usd: f64,
     pub mev_threshold_usd: f64,
     pub price_cache_ms: u64,
+    /// Share of expected profit we're willing to bid on gas (0-1)
+    pub gas_profit_share: f64,
+    /// Liquidate with wallet or contract inventory instead of a flash loan when it covers the debt
+    pub self_funded: bool,
+    /// Keep seized Aave collateral as aTokens in self-funded liquidations
+    pub receive_a_token: bool,
     pub owner_wallet: String,
     pub chains: Vec<ChainConfig>,
 }
@@ -59,100 +322,118 @@ impl Config {
         // Optional with defaults
         let discord_webhook = env::var("DISCORD_WEBHOOK").ok();
         let dry_run = env::var("DRY_RUN").map(|v| v == "true").unwrap_or(false);
+        let self_funded = env::var("SELF_FUNDED").map(|v| v == "true").unwrap_or(false);
+        let receive_a_token = env::var("RECEIVE_ATOKEN").map(|v| v == "true").unwrap_or(false);
         let health_port = env::var("HEALTH_PORT")
             .ok()
             .and_then(|p| p.parse().ok())
             .unwrap_or(3847);
+        
+        // Thresholds: env overrides the config file
+        let file = load_config_file()?;
         let min_profit_usd = env::var("MIN_PROFIT_USD")
             .ok()
             .and_then(|p| p.parse().ok())
+            .or(file.thresholds.min_profit_usd)
             .unwrap_or(5.0);
         let mev_threshold_usd = env::var("MEV_THRESHOLD_USD")
             .ok()
             .and_then(|p| p.parse().ok())
+            .or(file.thresholds.mev_threshold_usd)
             .unwrap_or(500.0);
         let price_cache_ms = env::var("PRICE_CACHE_MS")
             .ok()
             .and_then(|p| p.parse().ok())
+            .or(file.thresholds.price_cache_ms)
             .unwrap_or(10000);
+        let gas_profit_share = env::var("GAS_PROFIT_SHARE")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .or(file.thresholds.gas_profit_share)
+            .unwrap_or(0.5_f64)
+            .clamp(0.0, 1.0);
         let owner_wallet = env::var("OWNER_WALLET")
             .unwrap_or_else(|_| "0x55F5F2186f907057EB40a9EFEa99A0A41BcbB885".to_string());
         
-        // Build chain configs
+        // Build chain configs from the registry, enabling chains with an RPC
         let mut chains = Vec::new();
         
-        // Base
-        if let Ok(rpc) = env::var("BASE_RPC_URL") {
-            chains.push(ChainConfig {
-                name: "base".to_string(),
-                rpc_url: rpc,
-                ws_url: env::var("BASE_WS_URL").ok(),
-                pool_address: "0xA238Dd80C259a72e81d7e4664a9801593F98d1c5".to_string(),
-                data_provider: "0x2d8A3C5677189723C4cB8873CfC9C8976FDF38Ac".to_string(),
-                liquidator_address: env::var("BASE_LIQUIDATOR").ok(),
-                chain_id: 8453,
-                gas_limit: 800_000,
-                native_price_fallback: 3000.0,
-            });
-        }
-        
-        // Polygon
-        if let Ok(rpc) = env::var("POLYGON_RPC_URL") {
-            chains.push(ChainConfig {
-                name: "polygon".to_string(),
-                rpc_url: rpc,
-                ws_url: env::var("POLYGON_WS_URL").ok(),
-                pool_address: "0x794a61358D6845594F94dc1DB02A252b5b4814aD".to_string(),
-                data_provider: "0x69FA688f1Dc47d4B5d8029D5a35FB7a548310654".to_string(),
-                liquidator_address: env::var("POLYGON_LIQUIDATOR").ok(),
-                chain_id: 137,
-                gas_limit: 800_000,
-                native_price_fallback: 0.5,
-            });
-        }
-        
-        // Arbitrum
-        if let Ok(rpc) = env::var("ARBITRUM_RPC_URL") {
-            chains.push(ChainConfig {
-                name: "arbitrum".to_string(),
-                rpc_url: rpc,
-                ws_url: env::var("ARBITRUM_WS_URL").ok(),
-                pool_address: "0x794a61358D6845594F94dc1DB02A252b5b4814aD".to_string(),
-                data_provider: "0x69FA688f1Dc47d4B5d8029D5a35FB7a548310654".to_string(),
-                liquidator_address: env::var("ARBITRUM_LIQUIDATOR").ok(),
-                chain_id: 42161,
-                gas_limit: 1_500_000,
-                native_price_fallback: 3000.0,
-            });
-        }
-        
-        // Avalanche
-        if let Ok(rpc) = env::var("AVALANCHE_RPC_URL") {
+        for entry in file.chains {
+            let prefix = entry.name.to_uppercase();
+            let rpc = match env::var(format!("{}_RPC_URL", prefix)) {
+                Ok(rpc) => rpc,
+                Err(_) => continue,
+            };
+            
+            let legacy_liquidator = env::var(format!("{}_LIQUIDATOR", prefix)).ok();
+            let aave_liquidator = env::var(format!("{}_AAVE_LIQUIDATOR", prefix)).ok();
+            let venus_liquidator = env::var(format!("{}_VENUS_LIQUIDATOR", prefix)).ok();
+            
+            // On Venus chains {CHAIN}_LIQUIDATOR historically points at the
+            // Venus flash liquidator, so Aave needs {CHAIN}_AAVE_LIQUIDATOR there
+            let (core_liquidator, venus_liquidator_address) = if entry.venus.is_some() {
+                (aave_liquidator, venus_liquidator.or(legacy_liquidator))
+            } else {
+                (aave_liquidator.or(legacy_liquidator), venus_liquidator)
+            };
+            
+            // Flash liquidators are bound to one pool, so each market has its
+            // own; the unsuffixed variables above are the first market's
+            let aave_markets = entry.aave
+                .into_iter()
+                .chain(entry.aave_markets)
+                .enumerate()
+                .map(|(i, market)| {
+                    let var = format!("{}_AAVE_{}_LIQUIDATOR", prefix, market.name.to_uppercase());
+                    let fallback = if i == 0 { core_liquidator.clone() } else { None };
+                    AaveDeployment {
+                        liquidator_address: env::var(var).ok().or(fallback),
+                        ..market
+                    }
+                })
+                .collect();
+            
+            let abi_var = format!("{}_AAVE_LIQUIDATOR_ABI", prefix);
+            let aave_liquidator_abi = match env::var(&abi_var).ok().as_deref() {
+                None | Some("v1") => LiquidatorAbi::V1,
+                Some("v2") => LiquidatorAbi::V2,
+                Some(other) => {
+                    return Err(ConfigError::ParseError(format!("{}: unknown ABI '{}'", abi_var, other)));
+                }
+            };
+            
+            let relays = entry.relays
+                .into_iter()
+                .map(|relay| RelayEndpoint {
+                    auth: env::var(format!("{}_{}_AUTH", prefix, relay.name.to_uppercase())).ok(),
+                    ..relay
+                })
+                .collect();
+            
             chains.push(ChainConfig {
-                name: "avalanche".to_string(),
                 rpc_url: rpc,
-                ws_url: env::var("AVALANCHE_WS_URL").ok(),
-                pool_address: "0x794a61358D6845594F94dc1DB02A252b5b4814aD".to_string(),
-                data_provider: "0x69FA688f1Dc47d4B5d8029D5a35FB7a548310654".to_string(),
-                liquidator_address: env::var("AVALANCHE_LIQUIDATOR").ok(),
-                chain_id: 43114,
-                gas_limit: 800_000,
-                native_price_fallback: 35.0,
-            });
-        }
-        
-        // BNB Chain
-        if let Ok(rpc) = env::var("BNB_RPC_URL") {
-            chains.push(ChainConfig {
-                name: "bnb".to_string(),
-                rpc_url: rpc,
-                ws_url: env::var("BNB_WS_URL").ok(),
-                pool_address: "0xfD36E2c2a6789Db23113685031d7F16329158384".to_string(), // Venus Comptroller
-                data_provider: "".to_string(),
-                liquidator_address: env::var("BNB_LIQUIDATOR").ok(),
-                chain_id: 56,
-                gas_limit: 1_500_000,
-                native_price_fallback: 600.0,
+                ws_url: env::var(format!("{}_WS_URL", prefix)).ok(),
+                compound_liquidator_address: env::var(format!("{}_COMPOUND_LIQUIDATOR", prefix)).ok(),
+                venus_liquidator_address,
+                name: entry.name,
+                aave_markets,
+                aave_liquidator_abi,
+                venus: entry.venus,
+                chain_id: entry.chain_id,
+                gas_limit: entry.gas_limit,
+                eip1559: entry.eip1559,
+                native_price_fallback: entry.native_price_fallback,
+                wrapped_native: entry.wrapped_native,
+                l1_fee: entry.l1_fee,
+                mev: entry.mev,
+                backrun_oracle_updates: entry.backrun_oracle_updates,
+                relays,
+                quoter_address: entry.quoter,
+                multicall: entry.multicall,
+                intermediate_tokens: entry.intermediate_tokens,
+                flash_loans: entry.flash_loans,
+                comet_markets: entry.comet_markets,
+                chainlink_feeds: entry.chainlink_feeds,
             });
         }
         
@@ -164,8 +445,52 @@ impl Config {
             min_profit_usd,
             mev_threshold_usd,
             price_cache_ms,
+            gas_profit_share,
+            self_funded,
+            receive_a_token,
             owner_wallet,
             chains,
         })
     }
 }
+
+/// Read the chain registry from `CHAINS_CONFIG`, `config/chains.toml` or the embedded copy
+fn load_config_file() -> Result<ConfigFile, ConfigError> {
+    let explicit_path = env::var("CHAINS_CONFIG").ok();
+    let path = explicit_path.clone().unwrap_or_else(|| DEFAULT_CHAINS_CONFIG.to_string());
+    
+    let content = match std::fs::read_to_string(&path) {
+        Ok(c) => {
+            info!("Chain registry loaded from {}", path);
+            c
+        }
+        Err(e) if explicit_path.is_some() => {
+            return Err(ConfigError::ParseError(format!("{}: {}", path, e)));
+        }
+        Err(_) => {
+            info!("Chain registry loaded from embedded defaults");
+            EMBEDDED_CHAINS_CONFIG.to_string()
+        }
+    };
+    
+    let file: ConfigFile = toml::from_str(&content)
+        .map_err(|e| ConfigError::ParseError(format!("{}: {}", path, e)))?;
+    
+    let mut names = HashSet::new();
+    for chain in &file.chains {
+        if !names.insert(chain.name.as_str()) {
+            return Err(ConfigError::ParseError(format!("duplicate chain '{}'", chain.name)));
+        }
+        
+        let mut markets = HashSet::new();
+        for market in chain.aave.iter().chain(&chain.aave_markets) {
+            if !markets.insert(market.name.to_lowercase()) {
+                return Err(ConfigError::ParseError(format!(
+                    "duplicate Aave market '{}' on {}", market.name, chain.name
+                )));
+            }
+        }
+    }
+    
+    Ok(file)
+}
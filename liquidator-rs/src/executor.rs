@@ -1,39 +1,38 @@
-//! Transaction execution for liquidations with Flashbots MEV protection.
+//! Transaction execution for liquidations, with MEV protection through
+//! private relays and builder bundles.
 
 use ethers::prelude::*;
-use ethers::providers::{Provider, Http, Middleware};
+use ethers::providers::{Middleware, RpcError};
 use ethers::signers::Signer;
 use ethers::types::{Address, U256, Bytes, TransactionRequest, H256};
 use ethers::types::transaction::eip2718::TypedTransaction;
 use ethers::utils::keccak256;
+use futures::future::join_all;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::{info, warn, error};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn, error};
 
-use crate::types::{Position, Protocol};
+use crate::types::Position;
 use crate::chains::ChainState;
+use crate::config::{LiquidatorAbi, MevSubmission, RelayKind};
+use crate::relays::{self, Relay};
+use crate::receipts::{self, BundleTargets, ReceiptTracker, TrackedTx, TxPurpose};
+use crate::gas::{self, L1Cost};
+use crate::revert::{decode_revert_reason, SimulationRevert};
+use crate::protocols::aave::IAavePool;
+use crate::protocols::compound::IComet;
+use crate::protocols::venus::{IVToken, VenusMarket};
+use crate::flashloan::FlashLoan;
+use crate::inventory::{self, Funding};
+use crate::simulator::u256_to_f64;
+use crate::swap::SwapRoute;
 
-/// Flashbots RPC endpoints by chain
-pub fn get_flashbots_rpc(chain: &str) -> Option<&'static str> {
-    match chain {
-        "ethereum" => Some("https://relay.flashbots.net"),
-        "base" => Some("https://rpc.flashbots.net/fast"),
-        _ => None,
-    }
-}
-
-/// Flashbots Protect RPC (simpler, no bundle signing needed)
-pub fn get_flashbots_protect_rpc(chain: &str) -> Option<&'static str> {
-    match chain {
-        "ethereum" => Some("https://rpc.flashbots.net"),
-        "base" => Some("https://rpc.flashbots.net/fast"),
-        _ => None,
-    }
-}
-
-// Liquidator contract ABI for flash loan liquidations
-// Matches deployed contract at 0x163A862679E73329eA835aC302E54aCBee7A58B1
+// Aave flash liquidator ABI shared by both versions (see `LiquidatorAbi`).
+// v1 contracts only take `executeLiquidation` and pick their own swap;
+// FlashLiquidatorV2's route, inventory and batch entry points are encoded
+// by hand in the `build_*_calldata` functions below.
 abigen!(
     IFlashLiquidator,
     r#"[
@@ -46,332 +45,1057 @@ abigen!(
     ]"#
 );
 
-/// Flashbots bundle request
-#[derive(Debug, Serialize)]
-struct FlashbotsBundle {
-    jsonrpc: &'static str,
-    id: u64,
-    method: &'static str,
-    params: Vec<FlashbotsBundleParams>,
-}
+// Compound V3 flash liquidator (contracts/CompoundV3FlashLiquidator.sol)
+// Flash loans the base token, buys absorbed collateral and swaps it back
+abigen!(
+    ICompoundFlashLiquidator,
+    r#"[
+        function executeLiquidation(address comet, address borrower, address collateralAsset, uint256 baseAmount) external
+        function supportedComets(address comet) external view returns (bool)
+    ]"#
+);
+
+// Venus flash liquidator (contracts/BNBFlashLiquidator.sol)
+// Flash loans the repay asset, liquidates, redeems seized vTokens and swaps back
+abigen!(
+    IVenusFlashLiquidator,
+    r#"[
+        function executeLiquidation(address debtAsset, uint256 debtAmount, address vTokenBorrowed, address vTokenCollateral, address borrower) external
+    ]"#
+);
+
+/// How long the chain's executor waits on a prerequisite transaction before
+/// moving on and leaving it to the receipt tracker. Kept to a couple of blocks
+/// so a stuck approval or absorb can't stall the chain's other liquidations.
+const PREREQUISITE_TX_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How often a prerequisite transaction is checked for inclusion
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Consecutive blocks a bundle is submitted for
+const BUNDLE_TARGET_BLOCKS: u64 = 3;
+
+/// Most Aave liquidations sharing one flash loan and swap
+pub const MAX_AAVE_BATCH_SIZE: usize = 8;
+
+/// Gas assumed per batched liquidation when the batch can't be estimated
+const BATCH_ITEM_GAS: u64 = 400_000;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct FlashbotsBundleParams {
     txs: Vec<String>,           // Signed transaction hex strings
     block_number: String,       // Target block (hex)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    min_timestamp: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    max_timestamp: Option<u64>,
 }
 
-/// Flashbots bundle response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CallBundleParams {
+    txs: Vec<String>,
+    block_number: String,
+    state_block_number: &'static str,
+}
+
 #[derive(Debug, Deserialize)]
-struct FlashbotsResponse {
-    #[serde(default)]
-    result: Option<FlashbotsResult>,
+#[serde(rename_all = "camelCase")]
+struct SendBundleResult {
     #[serde(default)]
-    error: Option<FlashbotsError>,
+    bundle_hash: Option<String>,
 }
 
+/// `eth_callBundle` result
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct FlashbotsResult {
-    bundle_hash: Option<String>,
+struct CallBundleResult {
+    results: Vec<CallBundleTx>,
+    #[serde(default)]
+    total_gas_used: u64,
 }
 
 #[derive(Debug, Deserialize)]
-struct FlashbotsError {
-    message: String,
+struct CallBundleTx {
     #[serde(default)]
-    code: i64,
+    error: Option<String>,
+    #[serde(default)]
+    revert: Option<String>,
+}
+
+/// How a liquidation transaction reaches a block builder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubmitRoute {
+    /// Public mempool
+    Public,
+    /// Private transaction fanned out to the chain's private relays
+    Protect,
+    /// Bundle fanned out to the chain's builders, behind any pending oracle
+    /// updates for `backrun` tokens
+    Bundle { backrun: Vec<Address> },
+}
+
+/// Gas price limits for one liquidation (wei per gas)
+#[derive(Debug, Clone, Copy)]
+pub struct GasCeiling {
+    /// Above this the liquidation no longer clears `min_profit_usd`
+    pub break_even: U256,
+    /// Most we bid: `gas_profit_share` of expected profit, within break-even
+    pub max_bid: U256,
+}
+
+/// One Aave liquidation pair, its amount and how the seized collateral is sold
+#[derive(Debug, Clone)]
+pub struct AaveLiquidation {
+    pub collateral_asset: Address,
+    pub debt_asset: Address,
+    pub debt_to_cover: U256,
+    /// Quoted swap route (v2 liquidators only)
+    pub route: SwapRoute,
+    /// Debt asset left over after repaying the flash loan, below which a v2
+    /// liquidator reverts
+    pub min_profit: U256,
+    /// Where the debt is borrowed from (v1 liquidators always use their pool)
+    pub flash: FlashLoan,
+    /// Flash loan, or inventory already holding the debt asset
+    pub funding: Funding,
+}
+
+/// One position of a batched Aave liquidation
+#[derive(Debug, Clone, Copy)]
+pub struct BatchItem {
+    pub user: Address,
+    pub debt_to_cover: U256,
+}
+
+/// Aave liquidations of one (collateral, debt) pair on one pool, repaid with
+/// a single flash loan of their summed debt and sold in a single swap
+/// (`executeBatchLiquidation`, v2 liquidators only)
+#[derive(Debug, Clone)]
+pub struct AaveBatch {
+    pub collateral_asset: Address,
+    pub debt_asset: Address,
+    pub items: Vec<BatchItem>,
+    /// Quoted route for the items' summed collateral
+    pub route: SwapRoute,
+    /// Surplus below which the batch reverts, scaled on-chain to the debt
+    /// actually repaid when items fail
+    pub min_profit: U256,
+    pub flash: FlashLoan,
+    /// Debt asset decimals and USD price, for per-position profit reports
+    pub debt_decimals: u8,
+    pub debt_price_usd: f64,
+}
+
+impl AaveBatch {
+    /// Summed debt of every item: the flash loan amount
+    pub fn debt_to_cover(&self) -> U256 {
+        self.items.iter().fold(U256::zero(), |sum, item| sum + item.debt_to_cover)
+    }
+}
+
+/// What one batch item did on-chain
+#[derive(Debug, Clone)]
+pub enum BatchItemResult {
+    Liquidated { debt_repaid: U256, collateral_seized: U256 },
+    /// The item's `liquidationCall` reverted; the rest of the batch went on
+    Failed { reason: String },
+}
+
+/// Per-item results of a landed batch, from its receipt's events
+#[derive(Debug, Clone)]
+pub struct BatchOutcome {
+    pub items: Vec<(Address, BatchItemResult)>,
+    /// Debt repaid across liquidated items
+    pub debt_repaid: U256,
+    /// Debt asset left after repaying the flash loan
+    pub profit: U256,
+}
+
+impl BatchOutcome {
+    /// Share of `profit` earned by an item that repaid `debt_repaid`
+    pub fn attributed_profit(&self, debt_repaid: U256) -> U256 {
+        if self.debt_repaid.is_zero() {
+            return U256::zero();
+        }
+        self.profit * debt_repaid / self.debt_repaid
+    }
+}
+
+/// Split liquidations into batches that can share one flash loan and swap:
+/// same (collateral, debt) pair, different assets, at most
+/// [`MAX_AAVE_BATCH_SIZE`] each. Pairs with a single liquidation come back
+/// as their own one-item group.
+pub fn group_aave_batches<T>(items: Vec<T>, pair: impl Fn(&T) -> (Address, Address)) -> Vec<Vec<T>> {
+    let mut groups: Vec<((Address, Address), Vec<T>)> = Vec::new();
+    for item in items {
+        let key = pair(&item);
+        match groups.iter_mut().find(|(k, group)| *k == key && group.len() < MAX_AAVE_BATCH_SIZE && key.0 != key.1) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((key, vec![item])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
 }
 
 pub struct Executor {
     pub dry_run: bool,
     pub min_profit_usd: f64,
     pub mev_threshold_usd: f64,
+    /// Share of expected profit we're willing to spend on gas
+    pub gas_profit_share: f64,
+    /// Liquidate from inventory instead of a flash loan when it covers the debt
+    pub self_funded: bool,
+    /// Keep seized Aave collateral as aTokens when self-funded
+    pub receive_a_token: bool,
+    /// Follows sent liquidations so execution doesn't wait on receipts
+    receipts: ReceiptTracker,
     http_client: Client,
 }
 
 impl Executor {
-    pub fn new(dry_run: bool, min_profit_usd: f64, mev_threshold_usd: f64) -> Self {
+    pub fn new(
+        dry_run: bool,
+        min_profit_usd: f64,
+        mev_threshold_usd: f64,
+        gas_profit_share: f64,
+        self_funded: bool,
+        receive_a_token: bool,
+        receipts: ReceiptTracker,
+    ) -> Self {
         Self {
             dry_run,
             min_profit_usd,
             mev_threshold_usd,
+            gas_profit_share,
+            self_funded,
+            receive_a_token,
+            receipts,
             http_client: Client::new(),
         }
     }
     
+    /// How to fund repaying `amount` of `asset`: a flash loan unless self-funded
+    /// and `contract` (a v2 liquidator) or the wallet already holds enough
+    pub async fn funding(
+        &self,
+        chain: &ChainState,
+        asset: Address,
+        amount: U256,
+        contract: Option<Address>,
+    ) -> Funding {
+        if !self.self_funded {
+            return Funding::FlashLoan;
+        }
+        inventory::funding_for(chain.provider(), chain.wallet.address(), contract, asset, amount).await
+    }
+    
+    /// Approve `spender` to pull the wallet's `token` unless it already may
+    /// pull `amount`. False when the approval wasn't sent.
+    async fn ensure_allowance(
+        &self,
+        chain: &ChainState,
+        token: Address,
+        spender: Address,
+        amount: U256,
+        expected_profit_usd: f64,
+    ) -> anyhow::Result<bool> {
+        let allowance = inventory::allowance(chain.provider(), token, chain.wallet.address(), spender).await?;
+        if allowance >= amount {
+            return Ok(true);
+        }
+        
+        info!("🔓 Approving {:?} to spend wallet {:?}", spender, token);
+        let tx_data = inventory::approve_max_calldata(chain.provider(), token, spender)?;
+        let sent = self.submit_call(
+            chain,
+            (token, tx_data),
+            100_000,
+            SubmitRoute::Public,
+            expected_profit_usd,
+            TxPurpose::Prerequisite,
+        ).await?;
+        Ok(sent.is_some())
+    }
+    
+    /// Gas price limits for a liquidation expected to earn `expected_profit_usd` before gas
+    pub fn gas_ceiling(&self, expected_profit_usd: f64, gas_limit: U256, native_price_usd: f64) -> GasCeiling {
+        if native_price_usd <= 0.0 || gas_limit.is_zero() {
+            return GasCeiling { break_even: U256::MAX, max_bid: U256::MAX };
+        }
+        
+        // USD budget -> wei per gas
+        let to_price = |usd: f64| -> U256 {
+            let wei = usd.max(0.0) / native_price_usd * 1e18 / u256_to_f64(gas_limit, 0);
+            U256::from(wei as u128)
+        };
+        
+        let break_even = to_price(expected_profit_usd - self.min_profit_usd);
+        let max_bid = to_price(expected_profit_usd * self.gas_profit_share).min(break_even);
+        GasCeiling { break_even, max_bid }
+    }
+    
+    /// On-chain `minProfit` for a swap expected to leave `surplus` debt asset
+    /// worth `expected_profit_usd`: the share of it that pays for gas and
+    /// `min_profit_usd`, so a worse fill reverts instead of losing money
+    pub fn profit_guard(&self, surplus: U256, expected_profit_usd: f64, gas_cost_usd: f64) -> U256 {
+        if expected_profit_usd <= 0.0 {
+            return surplus;
+        }
+        let share = ((gas_cost_usd + self.min_profit_usd) / expected_profit_usd).clamp(0.0, 1.0);
+        surplus * U256::from((share * 10_000.0).ceil() as u64) / 10_000
+    }
+    
     /// Check if we should use MEV protection
-    pub fn should_use_mev(&self, debt_usd: f64, chain: &str) -> bool {
-        debt_usd >= self.mev_threshold_usd && get_flashbots_protect_rpc(chain).is_some()
+    pub fn should_use_mev(&self, debt_usd: f64, chain: &ChainState) -> bool {
+        debt_usd >= self.mev_threshold_usd && chain.relays.has(chain.config.mev.relay_kind())
+    }
+    
+    /// Route for a liquidation of `debt_usd` whose prices come from `assets`' feeds
+    pub fn submit_route(&self, chain: &ChainState, debt_usd: f64, assets: &[Address]) -> SubmitRoute {
+        if !self.should_use_mev(debt_usd, chain) {
+            return SubmitRoute::Public;
+        }
+        
+        match chain.config.mev {
+            MevSubmission::Protect => SubmitRoute::Protect,
+            MevSubmission::Bundle => SubmitRoute::Bundle {
+                backrun: if chain.config.backrun_oracle_updates { assets.to_vec() } else { Vec::new() },
+            },
+        }
     }
     
-    /// Execute an Aave liquidation via flash loan
+    /// Execute an Aave liquidation via flash loan, or from inventory when funded by it
     pub async fn execute_aave_liquidation(
         &self,
         chain: &ChainState,
         position: &Position,
-        collateral_asset: Address,
-        debt_asset: Address,
-        debt_to_cover: U256,
+        liquidation: &AaveLiquidation,
+        expected_profit_usd: f64,
     ) -> anyhow::Result<Option<TxHash>> {
         if self.dry_run {
             info!("🧪 DRY RUN: Would execute Aave liquidation");
             info!("   User: {:?}", position.user);
+            info!("   Collateral: {:?}", liquidation.collateral_asset);
+            info!("   Debt: {:?}", liquidation.debt_asset);
+            info!("   Amount: {}", liquidation.debt_to_cover);
+            info!("   Funding: {}", liquidation.funding);
+            info!("   MEV Protection: {}", self.should_use_mev(position.debt_usd, chain));
+            return Ok(None);
+        }
+        
+        if liquidation.funding == Funding::Wallet {
+            let approved = self.ensure_allowance(
+                chain,
+                liquidation.debt_asset,
+                position.market,
+                liquidation.debt_to_cover,
+                expected_profit_usd,
+            ).await?;
+            if !approved {
+                return Ok(None);
+            }
+        }
+        
+        let (liquidator_address, tx_data) = match self.aave_liquidation_call(
+            chain,
+            position.market,
+            position.user,
+            liquidation,
+        )? {
+            Some(call) => call,
+            None => {
+                warn!("No liquidator contract configured for {}", chain.config.name);
+                return Ok(None);
+            }
+        };
+        
+        let route = self.submit_route(
+            chain,
+            position.debt_usd,
+            &[liquidation.collateral_asset, liquidation.debt_asset],
+        );
+        self.submit_call(chain, (liquidator_address, tx_data), chain.config.gas_limit, route, expected_profit_usd, TxPurpose::liquidation(position)).await
+    }
+    
+    /// Flash liquidator call for an Aave liquidation (`None` without a liquidator contract).
+    /// The route and profit guard only reach v2 liquidators; v1 ones pick their own swap.
+    /// Wallet-funded liquidations call the pool directly.
+    pub fn aave_liquidation_call(
+        &self,
+        chain: &ChainState,
+        pool: Address,
+        user: Address,
+        liquidation: &AaveLiquidation,
+    ) -> anyhow::Result<Option<(Address, Bytes)>> {
+        if liquidation.funding == Funding::Wallet {
+            let pool_contract = IAavePool::new(pool, Arc::new(chain.provider().clone()));
+            let tx_data = pool_contract
+                .liquidation_call(
+                    liquidation.collateral_asset,
+                    liquidation.debt_asset,
+                    user,
+                    liquidation.debt_to_cover,
+                    self.receive_a_token,
+                )
+                .calldata()
+                .ok_or_else(|| anyhow::anyhow!("Failed to encode liquidationCall calldata"))?;
+            return Ok(Some((pool, tx_data)));
+        }
+        
+        let liquidator = chain.config
+            .aave_market(pool)
+            .and_then(|market| market.liquidator_address.as_ref());
+        let liquidator_address: Address = match liquidator {
+            Some(addr) => addr.parse()?,
+            None => return Ok(None),
+        };
+        
+        let tx_data = match chain.config.aave_liquidator_abi {
+            LiquidatorAbi::V1 => build_liquidation_calldata(
+                user,
+                liquidation.collateral_asset,
+                liquidation.debt_asset,
+                liquidation.debt_to_cover,
+            ),
+            LiquidatorAbi::V2 => match liquidation.funding {
+                Funding::Contract(_) => build_inventory_liquidation_calldata(user, liquidation, self.receive_a_token),
+                _ => build_route_liquidation_calldata(user, liquidation),
+            },
+        };
+        Ok(Some((liquidator_address, tx_data)))
+    }
+    
+    /// `executeBatchLiquidation` call on the pool's v2 liquidator (`None`
+    /// without one)
+    pub fn aave_batch_call(
+        &self,
+        chain: &ChainState,
+        pool: Address,
+        batch: &AaveBatch,
+    ) -> Option<(Address, Bytes)> {
+        if chain.config.aave_liquidator_abi != LiquidatorAbi::V2 {
+            return None;
+        }
+        let liquidator = chain.config.aave_liquidator(pool)?;
+        Some((liquidator, build_batch_liquidation_calldata(batch)))
+    }
+    
+    /// Execute a batch of Aave liquidations with one flash loan and swap
+    pub async fn execute_aave_batch(
+        &self,
+        chain: &ChainState,
+        pool: Address,
+        batch: &AaveBatch,
+        debt_usd: f64,
+        expected_profit_usd: f64,
+    ) -> anyhow::Result<Option<TxHash>> {
+        if self.dry_run {
+            info!("🧪 DRY RUN: Would execute a batch of {} Aave liquidations", batch.items.len());
+            info!("   Collateral: {:?}", batch.collateral_asset);
+            info!("   Debt: {:?}", batch.debt_asset);
+            info!("   Flash loan: {} from {}", batch.debt_to_cover(), batch.flash);
+            for item in &batch.items {
+                info!("   User {:?}: {}", item.user, item.debt_to_cover);
+            }
+            return Ok(None);
+        }
+        
+        let (liquidator_address, tx_data) = match self.aave_batch_call(chain, pool, batch) {
+            Some(call) => call,
+            None => {
+                warn!("No v2 Aave liquidator configured for {}", chain.config.name);
+                return Ok(None);
+            }
+        };
+        
+        let route = self.submit_route(chain, debt_usd, &[batch.collateral_asset, batch.debt_asset]);
+        let fallback_gas = BATCH_ITEM_GAS * batch.items.len() as u64 + 400_000;
+        let purpose = TxPurpose::AaveBatch {
+            pool,
+            users: batch.items.iter().map(|item| item.user).collect(),
+            liquidator: liquidator_address,
+            debt_decimals: batch.debt_decimals,
+            debt_price_usd: batch.debt_price_usd,
+        };
+        self.submit_call(chain, (liquidator_address, tx_data), fallback_gas, route, expected_profit_usd, purpose).await
+    }
+    
+    /// Comet `absorb` call crediting the bot wallet as absorber
+    pub fn compound_absorb_call(
+        &self,
+        chain: &ChainState,
+        comet: Address,
+        accounts: Vec<Address>,
+    ) -> anyhow::Result<(Address, Bytes)> {
+        let comet_contract = IComet::new(comet, Arc::new(chain.provider().clone()));
+        let tx_data = comet_contract
+            .absorb(chain.wallet.address(), accounts)
+            .calldata()
+            .ok_or_else(|| anyhow::anyhow!("Failed to encode absorb calldata"))?;
+        Ok((comet, tx_data))
+    }
+    
+    /// Absorb underwater Compound V3 accounts, crediting the bot wallet as absorber
+    pub async fn execute_compound_absorb(
+        &self,
+        chain: &ChainState,
+        comet: Address,
+        accounts: Vec<Address>,
+        expected_profit_usd: f64,
+    ) -> anyhow::Result<Option<TxHash>> {
+        if self.dry_run {
+            info!("🧪 DRY RUN: Would absorb {} Compound account(s)", accounts.len());
+            info!("   Comet: {:?}", comet);
+            return Ok(None);
+        }
+        
+        let (_, tx_data) = self.compound_absorb_call(chain, comet, accounts)?;
+        
+        info!("🧽 Absorbing Compound account(s) on {:?}", comet);
+        // The collateral purchase needs the absorb mined first
+        self.submit_call(chain, (comet, tx_data), 500_000, SubmitRoute::Public, expected_profit_usd, TxPurpose::Prerequisite).await
+    }
+    
+    /// Buy absorbed Compound V3 collateral via the flash liquidator contract,
+    /// or straight from the Comet with the wallet's base token when self-funded
+    pub async fn execute_compound_liquidation(
+        &self,
+        chain: &ChainState,
+        position: &Position,
+        comet: Address,
+        collateral_asset: Address,
+        base_amount: U256,
+        expected_profit_usd: f64,
+    ) -> anyhow::Result<Option<TxHash>> {
+        if self.dry_run {
+            info!("🧪 DRY RUN: Would buy Compound collateral");
+            info!("   Comet: {:?}", comet);
             info!("   Collateral: {:?}", collateral_asset);
-            info!("   Debt: {:?}", debt_asset);
-            info!("   Amount: {}", debt_to_cover);
-            info!("   MEV Protection: {}", self.should_use_mev(position.debt_usd, &chain.config.name));
+            info!("   Base amount: {}", base_amount);
             return Ok(None);
         }
         
-        let liquidator_address: Address = match &chain.config.liquidator_address {
+        let route = self.submit_route(chain, position.debt_usd, &[collateral_asset]);
+        
+        if self.self_funded {
+            let comet_contract = IComet::new(comet, Arc::new(chain.provider().clone()));
+            let base_token = comet_contract.base_token().call().await?;
+            
+            if self.funding(chain, base_token, base_amount, None).await == Funding::Wallet {
+                if !self.ensure_allowance(chain, base_token, comet, base_amount, expected_profit_usd).await? {
+                    return Ok(None);
+                }
+                
+                // Allow 1% for the price feed moving before inclusion
+                let quoted = comet_contract.quote_collateral(collateral_asset, base_amount).call().await?;
+                let tx_data = comet_contract
+                    .buy_collateral(collateral_asset, quoted * 99 / 100, base_amount, chain.wallet.address())
+                    .calldata()
+                    .ok_or_else(|| anyhow::anyhow!("Failed to encode buyCollateral calldata"))?;
+                
+                info!("💼 Buying Compound collateral with wallet inventory");
+                return self.submit_call(chain, (comet, tx_data), 300_000, route, expected_profit_usd, TxPurpose::liquidation(position)).await;
+            }
+        }
+        
+        let liquidator_address: Address = match &chain.config.compound_liquidator_address {
             Some(addr) => addr.parse()?,
             None => {
-                warn!("No liquidator contract configured for {}", chain.config.name);
+                warn!("No Compound liquidator contract configured for {}", chain.config.name);
                 return Ok(None);
             }
         };
         
-        // Build transaction
-        let tx_data = build_liquidation_calldata(
+        let liquidator = ICompoundFlashLiquidator::new(
+            liquidator_address,
+            Arc::new(chain.provider().clone()),
+        );
+        
+        if !liquidator.supported_comets(comet).call().await.unwrap_or(false) {
+            warn!("Comet {:?} is not enabled on liquidator {:?}", comet, liquidator_address);
+            return Ok(None);
+        }
+        
+        let tx_data = liquidator
+            .execute_liquidation(comet, position.user, collateral_asset, base_amount)
+            .calldata()
+            .ok_or_else(|| anyhow::anyhow!("Failed to encode Compound liquidation calldata"))?;
+        
+        self.submit_call(chain, (liquidator_address, tx_data), chain.config.gas_limit, route, expected_profit_usd, TxPurpose::liquidation(position)).await
+    }
+    
+    /// Execute a Venus liquidation via the BNB flash liquidator contract.
+    /// For the native vBNB market the contract borrows WBNB and repays with BNB.
+    /// Self-funded, token markets are repaid from the wallet with `liquidateBorrow`.
+    pub async fn execute_venus_liquidation(
+        &self,
+        chain: &ChainState,
+        position: &Position,
+        repay_market: &VenusMarket,
+        seize_market: &VenusMarket,
+        repay_amount: U256,
+        expected_profit_usd: f64,
+    ) -> anyhow::Result<Option<TxHash>> {
+        if self.dry_run {
+            info!("🧪 DRY RUN: Would execute Venus liquidation");
+            info!("   User: {:?}", position.user);
+            info!("   Repay: {} {}", repay_amount, repay_market.symbol);
+            info!("   Seize: {}", seize_market.symbol);
+            return Ok(None);
+        }
+        
+        let route = self.submit_route(
+            chain,
+            position.debt_usd,
+            &[repay_market.repay_token(), seize_market.repay_token()],
+        );
+        
+        if !repay_market.is_native()
+            && self.funding(chain, repay_market.underlying, repay_amount, None).await == Funding::Wallet
+        {
+            let approved = self.ensure_allowance(
+                chain,
+                repay_market.underlying,
+                repay_market.v_token,
+                repay_amount,
+                expected_profit_usd,
+            ).await?;
+            if !approved {
+                return Ok(None);
+            }
+            
+            let v_token = IVToken::new(repay_market.v_token, Arc::new(chain.provider().clone()));
+            let tx_data = v_token
+                .liquidate_borrow(position.user, repay_amount, seize_market.v_token)
+                .calldata()
+                .ok_or_else(|| anyhow::anyhow!("Failed to encode liquidateBorrow calldata"))?;
+            
+            info!("💼 Repaying Venus {} with wallet inventory", repay_market.symbol);
+            let call = (repay_market.v_token, tx_data);
+            return self.submit_call(chain, call, 600_000, route, expected_profit_usd, TxPurpose::liquidation(position)).await;
+        }
+        
+        let (liquidator_address, tx_data) = match self.venus_liquidation_call(
+            chain,
             position.user,
-            collateral_asset,
-            debt_asset,
-            debt_to_cover,
-            3000, // 0.3% pool fee
+            repay_market,
+            seize_market,
+            repay_amount,
+        )? {
+            Some(call) => call,
+            None => {
+                warn!("No Venus liquidator contract configured for {}", chain.config.name);
+                return Ok(None);
+            }
+        };
+        
+        self.submit_call(chain, (liquidator_address, tx_data), chain.config.gas_limit, route, expected_profit_usd, TxPurpose::liquidation(position)).await
+    }
+    
+    /// Venus flash liquidator call (`None` without a liquidator contract)
+    pub fn venus_liquidation_call(
+        &self,
+        chain: &ChainState,
+        user: Address,
+        repay_market: &VenusMarket,
+        seize_market: &VenusMarket,
+        repay_amount: U256,
+    ) -> anyhow::Result<Option<(Address, Bytes)>> {
+        let liquidator_address: Address = match &chain.config.venus_liquidator_address {
+            Some(addr) => addr.parse()?,
+            None => return Ok(None),
+        };
+        
+        let liquidator = IVenusFlashLiquidator::new(
+            liquidator_address,
+            Arc::new(chain.provider().clone()),
         );
         
+        let tx_data = liquidator
+            .execute_liquidation(
+                repay_market.repay_token(),
+                repay_amount,
+                repay_market.v_token,
+                seize_market.v_token,
+                user,
+            )
+            .calldata()
+            .ok_or_else(|| anyhow::anyhow!("Failed to encode Venus liquidation calldata"))?;
+        Ok(Some((liquidator_address, tx_data)))
+    }
+    
+    /// Simulate, estimate gas, price and submit a contract call (target, calldata)
+    /// from the bot wallet. The priority fee bid scales with `expected_profit_usd`
+    /// on EIP-1559 chains. Calls that revert against the pending block fail with
+    /// [`SimulationRevert`]. Returns once the transaction is accepted; `purpose`
+    /// decides whether its receipt is tracked in the background or waited on.
+    async fn submit_call(
+        &self,
+        chain: &ChainState,
+        call: (Address, Bytes),
+        fallback_gas: u64,
+        route: SubmitRoute,
+        expected_profit_usd: f64,
+        purpose: TxPurpose,
+    ) -> anyhow::Result<Option<TxHash>> {
+        let (to, tx_data) = call;
         let estimate_tx: TypedTransaction = TransactionRequest::new()
-            .to(liquidator_address)
+            .to(to)
             .data(tx_data.clone())
             .from(chain.wallet.address())
             .into();
         
+        // Pre-flight: a liquidation that reverts now would only burn gas on-chain.
+        // Bundles are simulated with `eth_callBundle` instead, behind any oracle
+        // update they backrun.
+        let bundled = matches!(route, SubmitRoute::Bundle { .. });
+        if !bundled {
+            if let Err(e) = chain.provider().call(&estimate_tx, Some(BlockNumber::Pending.into())).await {
+                let revert_data = RpcError::as_error_response(&e).and_then(|rpc| rpc.as_revert_data());
+                return match revert_data {
+                    Some(data) => {
+                        let reason = decode_revert_reason(&data);
+                        warn!("🧪 Simulation reverted: {} - not sending", reason);
+                        Err(SimulationRevert { reason }.into())
+                    }
+                    None => Err(anyhow::anyhow!("Pre-flight simulation failed: {}", e)),
+                };
+            }
+        }
+        
         let gas_estimate = chain.provider()
             .estimate_gas(&estimate_tx, None)
             .await
-            .unwrap_or(U256::from(800_000));
+            .unwrap_or(U256::from(fallback_gas));
         
-        let gas_limit = gas_estimate * 120 / 100; // 20% buffer
-        let gas_price = chain.provider().get_gas_price().await?;
-        let nonce = chain.next_nonce();
+        let gas_limit = gas::gas_limit_with_buffer(&chain.config, gas_estimate);
+        let native_price = chain.native_price_usd().await;
         
-        let tx = TransactionRequest::new()
-            .to(liquidator_address)
-            .data(tx_data)
-            .gas(gas_limit)
-            .gas_price(gas_price)
-            .nonce(nonce)
-            .chain_id(chain.config.chain_id);
+        // A separately charged L1 data fee comes off the profit before bidding
+        let l1 = gas::l1_data_cost(chain.provider(), &chain.config, to, &tx_data, gas_limit)
+            .await
+            .unwrap_or_default();
+        let fees = gas::estimate_fees(chain.provider(), &chain.config, expected_profit_usd)
+            .await?
+            .with_min_priority_fee(l1.min_priority_fee);
+        let l1_fee_usd = if l1.included_gas.is_zero() {
+            u256_to_f64(l1.fee_wei, 18) * native_price
+        } else {
+            0.0
+        };
+        let ceiling = self.gas_ceiling(expected_profit_usd - l1_fee_usd, gas_limit, native_price);
         
-        // Use Flashbots Protect for MEV protection on supported chains
-        if self.should_use_mev(position.debt_usd, &chain.config.name) {
-            info!("🛡️  Using Flashbots Protect for MEV protection");
-            return self.send_via_flashbots_protect(chain, tx).await;
+        // Abort before signing if the network alone already eats the profit
+        if fees.base_fee > ceiling.break_even {
+            warn!(
+                "⛽ Gas {:.3} gwei above break-even {:.3} gwei for ~${:.2} - not sending",
+                u256_to_f64(fees.base_fee, 9),
+                u256_to_f64(ceiling.break_even, 9),
+                expected_profit_usd
+            );
+            return Ok(None);
         }
         
-        // Standard submission
-        info!("📤 Sending liquidation TX (standard)...");
-        self.send_standard(chain, tx).await
+        let pricing = fees.bid(ceiling.max_bid);
+        let nonce = chain.nonces.reserve();
+        
+        info!("⛽ Gas: {}", pricing);
+        let tx = pricing.build_tx(to, tx_data, gas_limit, U256::zero(), nonce, chain.config.chain_id);
+        
+        let result = match route {
+            SubmitRoute::Public => {
+                info!("📤 Sending liquidation TX (standard)...");
+                self.send_standard(chain, tx, ceiling.max_bid, purpose).await
+            }
+            SubmitRoute::Protect => {
+                info!("🛡️  Using private relays for MEV protection");
+                self.send_private(chain, tx, ceiling.max_bid, purpose).await
+            }
+            SubmitRoute::Bundle { backrun } => {
+                info!("📦 Using a builder bundle for MEV protection");
+                self.send_bundle(chain, tx, ceiling.max_bid, &backrun, purpose).await
+            }
+        };
+        
+        // Errors mean no node accepted the transaction, so the nonce is still free
+        if result.is_err() {
+            chain.nonces.release(nonce);
+        }
+        result
     }
     
-    /// Send transaction via Flashbots Protect RPC
-    /// This is the simplest MEV protection - just send to a different RPC
-    async fn send_via_flashbots_protect(
+    /// Send a signed transaction to the chain's private relays in parallel.
+    /// Falls back to the public mempool when no relay accepts it.
+    async fn send_private(
         &self,
         chain: &ChainState,
-        tx: TransactionRequest,
+        tx: TypedTransaction,
+        max_price_per_gas: U256,
+        purpose: TxPurpose,
     ) -> anyhow::Result<Option<TxHash>> {
-        let flashbots_rpc = match get_flashbots_protect_rpc(&chain.config.name) {
-            Some(rpc) => rpc,
-            None => {
-                warn!("No Flashbots RPC for {}, falling back to standard", chain.config.name);
-                return self.send_standard(chain, tx).await;
-            }
-        };
-        
-        // Create a provider pointing to Flashbots
-        let flashbots_provider = Provider::<Http>::try_from(flashbots_rpc)?;
+        let candidates = chain.relays.select(RelayKind::PrivateTx);
+        if candidates.is_empty() {
+            warn!("No private relays for {}, falling back to standard", chain.config.name);
+            return self.send_standard(chain, tx, max_price_per_gas, purpose).await;
+        }
         
         // Sign the transaction
-        let typed_tx: TypedTransaction = tx.clone().into();
-        let signature = chain.wallet.sign_transaction(&typed_tx).await?;
+        let signature = chain.wallet.sign_transaction(&tx).await?;
         let signed_tx = tx.rlp_signed(&signature);
+        let tx_hash = H256::from_slice(&keccak256(&signed_tx));
+        let raw_tx = format!("0x{}", hex::encode(&signed_tx));
         
-        info!("🛡️  Submitting to Flashbots Protect: {}", flashbots_rpc);
+        let results = join_all(candidates.iter().map(|relay| {
+            relays::request::<_, serde_json::Value>(
+                &self.http_client,
+                &relay.endpoint,
+                &chain.wallet,
+                "eth_sendRawTransaction",
+                raw_tx.clone(),
+            )
+        })).await;
         
-        // Send raw transaction to Flashbots
-        match flashbots_provider.send_raw_transaction(signed_tx).await {
-            Ok(pending_tx) => {
-                let tx_hash = pending_tx.tx_hash();
-                info!("⏳ TX submitted via Flashbots: {:?}", tx_hash);
-                
-                // Wait for confirmation (Flashbots may take longer)
-                match tokio::time::timeout(
-                    std::time::Duration::from_secs(120), // Longer timeout for Flashbots
-                    pending_tx,
-                ).await {
-                    Ok(Ok(Some(receipt))) => {
-                        if receipt.status == Some(U64::from(1)) {
-                            info!("✅ Flashbots TX successful! Gas: {}", receipt.gas_used.unwrap_or_default());
-                            return Ok(Some(tx_hash));
-                        } else {
-                            error!("❌ Flashbots TX reverted");
-                            return Ok(None);
-                        }
-                    }
-                    Ok(Ok(None)) => {
-                        warn!("⏰ Flashbots TX pending (no receipt yet)");
-                        return Ok(Some(tx_hash));
-                    }
-                    Ok(Err(e)) => {
-                        error!("❌ Flashbots TX error: {}", e);
-                    }
-                    Err(_) => {
-                        warn!("⏰ Flashbots TX timeout - may still land");
-                        return Ok(Some(tx_hash));
-                    }
-                }
-            }
-            Err(e) => {
-                error!("❌ Flashbots submission failed: {}", e);
-                // Fallback to standard
-                warn!("Falling back to standard submission");
-                return self.send_standard(chain, tx).await;
+        let mut accepted = Vec::new();
+        for (relay, result) in candidates.iter().zip(results) {
+            relay.record_submission(result.is_ok());
+            match result {
+                Ok(_) => accepted.push(relay.clone()),
+                Err(e) => warn!("🛡️  {} rejected TX: {}", relay.name(), e),
             }
         }
         
-        Ok(None)
+        if accepted.is_empty() {
+            error!("❌ No private relay accepted the TX");
+            warn!("Falling back to standard submission");
+            return self.send_standard(chain, tx, max_price_per_gas, purpose).await;
+        }
+        
+        chain.nonces.track(&tx, tx_hash, max_price_per_gas, true);
+        info!("⏳ TX submitted via {}: {:?}", relay_names(&accepted), tx_hash);
+        
+        self.follow(chain, &tx, tx_hash, accepted, purpose).await
     }
     
-    /// Send transaction via Flashbots Bundle API (advanced)
-    /// This allows targeting specific blocks and provides better guarantees
-    #[allow(dead_code)]
-    async fn send_via_flashbots_bundle(
+    /// Send transaction as a bundle to the chain's builders. The bundle is
+    /// simulated with `eth_callBundle`, submitted to every selected builder for
+    /// the next `BUNDLE_TARGET_BLOCKS` blocks and handed to the receipt
+    /// tracker, which follows it until it lands or the last target passes.
+    /// Pending oracle updates for `backrun` tokens go first.
+    async fn send_bundle(
         &self,
         chain: &ChainState,
-        tx: TransactionRequest,
+        tx: TypedTransaction,
+        max_price_per_gas: U256,
+        backrun: &[Address],
+        purpose: TxPurpose,
     ) -> anyhow::Result<Option<TxHash>> {
-        let flashbots_rpc = match get_flashbots_rpc(&chain.config.name) {
-            Some(rpc) => rpc,
-            None => return self.send_standard(chain, tx).await,
-        };
+        let candidates = chain.relays.select(RelayKind::Bundle);
+        if candidates.is_empty() {
+            warn!("No bundle relays for {}, falling back to standard", chain.config.name);
+            return self.send_standard(chain, tx, max_price_per_gas, purpose).await;
+        }
         
         // Sign the transaction
-        let typed_tx: TypedTransaction = tx.clone().into();
-        let signature = chain.wallet.sign_transaction(&typed_tx).await?;
+        let signature = chain.wallet.sign_transaction(&tx).await?;
         let signed_tx = tx.rlp_signed(&signature);
-        let signed_tx_hex = format!("0x{}", hex::encode(&signed_tx));
-        
-        // Get current block and target next block
-        let current_block = chain.provider().get_block_number().await?;
-        let target_block = current_block + 1;
-        let target_block_hex = format!("0x{:x}", target_block);
-        
-        // Build bundle request
-        let bundle = FlashbotsBundle {
-            jsonrpc: "2.0",
-            id: 1,
-            method: "eth_sendBundle",
-            params: vec![FlashbotsBundleParams {
-                txs: vec![signed_tx_hex],
-                block_number: target_block_hex,
-                min_timestamp: None,
-                max_timestamp: Some(
-                    SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs() + 120
-                ),
-            }],
-        };
+        let tx_hash = H256::from_slice(&keccak256(&signed_tx));
+        let liquidation = format!("0x{}", hex::encode(&signed_tx));
         
-        // Sign the bundle payload for authentication
-        let body = serde_json::to_string(&bundle)?;
-        let body_hash = keccak256(body.as_bytes());
-        let fb_signature = chain.wallet.sign_message(&body_hash).await?;
-        let fb_auth_header = format!(
-            "{}:0x{}",
-            chain.wallet.address(),
-            hex::encode(fb_signature.to_vec())
-        );
+        let current_block = chain.provider().get_block_number().await?.as_u64();
+        let first_target = current_block + 1;
+        let last_target = current_block + BUNDLE_TARGET_BLOCKS;
         
-        info!("🛡️  Submitting Flashbots bundle for block {}", target_block);
+        // Backrun pending oracle updates if the bundle still works with them
+        let oracle_updates = chain.pending_oracle_updates(backrun);
+        let mut txs: Vec<String> = oracle_updates
+            .iter()
+            .map(|update| format!("0x{}", hex::encode(&update.raw)))
+            .chain(std::iter::once(liquidation.clone()))
+            .collect();
         
-        // Send bundle
-        let response = self.http_client
-            .post(flashbots_rpc)
-            .header("Content-Type", "application/json")
-            .header("X-Flashbots-Signature", fb_auth_header)
-            .body(body)
-            .send()
-            .await?;
+        let mut revert = self.simulate_bundle(chain, &candidates, &txs, first_target).await?;
+        if revert.is_some() && !oracle_updates.is_empty() {
+            debug!("Bundle behind {} oracle update(s) reverted, simulating alone", oracle_updates.len());
+            txs = vec![liquidation];
+            revert = self.simulate_bundle(chain, &candidates, &txs, first_target).await?;
+        }
+        if let Some(reason) = revert {
+            warn!("🧪 Bundle simulation reverted: {} - not sending", reason);
+            return Err(SimulationRevert { reason }.into());
+        }
+        if txs.len() > 1 {
+            for update in &oracle_updates {
+                info!("🎯 Backrunning oracle update {:?}", update.hash);
+            }
+        }
         
-        let fb_response: FlashbotsResponse = response.json().await?;
+        // Submit to every builder for each target block
+        let submissions: Vec<(Arc<Relay>, u64)> = candidates
+            .iter()
+            .flat_map(|relay| (first_target..=last_target).map(move |target| (relay.clone(), target)))
+            .collect();
+        let results = join_all(submissions.iter().map(|(relay, target)| {
+            let params = FlashbotsBundleParams {
+                txs: txs.clone(),
+                block_number: format!("0x{:x}", target),
+            };
+            relays::request::<_, SendBundleResult>(
+                &self.http_client,
+                &relay.endpoint,
+                &chain.wallet,
+                "eth_sendBundle",
+                params,
+            )
+        })).await;
         
-        if let Some(error) = fb_response.error {
-            error!("❌ Flashbots bundle error: {} (code: {})", error.message, error.code);
-            return Ok(None);
+        let mut submitted = Vec::new();
+        for ((relay, target), result) in submissions.into_iter().zip(results) {
+            match result {
+                Ok(result) => {
+                    debug!("📦 {} bundle {:?} targets block {}", relay.name(), result.bundle_hash, target);
+                    submitted.push((relay, target, result.bundle_hash));
+                }
+                Err(e) => warn!("📦 {} rejected bundle for block {}: {}", relay.name(), target, e),
+            }
         }
         
-        if let Some(result) = fb_response.result {
-            if let Some(bundle_hash) = result.bundle_hash {
-                info!("📦 Bundle submitted: {}", bundle_hash);
-                
-                // Calculate expected TX hash
-                let tx_hash = H256::from_slice(&keccak256(&signed_tx));
-                
-                // Wait for inclusion (check multiple blocks)
-                for _ in 0..12 {
-                    tokio::time::sleep(std::time::Duration::from_secs(12)).await;
-                    
-                    if let Ok(Some(receipt)) = chain.provider().get_transaction_receipt(tx_hash).await {
-                        if receipt.status == Some(U64::from(1)) {
-                            info!("✅ Bundle landed! TX: {:?}", tx_hash);
-                            return Ok(Some(tx_hash));
-                        } else {
-                            error!("❌ Bundle TX reverted");
-                            return Ok(None);
-                        }
-                    }
+        let accepted: Vec<Arc<Relay>> = candidates
+            .iter()
+            .filter(|relay| submitted.iter().any(|(r, _, _)| Arc::ptr_eq(r, relay)))
+            .cloned()
+            .collect();
+        for relay in &candidates {
+            relay.record_submission(accepted.iter().any(|r| Arc::ptr_eq(r, relay)));
+        }
+        if accepted.is_empty() {
+            anyhow::bail!("No builder accepted the bundle for any target block");
+        }
+        info!(
+            "📦 Bundle submitted to {} for blocks {}-{}",
+            relay_names(&accepted), first_target, last_target
+        );
+        
+        // The receipt tracker follows it through the target blocks
+        submitted.sort_by_key(|(_, target, _)| *target);
+        let bundle = BundleTargets { last_target, submitted };
+        self.track(chain, &tx, tx_hash, accepted, purpose, Some(bundle));
+        Ok(Some(tx_hash))
+    }
+    
+    /// Simulate a bundle with `eth_callBundle` on the first relay that supports
+    /// it; returns the first revert reason
+    async fn simulate_bundle(
+        &self,
+        chain: &ChainState,
+        candidates: &[Arc<Relay>],
+        txs: &[String],
+        target_block: u64,
+    ) -> anyhow::Result<Option<String>> {
+        let mut last_error = None;
+        
+        for relay in candidates {
+            let params = CallBundleParams {
+                txs: txs.to_vec(),
+                block_number: format!("0x{:x}", target_block),
+                state_block_number: "latest",
+            };
+            let result = match relays::request::<_, CallBundleResult>(
+                &self.http_client,
+                &relay.endpoint,
+                &chain.wallet,
+                "eth_callBundle",
+                params,
+            ).await {
+                Ok(result) => result,
+                Err(e) => {
+                    debug!("{} can't simulate bundles: {}", relay.name(), e);
+                    last_error = Some(e);
+                    continue;
                 }
-                
-                warn!("⏰ Bundle may not have landed after 12 blocks");
-                return Ok(Some(tx_hash));
-            }
+            };
+            debug!("🧪 Bundle simulation on {}: {} gas", relay.name(), result.total_gas_used);
+            
+            return Ok(result.results.into_iter().find_map(|tx| {
+                let revert = tx.revert.or(tx.error)?;
+                // Some relays return raw revert data instead of the decoded reason
+                Some(match revert.parse::<Bytes>() {
+                    Ok(data) => decode_revert_reason(&data),
+                    Err(_) => revert,
+                })
+            }));
         }
         
-        Ok(None)
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No relay to simulate the bundle")))
     }
     
     /// Standard transaction submission
     async fn send_standard(
         &self,
         chain: &ChainState,
-        tx: TransactionRequest,
+        tx: TypedTransaction,
+        max_price_per_gas: U256,
+        purpose: TxPurpose,
     ) -> anyhow::Result<Option<TxHash>> {
         // Sign and send
-        let typed_tx: TypedTransaction = tx.clone().into();
-        let signature = chain.wallet.sign_transaction(&typed_tx).await?;
+        let signature = chain.wallet.sign_transaction(&tx).await?;
         let signed_tx = tx.rlp_signed(&signature);
         
         info!("📤 Sending TX (standard)...");
         
         let pending_tx = chain.provider().send_raw_transaction(signed_tx).await?;
         let tx_hash = pending_tx.tx_hash();
+        chain.nonces.track(&tx, tx_hash, max_price_per_gas, false);
         
         info!("⏳ TX submitted: {:?}", tx_hash);
         
-        match tokio::time::timeout(
-            std::time::Duration::from_secs(60),
-            pending_tx,
-        ).await {
-            Ok(Ok(Some(receipt))) => {
+        self.follow(chain, &tx, tx_hash, Vec::new(), purpose).await
+    }
+    
+    /// Mark a sent transaction's positions in flight and hand it to the
+    /// receipt tracker, which clears them once it's settled
+    fn track(
+        &self,
+        chain: &ChainState,
+        tx: &TypedTransaction,
+        tx_hash: TxHash,
+        relays: Vec<Arc<Relay>>,
+        purpose: TxPurpose,
+        bundle: Option<BundleTargets>,
+    ) {
+        for position in purpose.positions() {
+            chain.in_flight.insert(position);
+        }
+        self.receipts.track(TrackedTx {
+            chain: chain.config.name.clone(),
+            hash: tx_hash,
+            nonce: tx.nonce().map_or(0, |n| n.as_u64()),
+            relays,
+            purpose,
+            bundle,
+        });
+    }
+    
+    /// Hand an accepted transaction to the receipt tracker, or briefly wait for
+    /// its receipt when it's a prerequisite of the next call
+    async fn follow(
+        &self,
+        chain: &ChainState,
+        tx: &TypedTransaction,
+        tx_hash: TxHash,
+        relays: Vec<Arc<Relay>>,
+        purpose: TxPurpose,
+    ) -> anyhow::Result<Option<TxHash>> {
+        if !matches!(purpose, TxPurpose::Prerequisite) {
+            self.track(chain, tx, tx_hash, relays, purpose, None);
+            return Ok(Some(tx_hash));
+        }
+        
+        let deadline = tokio::time::Instant::now() + PREREQUISITE_TX_TIMEOUT;
+        while tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+            
+            if let Ok(Some(receipt)) = chain.provider().get_transaction_receipt(tx_hash).await {
+                if !relays.is_empty() {
+                    receipts::credit_relays(chain, &relays, &receipt).await;
+                }
                 if receipt.status == Some(U64::from(1)) {
                     info!("✅ TX successful! Gas: {}", receipt.gas_used.unwrap_or_default());
                     return Ok(Some(tx_hash));
@@ -380,54 +1104,64 @@ impl Executor {
                     return Ok(None);
                 }
             }
-            Ok(Ok(None)) => {
-                warn!("⏰ TX pending (no receipt)");
-                return Ok(Some(tx_hash));
-            }
-            Ok(Err(e)) => {
-                error!("❌ TX failed: {}", e);
-            }
-            Err(_) => {
-                warn!("⏰ TX timeout - may still succeed");
-                return Ok(Some(tx_hash));
-            }
         }
         
-        Ok(None)
+        warn!("⏰ TX not mined yet - tracking it in the background");
+        self.track(chain, tx, tx_hash, relays, purpose, None);
+        Ok(Some(tx_hash))
     }
     
-    /// Estimate gas cost in USD
+    /// Estimate gas cost in USD for `call` (target, calldata), including the
+    /// L1 data fee on rollups. Uses `eth_estimateGas` on the real calldata and
+    /// `fallback_gas` when there is no call yet or the estimate reverts.
     pub async fn estimate_gas_cost_usd(
         &self,
         chain: &ChainState,
-        gas_limit: u64,
+        call: Option<&(Address, Bytes)>,
+        fallback_gas: u64,
     ) -> anyhow::Result<f64> {
-        let gas_price = chain.provider().get_gas_price().await?;
-        let gas_price_gwei = gas_price.as_u128() as f64 / 1e9;
+        let mut fees = gas::estimate_fees(chain.provider(), &chain.config, 0.0).await?;
         
-        let gas_cost_native = gas_limit as f64 * gas_price_gwei * 1e-9;
-        let gas_cost_usd = gas_cost_native * chain.config.native_price_fallback;
+        let mut gas_used = U256::from(fallback_gas);
+        let mut l1 = L1Cost::default();
         
-        Ok(gas_cost_usd)
-    }
-    
-    /// Calculate optimal debt to cover (close factor)
-    pub fn calculate_debt_to_cover(
-        &self,
-        protocol: Protocol,
-        total_debt: U256,
-    ) -> U256 {
-        let close_factor = match protocol {
-            Protocol::Aave => 50,
-            Protocol::Compound => 50,
-            Protocol::Venus => 50,
-        };
+        if let Some((to, data)) = call {
+            let estimate_tx: TypedTransaction = TransactionRequest::new()
+                .to(*to)
+                .data(data.clone())
+                .from(chain.wallet.address())
+                .into();
+            
+            match chain.provider().estimate_gas(&estimate_tx, None).await {
+                Ok(estimate) => {
+                    l1 = gas::l1_data_cost(chain.provider(), &chain.config, *to, data, estimate)
+                        .await
+                        .unwrap_or_else(|e| {
+                            debug!("{}: L1 fee lookup failed: {}", chain.config.name, e);
+                            L1Cost::default()
+                        });
+                    gas_used = estimate.saturating_sub(l1.included_gas);
+                }
+                Err(e) => debug!("estimateGas failed ({}), assuming {} gas", e, fallback_gas),
+            }
+        }
         
-        total_debt * close_factor / 100
+        fees = fees.with_min_priority_fee(l1.min_priority_fee);
+        let price_per_gas = fees.base_fee + fees.priority_fee;
+        let cost_wei = gas_used * price_per_gas + l1.fee_wei;
+        let native_price = chain.native_price_usd().await;
+        let gas_cost_usd = u256_to_f64(cost_wei, 18) * native_price;
+        
+        debug!(
+            "⛽ {} gas @ {:.3} gwei + L1 {} wei = ${:.4} (native ${:.2})",
+            gas_used, u256_to_f64(price_per_gas, 9), l1.fee_wei, gas_cost_usd, native_price
+        );
+        
+        Ok(gas_cost_usd)
     }
 }
 
-/// Build liquidation calldata for deployed contract
+/// Build liquidation calldata for v1 flash liquidators
 /// Function: executeLiquidation(address user, address collateralAsset, address debtAsset, uint256 debtToCover)
 /// Selector: 0x05c3786d
 pub fn build_liquidation_calldata(
@@ -435,7 +1169,6 @@ pub fn build_liquidation_calldata(
     collateral_asset: Address,
     debt_asset: Address,
     debt_to_cover: U256,
-    _pool_fee: u32, // Unused - kept for API compatibility
 ) -> Bytes {
     // executeLiquidation(address,address,address,uint256) = 0x05c3786d
     let selector = ethers::utils::id("executeLiquidation(address,address,address,uint256)");
@@ -452,40 +1185,164 @@ pub fn build_liquidation_calldata(
     Bytes::from(data)
 }
 
-/// Pre-computed transaction template for faster execution
-#[derive(Clone)]
-pub struct TxTemplate {
-    pub to: Address,
-    pub data: Bytes,
-    pub gas_limit: U256,
-    pub value: U256,
+/// Build route-carrying liquidation calldata for FlashLiquidatorV2
+/// Function: executeLiquidationWithRoute(address collateralAsset, address debtAsset, address user,
+///   uint256 debtToCover, uint256 minProfit, (uint8 kind, address target, bytes data) route,
+///   (uint8 kind, address lender) flash)
+pub fn build_route_liquidation_calldata(user: Address, liquidation: &AaveLiquidation) -> Bytes {
+    let selector = ethers::utils::id(
+        "executeLiquidationWithRoute(address,address,address,uint256,uint256,(uint8,address,bytes),(uint8,address))",
+    );
+    
+    let mut data = selector[0..4].to_vec();
+    
+    data.extend_from_slice(&ethers::abi::encode(&[
+        ethers::abi::Token::Address(liquidation.collateral_asset),
+        ethers::abi::Token::Address(liquidation.debt_asset),
+        ethers::abi::Token::Address(user),
+        ethers::abi::Token::Uint(liquidation.debt_to_cover),
+        ethers::abi::Token::Uint(liquidation.min_profit),
+        route_token(&liquidation.route),
+        ethers::abi::Token::Tuple(vec![
+            ethers::abi::Token::Uint(U256::from(liquidation.flash.source as u8)),
+            ethers::abi::Token::Address(liquidation.flash.lender),
+        ]),
+    ]));
+    
+    Bytes::from(data)
+}
+
+/// Build self-funded liquidation calldata for FlashLiquidatorV2
+/// Function: liquidateFromInventory(address collateralAsset, address debtAsset, address user,
+///   uint256 debtToCover, uint256 minProfit, bool receiveAToken, (uint8 kind, address target, bytes data) route)
+pub fn build_inventory_liquidation_calldata(
+    user: Address,
+    liquidation: &AaveLiquidation,
+    receive_a_token: bool,
+) -> Bytes {
+    let selector = ethers::utils::id(
+        "liquidateFromInventory(address,address,address,uint256,uint256,bool,(uint8,address,bytes))",
+    );
+    
+    let mut data = selector[0..4].to_vec();
+    
+    data.extend_from_slice(&ethers::abi::encode(&[
+        ethers::abi::Token::Address(liquidation.collateral_asset),
+        ethers::abi::Token::Address(liquidation.debt_asset),
+        ethers::abi::Token::Address(user),
+        ethers::abi::Token::Uint(liquidation.debt_to_cover),
+        ethers::abi::Token::Uint(liquidation.min_profit),
+        ethers::abi::Token::Bool(receive_a_token),
+        route_token(&liquidation.route),
+    ]));
+    
+    Bytes::from(data)
 }
 
-impl TxTemplate {
-    pub fn new(
-        liquidator_address: Address,
-        user: Address,
-        collateral_asset: Address,
-        debt_asset: Address,
-        debt_to_cover: U256,
-        pool_fee: u32,
-    ) -> Self {
-        Self {
-            to: liquidator_address,
-            data: build_liquidation_calldata(user, collateral_asset, debt_asset, debt_to_cover, pool_fee),
-            gas_limit: U256::from(800_000),
-            value: U256::zero(),
+/// Build batched liquidation calldata for FlashLiquidatorV2
+/// Function: executeBatchLiquidation(address collateralAsset, address debtAsset,
+///   (address user, uint256 debtToCover)[] items, uint256 minProfit,
+///   (uint8 kind, address target, bytes data) route, (uint8 kind, address lender) flash)
+pub fn build_batch_liquidation_calldata(batch: &AaveBatch) -> Bytes {
+    let selector = ethers::utils::id(
+        "executeBatchLiquidation(address,address,(address,uint256)[],uint256,(uint8,address,bytes),(uint8,address))",
+    );
+    
+    let items = batch.items
+        .iter()
+        .map(|item| ethers::abi::Token::Tuple(vec![
+            ethers::abi::Token::Address(item.user),
+            ethers::abi::Token::Uint(item.debt_to_cover),
+        ]))
+        .collect();
+    
+    let mut data = selector[0..4].to_vec();
+    
+    data.extend_from_slice(&ethers::abi::encode(&[
+        ethers::abi::Token::Address(batch.collateral_asset),
+        ethers::abi::Token::Address(batch.debt_asset),
+        ethers::abi::Token::Array(items),
+        ethers::abi::Token::Uint(batch.min_profit),
+        route_token(&batch.route),
+        ethers::abi::Token::Tuple(vec![
+            ethers::abi::Token::Uint(U256::from(batch.flash.source as u8)),
+            ethers::abi::Token::Address(batch.flash.lender),
+        ]),
+    ]));
+    
+    Bytes::from(data)
+}
+
+/// Batch results from FlashLiquidatorV2's `BatchItemLiquidated`,
+/// `BatchItemFailed` and batch-level `LiquidationExecuted` events
+pub fn decode_batch_outcome(logs: &[Log], liquidator: Address) -> BatchOutcome {
+    use ethers::abi::{decode, ParamType, Token};
+    
+    let liquidated_topic = H256::from(keccak256("BatchItemLiquidated(address,uint256,uint256)"));
+    let failed_topic = H256::from(keccak256("BatchItemFailed(address,bytes)"));
+    let executed_topic = H256::from(keccak256(
+        "LiquidationExecuted(address,address,address,uint256,uint256,uint256,string)",
+    ));
+    
+    let mut outcome = BatchOutcome { items: Vec::new(), debt_repaid: U256::zero(), profit: U256::zero() };
+    
+    for log in logs.iter().filter(|log| log.address == liquidator) {
+        let (topic, user) = match (log.topics.first(), log.topics.get(1)) {
+            (Some(topic), Some(user)) => (*topic, Address::from(*user)),
+            _ => continue,
+        };
+        
+        if topic == liquidated_topic {
+            if let Ok(tokens) = decode(&[ParamType::Uint(256), ParamType::Uint(256)], &log.data) {
+                if let [Token::Uint(debt_repaid), Token::Uint(collateral_seized)] = tokens[..] {
+                    outcome.items.push((user, BatchItemResult::Liquidated { debt_repaid, collateral_seized }));
+                }
+            }
+        } else if topic == failed_topic {
+            let reason = match decode(&[ParamType::Bytes], &log.data).ok().as_deref() {
+                Some([Token::Bytes(data)]) => decode_revert_reason(data),
+                _ => "unknown".to_string(),
+            };
+            outcome.items.push((user, BatchItemResult::Failed { reason }));
+        } else if topic == executed_topic && user.is_zero() {
+            let params = [
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::String,
+            ];
+            if let Ok(tokens) = decode(&params, &log.data) {
+                if let (Some(Token::Uint(debt_repaid)), Some(Token::Uint(profit))) = (tokens.get(2), tokens.get(4)) {
+                    outcome.debt_repaid = *debt_repaid;
+                    outcome.profit = *profit;
+                }
+            }
         }
     }
     
-    pub fn to_request(&self, nonce: u64, gas_price: U256, chain_id: u64) -> TransactionRequest {
-        TransactionRequest::new()
-            .to(self.to)
-            .data(self.data.clone())
-            .gas(self.gas_limit)
-            .gas_price(gas_price)
-            .nonce(nonce)
-            .value(self.value)
-            .chain_id(chain_id)
-    }
+    outcome
+}
+
+/// `SwapRoute` tuple of FlashLiquidatorV2
+fn route_token(route: &SwapRoute) -> ethers::abi::Token {
+    // RouteKind: 0 = BestDex, 1 = UniswapV3, 2 = Aggregator. A zero Uniswap
+    // target makes the contract use its configured router.
+    let (kind, target, route_data) = match route {
+        SwapRoute::BestDex => (0u8, Address::zero(), Bytes::default()),
+        SwapRoute::UniswapV3 { path } => (1, Address::zero(), path.clone()),
+        SwapRoute::Aggregator { target, calldata } => (2, *target, calldata.clone()),
+    };
+    
+    ethers::abi::Token::Tuple(vec![
+        ethers::abi::Token::Uint(U256::from(kind)),
+        ethers::abi::Token::Address(target),
+        ethers::abi::Token::Bytes(route_data.to_vec()),
+    ])
+}
+
+/// Comma-separated relay names for logs
+fn relay_names(relays: &[Arc<Relay>]) -> String {
+    relays.iter().map(|r| r.name()).collect::<Vec<_>>().join(", ")
 }
@@ -0,0 +1,278 @@
+//! Flash loan lender selection.
+//!
+//! v2 flash liquidators can borrow the debt asset from their Aave pool, the
+//! Balancer V2 vault, Morpho Blue or a Uniswap V3 pool. The fees differ:
+//! - Aave charges the pool's `FLASHLOAN_PREMIUM_TOTAL` (5 bps on V3 today)
+//! - Balancer charges its protocol flash loan fee (currently zero)
+//! - Uniswap V3 charges the pool's fee tier
+//! - Morpho Blue is free
+//!
+//! Each liquidation borrows from the cheapest lender holding enough of the
+//! asset, falling back to the Aave pool. v1 liquidators and the Compound and
+//! Venus contracts can only take Aave flash loans.
+
+use ethers::prelude::*;
+use ethers::providers::{Http, Provider};
+use ethers::types::{Address, U256};
+use futures::future::join_all;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::config::{ChainConfig, FlashLoanLenders, LiquidatorAbi};
+use crate::protocols::aave::IERC20;
+use crate::swap::{DexSource, SwapQuote, FEE_HIGH, FEE_LOW, FEE_LOWEST, FEE_MEDIUM};
+
+/// Aave V3 flash loan premium (bps) when the pool's can't be read
+pub const DEFAULT_AAVE_PREMIUM_BPS: u64 = 5;
+
+/// Fee denominator: fees are in millionths, like Uniswap V3 fee tiers
+const FEE_UNIT: u32 = 1_000_000;
+
+abigen!(
+    IBalancerVault,
+    r#"[
+        function getProtocolFeesCollector() external view returns (address)
+    ]"#
+);
+
+abigen!(
+    IBalancerFeesCollector,
+    r#"[
+        function getFlashLoanFeePercentage() external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    IUniswapV3Factory,
+    r#"[
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address)
+    ]"#
+);
+
+/// Flash loan lender kind (`FlashKind` in contracts/FlashLiquidatorV2.sol)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashSource {
+    Aave = 0,
+    Balancer = 1,
+    UniswapV3 = 2,
+    Morpho = 3,
+}
+
+impl std::fmt::Display for FlashSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlashSource::Aave => write!(f, "Aave"),
+            FlashSource::Balancer => write!(f, "Balancer"),
+            FlashSource::UniswapV3 => write!(f, "Uniswap V3"),
+            FlashSource::Morpho => write!(f, "Morpho"),
+        }
+    }
+}
+
+/// A flash loan from one lender
+#[derive(Debug, Clone, Copy)]
+pub struct FlashLoan {
+    pub source: FlashSource,
+    /// Aave pool, Balancer vault, Uniswap V3 pool or Morpho Blue
+    pub lender: Address,
+    /// Fee on the borrowed amount, in millionths (500 = 0.05%)
+    pub fee: u32,
+}
+
+impl FlashLoan {
+    /// Flash loan from an Aave pool charging `premium_bps`
+    pub fn aave(pool: Address, premium_bps: u64) -> Self {
+        Self {
+            source: FlashSource::Aave,
+            lender: pool,
+            fee: (premium_bps * 100) as u32,
+        }
+    }
+
+    /// Fee owed on borrowing `amount`, rounded up
+    pub fn fee_on(&self, amount: U256) -> U256 {
+        let unit = U256::from(FEE_UNIT);
+        (amount * U256::from(self.fee) + unit - 1) / unit
+    }
+}
+
+impl std::fmt::Display for FlashLoan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:.3}%)", self.source, self.fee as f64 / 10_000.0)
+    }
+}
+
+/// The swap a flash-funded liquidation will make, as far as it's known
+#[derive(Debug, Clone, Copy)]
+pub enum SwapPlan<'a> {
+    /// Collateral and debt are the same asset
+    NoSwap,
+    /// Swapping along this quote's route
+    Quoted(&'a SwapQuote),
+    /// No quote; the contract picks a DEX on-chain
+    Unknown,
+}
+
+/// A Uniswap V3 pool holding an asset
+#[derive(Debug, Clone, Copy)]
+struct UniswapPool {
+    pool: Address,
+    /// The pool's other token
+    pair: Address,
+    fee: u32,
+}
+
+/// Flash loan lenders of one chain
+#[derive(Debug)]
+pub struct FlashLenders {
+    lenders: FlashLoanLenders,
+    /// Tokens paired with the debt asset when looking for Uniswap pools
+    pair_tokens: Vec<Address>,
+    /// Balancer flash loan fee (millionths), read once
+    balancer_fee: RwLock<Option<u32>>,
+    /// Uniswap V3 pools holding each asset, lowest fee first
+    uniswap_pools: RwLock<HashMap<Address, Vec<UniswapPool>>>,
+}
+
+impl FlashLenders {
+    /// Lenders `config`'s liquidators can borrow from; none besides Aave for v1
+    pub fn new(config: &ChainConfig) -> Self {
+        let lenders = match config.aave_liquidator_abi {
+            LiquidatorAbi::V1 => FlashLoanLenders::default(),
+            LiquidatorAbi::V2 => config.flash_loans.clone(),
+        };
+
+        Self {
+            lenders,
+            pair_tokens: config.intermediate_tokens.clone(),
+            balancer_fee: RwLock::new(None),
+            uniswap_pools: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Cheapest flash loan of `amount` `asset`, or `aave` when no other lender
+    /// is cheaper and holds enough. Uniswap pools on `swap`'s route are
+    /// skipped (they're locked during their own flash), and so are all of them
+    /// when the route isn't known: behind an aggregator or unquoted.
+    pub async fn select(
+        &self,
+        provider: &Provider<Http>,
+        asset: Address,
+        amount: U256,
+        aave: FlashLoan,
+        swap: SwapPlan<'_>,
+    ) -> FlashLoan {
+        let mut candidates = Vec::new();
+
+        if let Some(morpho) = self.lenders.morpho {
+            candidates.push(FlashLoan { source: FlashSource::Morpho, lender: morpho, fee: 0 });
+        }
+        if let Some(vault) = self.lenders.balancer_vault {
+            match self.balancer_fee(provider, vault).await {
+                Ok(fee) => candidates.push(FlashLoan { source: FlashSource::Balancer, lender: vault, fee }),
+                Err(e) => debug!("Balancer flash fee unavailable: {}", e),
+            }
+        }
+
+        let route_hops = match swap {
+            SwapPlan::NoSwap => Some(Vec::new()),
+            SwapPlan::Quoted(quote) if quote.source == DexSource::UniswapV3 => Some(uniswap_hops(quote)),
+            SwapPlan::Quoted(_) | SwapPlan::Unknown => None,
+        };
+        if let Some(hops) = route_hops {
+            for pool in self.uniswap_pools(provider, asset).await {
+                let on_route = hops.iter().any(|(a, b, fee)| {
+                    *fee == pool.fee && ((*a, *b) == (asset, pool.pair) || (*b, *a) == (asset, pool.pair))
+                });
+                if !on_route {
+                    candidates.push(FlashLoan { source: FlashSource::UniswapV3, lender: pool.pool, fee: pool.fee });
+                }
+            }
+        }
+
+        candidates.retain(|c| c.fee < aave.fee);
+        if candidates.is_empty() {
+            return aave;
+        }
+        candidates.sort_by_key(|c| c.fee);
+
+        let token = IERC20::new(asset, Arc::new(provider.clone()));
+        let balances = join_all(
+            candidates.iter().map(|c| {
+                let call = token.balance_of(c.lender);
+                async move { call.call().await }
+            }),
+        ).await;
+
+        candidates
+            .into_iter()
+            .zip(balances)
+            .find(|(_, balance)| balance.as_ref().is_ok_and(|b| *b >= amount))
+            .map(|(loan, _)| loan)
+            .unwrap_or(aave)
+    }
+
+    async fn balancer_fee(&self, provider: &Provider<Http>, vault: Address) -> anyhow::Result<u32> {
+        if let Some(fee) = *self.balancer_fee.read() {
+            return Ok(fee);
+        }
+
+        let vault = IBalancerVault::new(vault, Arc::new(provider.clone()));
+        let collector = vault.get_protocol_fees_collector().call().await?;
+        let collector = IBalancerFeesCollector::new(collector, Arc::new(provider.clone()));
+        // 1e18 = 100%
+        let percentage = collector.get_flash_loan_fee_percentage().call().await?;
+        let fee = (percentage / U256::exp10(12)).as_u32();
+
+        *self.balancer_fee.write() = Some(fee);
+        Ok(fee)
+    }
+
+    /// Uniswap V3 pools pairing `asset` with the chain's intermediate tokens
+    async fn uniswap_pools(&self, provider: &Provider<Http>, asset: Address) -> Vec<UniswapPool> {
+        let factory = match self.lenders.uniswap_v3_factory {
+            Some(f) => f,
+            None => return Vec::new(),
+        };
+        if let Some(pools) = self.uniswap_pools.read().get(&asset) {
+            return pools.clone();
+        }
+
+        let factory = IUniswapV3Factory::new(factory, Arc::new(provider.clone()));
+        let lookups = self.pair_tokens
+            .iter()
+            .filter(|pair| **pair != asset)
+            .flat_map(|pair| {
+                [FEE_LOWEST, FEE_LOW, FEE_MEDIUM, FEE_HIGH].map(|fee| (*pair, fee))
+            })
+            .map(|(pair, fee)| {
+                let call = factory.get_pool(asset, pair, fee);
+                async move { (pair, fee, call.call().await) }
+            });
+
+        let mut pools: Vec<UniswapPool> = join_all(lookups)
+            .await
+            .into_iter()
+            .filter_map(|(pair, fee, pool)| match pool {
+                Ok(pool) if !pool.is_zero() => Some(UniswapPool { pool, pair, fee }),
+                _ => None,
+            })
+            .collect();
+        pools.sort_by_key(|p| p.fee);
+
+        debug!("Found {} Uniswap V3 flash pools for {:?}", pools.len(), asset);
+        self.uniswap_pools.write().insert(asset, pools.clone());
+        pools
+    }
+}
+
+/// (token in, token out, fee tier) of each hop of a Uniswap V3 quote
+fn uniswap_hops(quote: &SwapQuote) -> Vec<(Address, Address, u32)> {
+    quote.path
+        .windows(2)
+        .zip(&quote.fees)
+        .map(|(pair, fee)| (pair[0], pair[1], *fee))
+        .collect()
+}
@@ -0,0 +1,344 @@
+//! Gas pricing for liquidation transactions.
+//!
+//! On EIP-1559 chains the priority fee is bid from recent `eth_feeHistory`
+//! reward percentiles: small opportunities pay the median tip, large ones
+//! bid into the top of recent blocks to beat competing liquidators. How much
+//! we are willing to pay overall is the executor's bidding policy.
+//!
+//! Rollups also charge for posting calldata to L1, read from the OP-stack
+//! `GasPriceOracle`, Scroll's `L1GasPriceOracle` or Arbitrum's
+//! `NodeInterface`. Linea folds it into the priority fee its sequencer
+//! requires, and zkSync Era into the L2 gas `eth_estimateGas` returns.
+
+use ethers::prelude::*;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockNumber, Bytes, Eip1559TransactionRequest, TransactionRequest, U256};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::config::{ChainConfig, L1FeeModel};
+
+// OP-stack L1 fee predeploy (Scroll's L1GasPriceOracle has the same interface)
+abigen!(
+    IGasPriceOracle,
+    r#"[
+        function getL1Fee(bytes memory _data) external view returns (uint256)
+    ]"#
+);
+
+// Arbitrum NodeInterface (virtual contract, only callable via eth_call)
+abigen!(
+    INodeInterface,
+    r#"[
+        function gasEstimateL1Component(address to, bool contractCreation, bytes calldata data) external payable returns (uint64 gasEstimateForL1, uint256 baseFee, uint256 l1BaseFeeEstimate)
+    ]"#
+);
+
+/// OP-stack `GasPriceOracle` predeploy
+const OP_GAS_PRICE_ORACLE: &str = "0x420000000000000000000000000000000000000F";
+
+/// Scroll `L1GasPriceOracle` predeploy
+const SCROLL_L1_GAS_ORACLE: &str = "0x5300000000000000000000000000000000000002";
+
+/// Arbitrum `NodeInterface` address
+const ARB_NODE_INTERFACE: &str = "0x00000000000000000000000000000000000000C8";
+
+/// Gas limit headroom over `eth_estimateGas`, as a percentage
+const GAS_LIMIT_BUFFER_PERCENT: u64 = 120;
+
+/// zkSync Era estimates include pubdata at the current L1 price, which can
+/// move before inclusion (unused gas is refunded)
+const ZKSYNC_GAS_LIMIT_BUFFER_PERCENT: u64 = 150;
+
+/// Blocks of fee history sampled for the priority fee
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// Reward percentiles requested from `eth_feeHistory`
+const REWARD_PERCENTILES: [f64; 4] = [25.0, 50.0, 75.0, 95.0];
+
+/// Expected profit (USD) at which we move up a percentile tier
+const PROFIT_TIERS_USD: [f64; 3] = [100.0, 1_000.0, 10_000.0];
+
+/// Base fee headroom: max fee covers this many consecutive full blocks
+const BASE_FEE_MULTIPLIER: u64 = 2;
+
+/// Same-nonce replacement fees as a percentage of the original (geth requires +10%)
+const REPLACEMENT_BUMP_PERCENT: u64 = 112;
+
+/// How a transaction pays for gas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasPricing {
+    Legacy {
+        gas_price: U256,
+    },
+    Eip1559 {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+impl GasPricing {
+    /// Build a transaction with this pricing
+    pub fn build_tx(
+        &self,
+        to: Address,
+        data: Bytes,
+        gas_limit: U256,
+        value: U256,
+        nonce: u64,
+        chain_id: u64,
+    ) -> TypedTransaction {
+        match *self {
+            GasPricing::Legacy { gas_price } => TransactionRequest::new()
+                .to(to)
+                .data(data)
+                .gas(gas_limit)
+                .gas_price(gas_price)
+                .value(value)
+                .nonce(nonce)
+                .chain_id(chain_id)
+                .into(),
+            GasPricing::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+                Eip1559TransactionRequest::new()
+                    .to(to)
+                    .data(data)
+                    .gas(gas_limit)
+                    .max_fee_per_gas(max_fee_per_gas)
+                    .max_priority_fee_per_gas(max_priority_fee_per_gas)
+                    .value(value)
+                    .nonce(nonce)
+                    .chain_id(chain_id)
+                    .into()
+            }
+        }
+    }
+
+    /// Pricing a built transaction was signed with
+    pub fn of(tx: &TypedTransaction) -> Option<Self> {
+        match tx {
+            TypedTransaction::Eip1559(req) => Some(GasPricing::Eip1559 {
+                max_fee_per_gas: req.max_fee_per_gas?,
+                max_priority_fee_per_gas: req.max_priority_fee_per_gas?,
+            }),
+            _ => tx.gas_price().map(|gas_price| GasPricing::Legacy { gas_price }),
+        }
+    }
+
+    /// Re-price a built transaction
+    pub fn apply(&self, tx: &mut TypedTransaction) {
+        match (*self, tx) {
+            (
+                GasPricing::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas },
+                TypedTransaction::Eip1559(req),
+            ) => {
+                req.max_fee_per_gas = Some(max_fee_per_gas);
+                req.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+            }
+            (pricing, tx) => {
+                tx.set_gas_price(pricing.max_price_per_gas());
+            }
+        }
+    }
+
+    /// Most this pricing can pay per gas
+    pub fn max_price_per_gas(&self) -> U256 {
+        match *self {
+            GasPricing::Legacy { gas_price } => gas_price,
+            GasPricing::Eip1559 { max_fee_per_gas, .. } => max_fee_per_gas,
+        }
+    }
+
+    /// Minimum pricing a node accepts as a replacement for this one
+    pub fn bumped(&self) -> Self {
+        let bump = |v: U256| v * REPLACEMENT_BUMP_PERCENT / 100 + 1;
+        match *self {
+            GasPricing::Legacy { gas_price } => GasPricing::Legacy { gas_price: bump(gas_price) },
+            GasPricing::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => GasPricing::Eip1559 {
+                max_fee_per_gas: bump(max_fee_per_gas),
+                max_priority_fee_per_gas: bump(max_priority_fee_per_gas),
+            },
+        }
+    }
+
+    /// Field-wise maximum of two pricings of the same kind
+    pub fn at_least(&self, other: &Self) -> Self {
+        match (*self, *other) {
+            (
+                GasPricing::Eip1559 { max_fee_per_gas: a_max, max_priority_fee_per_gas: a_tip },
+                GasPricing::Eip1559 { max_fee_per_gas: b_max, max_priority_fee_per_gas: b_tip },
+            ) => GasPricing::Eip1559 {
+                max_fee_per_gas: a_max.max(b_max),
+                max_priority_fee_per_gas: a_tip.max(b_tip),
+            },
+            (GasPricing::Legacy { gas_price }, other) => GasPricing::Legacy {
+                gas_price: gas_price.max(other.max_price_per_gas()),
+            },
+            (this, _) => this,
+        }
+    }
+}
+
+/// Current network fees and the tip we'd like to pay
+#[derive(Debug, Clone, Copy)]
+pub struct FeeEstimate {
+    pub eip1559: bool,
+    /// Pending block base fee, or the legacy gas price
+    pub base_fee: U256,
+    /// Tip from the fee history percentile for this opportunity (0 for legacy)
+    pub priority_fee: U256,
+}
+
+impl FeeEstimate {
+    /// Raise the tip to at least what the sequencer requires
+    pub fn with_min_priority_fee(self, min_priority_fee: U256) -> Self {
+        Self { priority_fee: self.priority_fee.max(min_priority_fee), ..self }
+    }
+
+    /// Pricing that pays at most `max_price_per_gas` (never below the base fee)
+    pub fn bid(&self, max_price_per_gas: U256) -> GasPricing {
+        if !self.eip1559 {
+            return GasPricing::Legacy { gas_price: self.base_fee };
+        }
+
+        let max_price_per_gas = max_price_per_gas.max(self.base_fee);
+        let tip = self.priority_fee.min(max_price_per_gas - self.base_fee);
+        GasPricing::Eip1559 {
+            max_fee_per_gas: (self.base_fee * BASE_FEE_MULTIPLIER + tip).min(max_price_per_gas),
+            max_priority_fee_per_gas: tip,
+        }
+    }
+}
+
+impl std::fmt::Display for GasPricing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gwei = |v: &U256| v.as_u128() as f64 / 1e9;
+        match self {
+            GasPricing::Legacy { gas_price } => write!(f, "legacy {:.3} gwei", gwei(gas_price)),
+            GasPricing::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => write!(
+                f,
+                "max {:.3} gwei, tip {:.3} gwei",
+                gwei(max_fee_per_gas),
+                gwei(max_priority_fee_per_gas)
+            ),
+        }
+    }
+}
+
+/// L1 data component of a transaction's cost
+#[derive(Debug, Clone, Copy, Default)]
+pub struct L1Cost {
+    /// L1 data fee in wei
+    pub fee_wei: U256,
+    /// Part of `eth_estimateGas` that already pays for L1 data (Arbitrum
+    /// charges L1 data in L2 gas units; OP-stack charges it separately)
+    pub included_gas: U256,
+    /// Tip the sequencer requires to cover L1 data (Linea)
+    pub min_priority_fee: U256,
+}
+
+/// `linea_estimateGas` response
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LineaGasEstimate {
+    priority_fee_per_gas: U256,
+}
+
+/// Gas limit to send with, given an `eth_estimateGas` result
+pub fn gas_limit_with_buffer(chain: &ChainConfig, estimate: U256) -> U256 {
+    let buffer = match chain.l1_fee {
+        Some(L1FeeModel::ZkSync) => ZKSYNC_GAS_LIMIT_BUFFER_PERCENT,
+        _ => GAS_LIMIT_BUFFER_PERCENT,
+    };
+    estimate * buffer / 100
+}
+
+/// L1 data fee for sending `data` to `to` on a rollup (zero elsewhere)
+pub async fn l1_data_cost(
+    provider: &Provider<Http>,
+    chain: &ChainConfig,
+    to: Address,
+    data: &Bytes,
+    gas_limit: U256,
+) -> anyhow::Result<L1Cost> {
+    match chain.l1_fee {
+        // Pubdata is paid for in L2 gas, already part of the estimate
+        None | Some(L1FeeModel::ZkSync) => Ok(L1Cost::default()),
+        Some(model @ (L1FeeModel::OpStack | L1FeeModel::Scroll)) => {
+            let oracle_address = if model == L1FeeModel::Scroll {
+                SCROLL_L1_GAS_ORACLE
+            } else {
+                OP_GAS_PRICE_ORACLE
+            };
+            // The oracle prices the unsigned RLP-encoded transaction
+            let tx: TypedTransaction = Eip1559TransactionRequest::new()
+                .to(to)
+                .data(data.clone())
+                .gas(gas_limit)
+                .chain_id(chain.chain_id)
+                .into();
+            let oracle = IGasPriceOracle::new(oracle_address.parse::<Address>()?, Arc::new(provider.clone()));
+            let fee_wei = oracle.get_l1_fee(tx.rlp()).call().await?;
+            Ok(L1Cost { fee_wei, ..Default::default() })
+        }
+        Some(L1FeeModel::Linea) => {
+            let request = serde_json::json!({ "to": to, "data": data });
+            let estimate: LineaGasEstimate = provider.request("linea_estimateGas", [request]).await?;
+            Ok(L1Cost { min_priority_fee: estimate.priority_fee_per_gas, ..Default::default() })
+        }
+        Some(L1FeeModel::Arbitrum) => {
+            let node = INodeInterface::new(ARB_NODE_INTERFACE.parse::<Address>()?, Arc::new(provider.clone()));
+            let (l1_gas, base_fee, _) = node
+                .gas_estimate_l1_component(to, false, data.clone())
+                .call()
+                .await?;
+            let included_gas = U256::from(l1_gas);
+            Ok(L1Cost { fee_wei: included_gas * base_fee, included_gas, ..Default::default() })
+        }
+    }
+}
+
+/// Which reward percentile to bid for a given expected profit
+pub fn percentile_index(expected_profit_usd: f64) -> usize {
+    PROFIT_TIERS_USD
+        .iter()
+        .filter(|tier| expected_profit_usd >= **tier)
+        .count()
+}
+
+/// Network fees for a transaction expected to earn `expected_profit_usd`
+pub async fn estimate_fees(
+    provider: &Provider<Http>,
+    chain: &ChainConfig,
+    expected_profit_usd: f64,
+) -> anyhow::Result<FeeEstimate> {
+    if !chain.eip1559 {
+        let gas_price = provider.get_gas_price().await?;
+        return Ok(FeeEstimate { eip1559: false, base_fee: gas_price, priority_fee: U256::zero() });
+    }
+
+    let history = provider
+        .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &REWARD_PERCENTILES)
+        .await?;
+
+    // The last entry is the base fee of the pending block
+    let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+
+    let index = percentile_index(expected_profit_usd);
+    let mut rewards: Vec<U256> = history
+        .reward
+        .iter()
+        .filter_map(|block| block.get(index).copied())
+        .collect();
+    rewards.sort();
+    // Median across sampled blocks, so one outlier block doesn't set our bid
+    let priority_fee = rewards.get(rewards.len() / 2).copied().unwrap_or_default();
+
+    debug!(
+        "{}: base fee {} wei, p{} tip {} wei (profit ${:.2})",
+        chain.name, base_fee, REWARD_PERCENTILES[index], priority_fee, expected_profit_usd
+    );
+
+    Ok(FeeEstimate { eip1559: true, base_fee, priority_fee })
+}
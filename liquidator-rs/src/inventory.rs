@@ -0,0 +1,77 @@
+//! Wallet and contract inventory for self-funded liquidations.
+//!
+//! With `SELF_FUNDED=true`, a liquidation whose debt asset is already held by
+//! the v2 liquidator contract or the bot wallet skips the flash loan: no
+//! lender fee, and a direct protocol call is cheaper than the flash callback
+//! round trip. Wallet liquidations need the protocol approved to pull the
+//! asset; that approval is sent once, for the maximum amount.
+
+use ethers::providers::{Http, Provider};
+use ethers::types::{Address, Bytes, U256};
+use std::sync::Arc;
+
+use crate::protocols::aave::IERC20;
+
+/// Where a liquidation's debt asset comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Funding {
+    /// Flash loan through the liquidator contract
+    #[default]
+    FlashLoan,
+    /// Bot wallet balance, liquidating directly on the protocol
+    Wallet,
+    /// The v2 liquidator contract's own balance (`liquidateFromInventory`)
+    Contract(Address),
+}
+
+impl std::fmt::Display for Funding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Funding::FlashLoan => write!(f, "flash loan"),
+            Funding::Wallet => write!(f, "wallet inventory"),
+            Funding::Contract(_) => write!(f, "liquidator inventory"),
+        }
+    }
+}
+
+/// Funding for `amount` of `asset`: `contract`'s balance, then the wallet's,
+/// otherwise a flash loan
+pub async fn funding_for(
+    provider: &Provider<Http>,
+    wallet: Address,
+    contract: Option<Address>,
+    asset: Address,
+    amount: U256,
+) -> Funding {
+    let token = IERC20::new(asset, Arc::new(provider.clone()));
+
+    if let Some(contract) = contract {
+        if token.balance_of(contract).call().await.is_ok_and(|b| b >= amount) {
+            return Funding::Contract(contract);
+        }
+    }
+    if token.balance_of(wallet).call().await.is_ok_and(|b| b >= amount) {
+        return Funding::Wallet;
+    }
+    Funding::FlashLoan
+}
+
+/// How much of `token` `spender` may pull from `owner`
+pub async fn allowance(
+    provider: &Provider<Http>,
+    token: Address,
+    owner: Address,
+    spender: Address,
+) -> anyhow::Result<U256> {
+    let token = IERC20::new(token, Arc::new(provider.clone()));
+    Ok(token.allowance(owner, spender).call().await?)
+}
+
+/// `approve(spender, type(uint256).max)` calldata
+pub fn approve_max_calldata(provider: &Provider<Http>, token: Address, spender: Address) -> anyhow::Result<Bytes> {
+    let token = IERC20::new(token, Arc::new(provider.clone()));
+    token
+        .approve(spender, U256::MAX)
+        .calldata()
+        .ok_or_else(|| anyhow::anyhow!("Failed to encode approve calldata"))
+}
@@ -5,8 +5,6 @@
 
 use std::sync::Arc;
 use std::path::PathBuf;
-use ethers::types::Address;
-use ethers::providers::Middleware;
 use tokio::signal;
 use tokio::sync::mpsc;
 use tokio::time::{interval, Duration};
@@ -24,14 +22,28 @@ mod oracle;
 mod borrowers;
 mod scanner;
 mod swap;
+mod flashloan;
+mod inventory;
+mod simulator;
+mod multicall;
+mod gas;
+mod nonce;
+mod relays;
+mod receipts;
+mod pipeline;
+mod revert;
+mod validate;
 
 use config::Config;
 use chains::ChainManager;
 use health::HealthServer;
 use oracle::{OracleManager, PriceUpdate};
-use borrowers::{BorrowerStore, discover_aave_borrowers};
+use borrowers::{BorrowerStore, chain_sources};
 use discord::Discord;
 use scanner::Scanner;
+use pipeline::ExecutionPipeline;
+use receipts::ReceiptTracker;
+use types::Protocol;
 
 /// How many blocks back to scan for borrowers on first run
 const INITIAL_DISCOVERY_BLOCKS: u64 = 500_000;
@@ -63,6 +75,12 @@ async fn main() -> anyhow::Result<()> {
     let config = Config::from_env()?;
     info!("Configuration loaded");
     
+    // `liquidator validate` checks the registry against the chains and exits
+    if std::env::args().nth(1).as_deref() == Some("validate") {
+        let problems = validate::validate_config(&config).await;
+        std::process::exit(if problems == 0 { 0 } else { 1 });
+    }
+    
     if config.dry_run {
         warn!("🧪 DRY RUN MODE - No transactions will be sent");
     }
@@ -88,13 +106,19 @@ async fn main() -> anyhow::Result<()> {
         let store = borrower_store.read().await;
         for chain_ref in chain_manager.chains.iter() {
             let chain_name = chain_ref.key();
-            let count = store.get_aave_borrowers(chain_name).len();
-            info!("{}: {} borrowers", chain_name, count);
+            for (source, count) in store.chain_counts(chain_name) {
+                info!("{}: {} {} borrowers ({:?})", chain_name, count, source.protocol, source.market);
+            }
         }
     }
 
-    // Create scanner
-    let scanner = Arc::new(Scanner::new(chain_manager.clone(), &config));
+    // Start receipt tracking for sent liquidations
+    let (receipt_tracker, receipt_rx) = ReceiptTracker::channel();
+    tokio::spawn(receipts::run(chain_manager.clone(), receipt_rx));
+
+    // Create scanner and per-chain executors
+    let scanner = Arc::new(Scanner::new(chain_manager.clone(), &config, receipt_tracker));
+    let pipeline = Arc::new(ExecutionPipeline::start(scanner.clone(), chain_manager.clone()));
 
     // Create price update channel
     let (price_tx, mut price_rx) = mpsc::channel::<PriceUpdate>(1000);
@@ -122,6 +146,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Start periodic full scan (every 30 seconds)
     let scanner_periodic = scanner.clone();
+    let pipeline_periodic = pipeline.clone();
     let borrower_store_periodic = borrower_store.clone();
     let chain_manager_periodic = chain_manager.clone();
     tokio::spawn(async move {
@@ -132,17 +157,17 @@ async fn main() -> anyhow::Result<()> {
             let store = borrower_store_periodic.read().await;
             for chain_ref in chain_manager_periodic.chains.iter() {
                 let chain_name = chain_ref.key();
-                let borrowers = store.get_aave_borrowers(chain_name);
+                let count: usize = store.chain_counts(chain_name).iter().map(|(_, n)| n).sum();
                 
-                if borrowers.is_empty() {
+                if count == 0 {
                     continue;
                 }
                 
-                debug!("Periodic scan: {} ({} borrowers)", chain_name, borrowers.len());
-                let positions = scanner_periodic.scan_chain(chain_name, &borrowers).await;
+                debug!("Periodic scan: {} ({} borrowers)", chain_name, count);
+                let positions = scanner_periodic.scan_chain(chain_name, &store).await;
                 
                 if !positions.is_empty() {
-                    scanner_periodic.process_positions(positions).await;
+                    pipeline_periodic.submit(chain_name, positions);
                 }
             }
         }
@@ -161,24 +186,24 @@ async fn main() -> anyhow::Result<()> {
             let mut total_added = 0;
             
             for chain_ref in chain_manager_discovery.chains.iter() {
-                let chain_name = chain_ref.key();
                 let chain = chain_ref.value();
-                let pool_address: Address = chain.config.pool_address.parse().unwrap_or_default();
                 
-                match borrowers::update_borrowers(
-                    &chain.provider(),
-                    pool_address,
-                    chain_name,
-                    &mut store,
-                ).await {
-                    Ok(added) => {
-                        if added > 0 {
-                            info!("{}: Discovered {} new borrowers", chain_name, added);
-                            total_added += added;
+                for source in chain_sources(&chain.config) {
+                    match borrowers::update_borrowers(
+                        chain.provider(),
+                        &source,
+                        &mut store,
+                        INITIAL_DISCOVERY_BLOCKS,
+                    ).await {
+                        Ok(added) => {
+                            if added > 0 {
+                                info!("{}: Discovered {} new borrowers", source, added);
+                                total_added += added;
+                            }
+                        }
+                        Err(e) => {
+                            debug!("{}: Discovery error - {}", source, e);
                         }
-                    }
-                    Err(e) => {
-                        debug!("{}: Discovery error - {}", chain_name, e);
                     }
                 }
             }
@@ -194,6 +219,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Start price event handler (trigger scans on price updates)
     let scanner_price = scanner.clone();
+    let pipeline_price = pipeline.clone();
     let borrower_store_price = borrower_store.clone();
     let cm_for_prices = chain_manager.clone();
     tokio::spawn(async move {
@@ -201,6 +227,7 @@ async fn main() -> anyhow::Result<()> {
             &mut price_rx, 
             &cm_for_prices, 
             &scanner_price,
+            &pipeline_price,
             &borrower_store_price,
         ).await;
     });
@@ -249,65 +276,56 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Load borrowers from disk, or discover them if none exist
+/// Load borrowers from disk (migrating V7.5 files) and catch every source up
 async fn load_or_discover_borrowers(
     path: &PathBuf,
     chain_manager: &ChainManager,
 ) -> BorrowerStore {
-    // Try to load existing
-    let mut store = BorrowerStore::load(path).await;
+    // V7.5 files only tracked Aave borrowers per chain
+    let mut store = BorrowerStore::load(path, |chain_name| {
+        let chain = chain_manager.get_chain(chain_name)?;
+        chain_sources(&chain.config)
+            .into_iter()
+            .find(|source| source.protocol == Protocol::Aave)
+    }).await;
     
     if store.total_count() > 0 {
         info!("Loaded {} borrowers from {}", store.total_count(), path.display());
-        return store;
     }
     
-    // No borrowers - discover them
-    info!("🔍 No borrowers found - starting discovery...");
+    // Discover new sources from scratch and resume known ones
+    let mut total_added = 0;
     
     for chain_ref in chain_manager.chains.iter() {
-        let chain_name = chain_ref.key();
         let chain = chain_ref.value();
         
-        let pool_address: Address = match chain.config.pool_address.parse() {
-            Ok(a) => a,
-            Err(_) => continue,
-        };
-        
-        let current_block = match chain.provider().get_block_number().await {
-            Ok(b) => b.as_u64(),
-            Err(e) => {
-                error!("{}: Failed to get block number - {}", chain_name, e);
-                continue;
-            }
-        };
-        
-        let from_block = current_block.saturating_sub(INITIAL_DISCOVERY_BLOCKS);
-        
-        info!("{}: Discovering borrowers from block {} to {}...", 
-            chain_name, from_block, current_block);
-        
-        match discover_aave_borrowers(
-            &chain.provider(),
-            pool_address,
-            chain_name,
-            from_block,
-            Some(current_block),
-        ).await {
-            Ok(borrowers) => {
-                let count = borrowers.len();
-                store.add_borrowers(chain_name, borrowers);
-                store.last_scanned_block.insert(chain_name.clone(), current_block);
-                info!("{}: Discovered {} borrowers", chain_name, count);
+        for source in chain_sources(&chain.config) {
+            if store.last_scanned_block(&source) == 0 {
+                info!("🔍 {}: Discovering borrowers ({} blocks back)...", 
+                    source, INITIAL_DISCOVERY_BLOCKS);
             }
-            Err(e) => {
-                error!("{}: Discovery failed - {}", chain_name, e);
+            
+            match borrowers::update_borrowers(
+                chain.provider(),
+                &source,
+                &mut store,
+                INITIAL_DISCOVERY_BLOCKS,
+            ).await {
+                Ok(added) => {
+                    if added > 0 {
+                        info!("{}: Discovered {} borrowers", source, added);
+                    }
+                    total_added += added;
+                }
+                Err(e) => {
+                    error!("{}: Discovery failed - {}", source, e);
+                }
             }
         }
     }
     
-    // Save discovered borrowers
-    if store.total_count() > 0 {
+    // Save discovered borrowers (also persists per-source scan progress)
+    if total_added > 0 || store.total_count() > 0 {
         if let Err(e) = store.save(path).await {
             warn!("Failed to save discovered borrowers: {}", e);
         } else {
@@ -323,6 +341,7 @@ async fn handle_price_updates(
     price_rx: &mut mpsc::Receiver<PriceUpdate>,
     chain_manager: &ChainManager,
     scanner: &Scanner,
+    pipeline: &ExecutionPipeline,
     borrower_store: &tokio::sync::RwLock<BorrowerStore>,
 ) {
     while let Some(update) = price_rx.recv().await {
@@ -340,21 +359,19 @@ async fn handle_price_updates(
         
         // Get borrowers for this chain
         let store = borrower_store.read().await;
-        let borrowers = store.get_aave_borrowers(&update.chain);
-        
-        if borrowers.is_empty() {
+        if store.chain_counts(&update.chain).is_empty() {
             continue;
         }
         
         // Scan positions on this chain
-        let positions = scanner.scan_chain(&update.chain, &borrowers).await;
+        let positions = scanner.scan_chain(&update.chain, &store).await;
         
         if !positions.is_empty() {
             info!(
                 "📊 {} scan: {} positions checked",
                 update.chain, positions.len()
             );
-            scanner.process_positions(positions).await;
+            pipeline.submit(&update.chain, positions);
         }
     }
 }
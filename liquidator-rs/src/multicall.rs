@@ -0,0 +1,74 @@
+//! Multicall3 `aggregate3` batching.
+//!
+//! Reads that used to be one RPC per market/reserve go out as a single
+//! `eth_call`. Each call sets `allowFailure`, so one reverting market
+//! (paused reserve, broken oracle feed) only loses its own result.
+
+use ethers::abi::{self, ParamType, Token};
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::{Address, Bytes, TransactionRequest};
+
+/// Calls per `aggregate3` request, to stay under node gas/response limits
+const MAX_CALLS_PER_BATCH: usize = 500;
+
+/// Execute `(target, calldata)` pairs through Multicall3 `aggregate3`.
+///
+/// Results are returned in call order; `None` for calls that reverted.
+/// Errors only when the RPC request itself fails.
+pub async fn aggregate3(
+    provider: &Provider<Http>,
+    multicall: Address,
+    calls: &[(Address, Bytes)],
+) -> anyhow::Result<Vec<Option<Bytes>>> {
+    let selector = ethers::utils::id("aggregate3((address,bool,bytes)[])");
+    let result_type = ParamType::Array(Box::new(ParamType::Tuple(vec![
+        ParamType::Bool,
+        ParamType::Bytes,
+    ])));
+
+    let mut results = Vec::with_capacity(calls.len());
+
+    for batch in calls.chunks(MAX_CALLS_PER_BATCH) {
+        let encoded_calls = batch
+            .iter()
+            .map(|(target, data)| Token::Tuple(vec![
+                Token::Address(*target),
+                Token::Bool(true),
+                Token::Bytes(data.to_vec()),
+            ]))
+            .collect();
+
+        let mut data = selector[0..4].to_vec();
+        data.extend_from_slice(&abi::encode(&[Token::Array(encoded_calls)]));
+
+        let tx = TransactionRequest::new().to(multicall).data(data);
+        let output = provider.call(&tx.into(), None).await?;
+
+        let decoded = abi::decode(std::slice::from_ref(&result_type), &output)?;
+        let entries = match decoded.into_iter().next() {
+            Some(Token::Array(entries)) => entries,
+            _ => anyhow::bail!("unexpected aggregate3 response"),
+        };
+        if entries.len() != batch.len() {
+            anyhow::bail!("aggregate3 returned {} results for {} calls", entries.len(), batch.len());
+        }
+
+        for entry in entries {
+            let result = match entry {
+                Token::Tuple(fields) => match fields.as_slice() {
+                    [Token::Bool(true), Token::Bytes(data)] => Some(Bytes::from(data.clone())),
+                    _ => None,
+                },
+                _ => None,
+            };
+            results.push(result);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Decode a call result with the abigen-generated return type
+pub fn decode<T: ethers::abi::AbiDecode>(result: &Option<Bytes>) -> Option<T> {
+    result.as_ref().and_then(|data| T::decode(data).ok())
+}
@@ -0,0 +1,294 @@
+//! Per-chain nonce management for the bot wallet.
+//!
+//! Nonces are handed out locally so concurrent liquidations don't wait on
+//! `eth_getTransactionCount`, and every transaction we send is tracked until
+//! the chain mines past its nonce. A periodic sweep keeps the wallet moving:
+//! - stuck transactions are re-sent at the same nonce with bumped fees
+//! - liquidations that no longer simulate (or outgrew their gas budget) are
+//!   cancelled with a zero-value self-transfer
+//! - nonces lost to failed sends are filled, or handed out again when nothing
+//!   is queued behind them
+
+use ethers::providers::Middleware;
+use ethers::signers::Signer;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockNumber, Bytes, TxHash, U256};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::chains::ChainState;
+use crate::gas::{self, FeeEstimate, GasPricing};
+
+/// Public transactions unmined after this long get replaced
+const STUCK_AFTER: Duration = Duration::from_secs(45);
+
+/// Flashbots Protect keeps retrying for ~25 blocks before dropping a transaction
+const PRIVATE_STUCK_AFTER: Duration = Duration::from_secs(300);
+
+/// Fee bumps for a liquidation before we give up and cancel it
+const MAX_REPLACEMENTS: u32 = 5;
+
+/// Gas limit of a plain self-transfer
+const CANCEL_GAS: u64 = 21_000;
+
+/// A transaction sent from the bot wallet that hasn't been mined yet
+#[derive(Debug, Clone)]
+pub struct PendingTx {
+    pub hash: TxHash,
+    /// Unsigned transaction, kept for re-pricing
+    pub tx: TypedTransaction,
+    pub pricing: GasPricing,
+    /// Most a liquidation may pay per gas before it's cancelled instead
+    pub max_price_per_gas: U256,
+    /// Sent through a private relay rather than the public mempool
+    pub private: bool,
+    /// Zero-value self-transfer freeing the nonce
+    pub cancel: bool,
+    pub sent_at: Instant,
+    pub replacements: u32,
+}
+
+#[derive(Debug, Default)]
+struct NonceState {
+    next: u64,
+    /// Handed out, not sent yet
+    reserved: BTreeSet<u64>,
+    /// Sent, not mined yet
+    pending: BTreeMap<u64, PendingTx>,
+}
+
+/// Nonce allocation and in-flight transaction tracking for one wallet
+#[derive(Debug)]
+pub struct NonceManager {
+    state: Mutex<NonceState>,
+}
+
+impl NonceManager {
+    pub fn new(next: u64) -> Self {
+        Self {
+            state: Mutex::new(NonceState { next, ..Default::default() }),
+        }
+    }
+
+    /// Hand out the next nonce
+    pub fn reserve(&self) -> u64 {
+        let mut state = self.state.lock();
+        let nonce = state.next;
+        state.next += 1;
+        state.reserved.insert(nonce);
+        nonce
+    }
+
+    /// Give back a nonce whose transaction never reached a node
+    pub fn release(&self, nonce: u64) {
+        let mut state = self.state.lock();
+        if state.reserved.remove(&nonce) && state.next == nonce + 1 {
+            state.next = nonce;
+        }
+    }
+
+    /// Track a transaction a node accepted
+    pub fn track(&self, tx: &TypedTransaction, hash: TxHash, max_price_per_gas: U256, private: bool) {
+        let (nonce, pricing) = match (tx.nonce(), GasPricing::of(tx)) {
+            (Some(nonce), Some(pricing)) => (nonce.as_u64(), pricing),
+            _ => return,
+        };
+
+        let mut state = self.state.lock();
+        state.reserved.remove(&nonce);
+        state.pending.insert(nonce, PendingTx {
+            hash,
+            tx: tx.clone(),
+            pricing,
+            max_price_per_gas,
+            private,
+            cancel: false,
+            sent_at: Instant::now(),
+            replacements: 0,
+        });
+    }
+
+    /// Transactions sent and not yet mined
+    pub fn pending_count(&self) -> usize {
+        self.state.lock().pending.len()
+    }
+
+    /// Latest hash sent at `nonce`, while it's unmined
+    pub fn pending_hash(&self, nonce: u64) -> Option<TxHash> {
+        self.state.lock().pending.get(&nonce).map(|tx| tx.hash)
+    }
+
+    /// Drop everything below the on-chain nonce and find nonces nothing was
+    /// sent for. Returns gaps that block queued transactions; trailing gaps
+    /// are handed out again.
+    fn reconcile(&self, mined: u64) -> Vec<u64> {
+        let mut state = self.state.lock();
+        state.pending = state.pending.split_off(&mined);
+        state.reserved.retain(|nonce| *nonce >= mined);
+
+        let highest = state.pending.keys().chain(state.reserved.iter()).max().copied();
+        let next = highest.map_or(mined, |nonce| nonce + 1);
+        if next != state.next {
+            info!("🔢 Nonce resync: {} -> {} (on-chain {})", state.next, next, mined);
+            state.next = next;
+        }
+
+        (mined..next)
+            .filter(|nonce| !state.pending.contains_key(nonce) && !state.reserved.contains(nonce))
+            .collect()
+    }
+
+    /// Record a replacement sent for `nonce`
+    fn replaced(&self, nonce: u64, replacement: PendingTx) {
+        self.state.lock().pending.insert(nonce, replacement);
+    }
+
+    fn stuck(&self) -> Vec<(u64, PendingTx)> {
+        self.state
+            .lock()
+            .pending
+            .iter()
+            .filter(|(_, tx)| {
+                let limit = if tx.private { PRIVATE_STUCK_AFTER } else { STUCK_AFTER };
+                tx.sent_at.elapsed() >= limit
+            })
+            .map(|(nonce, tx)| (*nonce, tx.clone()))
+            .collect()
+    }
+}
+
+/// Resync, fill nonce gaps and replace stuck transactions for one chain
+pub async fn manage_pending(chain: &ChainState) -> anyhow::Result<()> {
+    let mined = chain.provider()
+        .get_transaction_count(chain.wallet.address(), Some(BlockNumber::Latest.into()))
+        .await?
+        .as_u64();
+
+    let gaps = chain.nonces.reconcile(mined);
+    let stuck = chain.nonces.stuck();
+    if gaps.is_empty() && stuck.is_empty() {
+        return Ok(());
+    }
+
+    let fees = gas::estimate_fees(chain.provider(), &chain.config, 0.0).await?;
+
+    for nonce in gaps {
+        warn!("🔢 {}: nonce {} was never sent, filling it", chain.config.name, nonce);
+        let pricing = fees.bid(U256::MAX);
+        if let Err(e) = send_cancel(chain, nonce, pricing, 0).await {
+            warn!("{}: failed to fill nonce {}: {}", chain.config.name, nonce, e);
+        }
+    }
+
+    for (nonce, pending) in stuck {
+        if let Err(e) = replace_stuck(chain, nonce, &pending, &fees).await {
+            warn!("{}: failed to replace tx {:?} (nonce {}): {}", chain.config.name, pending.hash, nonce, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Bump a stuck liquidation, or cancel it once it's no longer worth landing
+async fn replace_stuck(
+    chain: &ChainState,
+    nonce: u64,
+    pending: &PendingTx,
+    fees: &FeeEstimate,
+) -> anyhow::Result<()> {
+    let pricing = pending.pricing.bumped().at_least(&fees.bid(U256::MAX));
+    let replacements = pending.replacements + 1;
+
+    let cancel_reason = if pending.cancel {
+        Some("cancellation still pending")
+    } else if pending.private {
+        Some("dropped by private relay")
+    } else if pending.replacements >= MAX_REPLACEMENTS {
+        Some("too many replacements")
+    } else if pricing.max_price_per_gas() > pending.max_price_per_gas {
+        Some("gas above bid ceiling")
+    } else if !still_executes(chain, &pending.tx).await {
+        Some("opportunity gone")
+    } else {
+        None
+    };
+
+    if let Some(reason) = cancel_reason {
+        warn!(
+            "🚫 {}: cancelling tx {:?} (nonce {}): {}",
+            chain.config.name, pending.hash, nonce, reason
+        );
+        return send_cancel(chain, nonce, pricing, replacements).await;
+    }
+
+    let mut tx = pending.tx.clone();
+    pricing.apply(&mut tx);
+    let hash = sign_and_send(chain, &tx).await?;
+    info!(
+        "⛽ {}: replaced stuck tx {:?} -> {:?} (nonce {}, {})",
+        chain.config.name, pending.hash, hash, nonce, pricing
+    );
+
+    chain.nonces.replaced(nonce, PendingTx {
+        hash,
+        tx,
+        pricing,
+        sent_at: Instant::now(),
+        replacements,
+        ..pending.clone()
+    });
+    Ok(())
+}
+
+/// Whether a pending liquidation would still succeed if mined now
+async fn still_executes(chain: &ChainState, tx: &TypedTransaction) -> bool {
+    let mut call = tx.clone();
+    call.set_from(chain.wallet.address());
+    match chain.provider().call(&call, None).await {
+        Ok(_) => true,
+        Err(e) => {
+            debug!("{}: pending tx no longer simulates: {}", chain.config.name, e);
+            false
+        }
+    }
+}
+
+/// Zero-value self-transfer at `nonce`
+async fn send_cancel(
+    chain: &ChainState,
+    nonce: u64,
+    pricing: GasPricing,
+    replacements: u32,
+) -> anyhow::Result<()> {
+    let wallet = chain.wallet.address();
+    let tx = pricing.build_tx(
+        wallet,
+        Bytes::default(),
+        U256::from(CANCEL_GAS),
+        U256::zero(),
+        nonce,
+        chain.config.chain_id,
+    );
+    let hash = sign_and_send(chain, &tx).await?;
+    info!("🚫 {}: cancel tx {:?} (nonce {}, {})", chain.config.name, hash, nonce, pricing);
+
+    chain.nonces.replaced(nonce, PendingTx {
+        hash,
+        tx,
+        pricing,
+        max_price_per_gas: U256::MAX,
+        private: false,
+        cancel: true,
+        sent_at: Instant::now(),
+        replacements,
+    });
+    Ok(())
+}
+
+async fn sign_and_send(chain: &ChainState, tx: &TypedTransaction) -> anyhow::Result<TxHash> {
+    let signature = chain.wallet.sign_transaction(tx).await?;
+    let pending = chain.provider().send_raw_transaction(tx.rlp_signed(&signature)).await?;
+    Ok(pending.tx_hash())
+}
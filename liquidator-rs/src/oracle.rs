@@ -6,11 +6,14 @@
 //! - Automatic reconnection with exponential backoff
 //! - Cross-validation of prices between providers
 //! - Health monitoring per connection
+//! - Mempool watching for Chainlink updates to backrun (bundle chains only)
 
+use ethers::contract::abigen;
+use ethers::providers::{Http, Middleware, Provider, Ws};
 use ethers::types::Address;
 use futures::{SinkExt, StreamExt};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{Duration, Instant};
@@ -21,6 +24,8 @@ use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{debug, error, info, warn};
 
 use crate::chains::ChainManager;
+use crate::config::{ChainConfig, MevSubmission};
+use crate::types::PendingOracleUpdate;
 
 /// Maximum price deviation allowed between providers (5%)
 const MAX_PRICE_DEVIATION: f64 = 0.05;
@@ -28,59 +33,31 @@ const MAX_PRICE_DEVIATION: f64 = 0.05;
 /// Health check interval for WS connections
 const HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
 
-/// Chainlink price feed addresses by chain and token
-pub fn get_chainlink_feeds() -> HashMap<String, HashMap<Address, Address>> {
-    let mut feeds: HashMap<String, HashMap<Address, Address>> = HashMap::new();
-    
-    // Base
-    let mut base_feeds = HashMap::new();
-    base_feeds.insert(
-        "0x4200000000000000000000000000000000000006".parse().unwrap(), // WETH
-        "0x71041dddad3595F9CEd3DcCFBe3D1F4b0a16Bb70".parse().unwrap(),
-    );
-    base_feeds.insert(
-        "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913".parse().unwrap(), // USDC
-        "0x7e860098F58bBFC8648a4311b374B1D669a2bc6B".parse().unwrap(),
-    );
-    feeds.insert("base".to_string(), base_feeds);
-    
-    // Polygon
-    let mut polygon_feeds = HashMap::new();
-    polygon_feeds.insert(
-        "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619".parse().unwrap(), // WETH
-        "0xF9680D99D6C9589e2a93a78A04A279e509205945".parse().unwrap(),
-    );
-    polygon_feeds.insert(
-        "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270".parse().unwrap(), // WMATIC
-        "0xAB594600376Ec9fD91F8e885dADF0CE036862dE0".parse().unwrap(),
-    );
-    feeds.insert("polygon".to_string(), polygon_feeds);
-    
-    // Arbitrum
-    let mut arb_feeds = HashMap::new();
-    arb_feeds.insert(
-        "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1".parse().unwrap(), // WETH
-        "0x639Fe6ab55C921f74e7fac1ee960C0B6293ba612".parse().unwrap(),
-    );
-    feeds.insert("arbitrum".to_string(), arb_feeds);
-    
-    // Avalanche
-    let mut avax_feeds = HashMap::new();
-    avax_feeds.insert(
-        "0xB31f66AA3C1e785363F0875A1B74E27b85FD66c7".parse().unwrap(), // WAVAX
-        "0x0A77230d17318075983913bC2145DB16C7366156".parse().unwrap(),
-    );
-    feeds.insert("avalanche".to_string(), avax_feeds);
-    
-    // BNB Chain
-    let mut bnb_feeds = HashMap::new();
-    bnb_feeds.insert(
-        "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c".parse().unwrap(), // WBNB
-        "0x0567F2323251f0Aab15c8dFb1967E4e8A7D42aeE".parse().unwrap(),
-    );
-    feeds.insert("bnb".to_string(), bnb_feeds);
-    
-    feeds
+/// How often feed proxies are checked for a new aggregator phase
+const AGGREGATOR_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
+// Chainlink feed proxy (EACAggregatorProxy)
+abigen!(
+    IAggregatorProxy,
+    r#"[
+        function aggregator() external view returns (address)
+        function phaseId() external view returns (uint16)
+    ]"#
+);
+
+/// Chainlink price feed addresses by chain and token, from the chain registry
+pub fn get_chainlink_feeds(chains: &[ChainConfig]) -> HashMap<String, HashMap<Address, Address>> {
+    chains
+        .iter()
+        .filter(|chain| !chain.chainlink_feeds.is_empty())
+        .map(|chain| {
+            let feeds = chain.chainlink_feeds
+                .iter()
+                .map(|f| (f.token, f.feed))
+                .collect();
+            (chain.name.clone(), feeds)
+        })
+        .collect()
 }
 
 /// Price update event
@@ -158,6 +135,50 @@ struct WsLog {
     data: String,
 }
 
+/// Underlying aggregators of Chainlink feed proxies. OCR `transmit`
+/// transactions go to the aggregator, not the proxy the feed is configured by.
+#[derive(Default)]
+struct FeedAggregators {
+    /// Aggregator -> feed proxy
+    proxies: HashMap<Address, Address>,
+    /// Feed proxy -> (phase, aggregator)
+    phases: HashMap<Address, (u16, Address)>,
+}
+
+impl FeedAggregators {
+    /// Resolve the aggregator of every proxy that is new or changed phase.
+    /// Feeds that aren't proxies are watched at their own address.
+    async fn refresh(&mut self, chain: &str, provider: &Provider<Http>, feeds: &HashSet<Address>) {
+        for proxy in feeds {
+            let contract = IAggregatorProxy::new(*proxy, Arc::new(provider.clone()));
+            let phase = match contract.phase_id().call().await {
+                Ok(p) => p,
+                Err(e) => {
+                    debug!("{}: {:?} has no phaseId ({}), watching it directly", chain, proxy, e);
+                    self.proxies.entry(*proxy).or_insert(*proxy);
+                    continue;
+                }
+            };
+            if self.phases.get(proxy).is_some_and(|(known, _)| *known == phase) {
+                continue;
+            }
+            let aggregator = match contract.aggregator().call().await {
+                Ok(a) => a,
+                Err(e) => {
+                    warn!("{}: Failed to read aggregator of {:?}: {}", chain, proxy, e);
+                    continue;
+                }
+            };
+            
+            if let Some((_, old)) = self.phases.insert(*proxy, (phase, aggregator)) {
+                self.proxies.remove(&old);
+                info!("{}: Feed {:?} moved to phase {} (aggregator {:?})", chain, proxy, phase, aggregator);
+            }
+            self.proxies.insert(aggregator, *proxy);
+        }
+    }
+}
+
 /// Oracle manager with multi-provider redundancy
 pub struct OracleManager {
     chain_manager: Arc<ChainManager>,
@@ -174,10 +195,12 @@ impl OracleManager {
         chain_manager: Arc<ChainManager>,
         price_tx: mpsc::Sender<PriceUpdate>,
     ) -> Self {
+        let feeds = get_chainlink_feeds(&chain_manager.config.chains);
+        
         Self {
             chain_manager,
             price_tx,
-            feeds: get_chainlink_feeds(),
+            feeds,
             connection_health: Arc::new(RwLock::new(HashMap::new())),
             last_prices: Arc::new(RwLock::new(HashMap::new())),
         }
@@ -230,6 +253,22 @@ impl OracleManager {
             }
             
             info!("  ✅ {}: {} feeds on {} providers", chain, feeds.len(), ws_urls.len());
+            
+            // Watch the mempool for updates to these feeds when bundles can backrun them
+            let backrun = self.chain_manager
+                .get_chain(chain)
+                .map(|c| c.config.mev == MevSubmission::Bundle && c.config.backrun_oracle_updates)
+                .unwrap_or(false);
+            if backrun {
+                let chain_clone = chain.clone();
+                let ws_url = ws_urls[0].clone();
+                let feed_set: HashSet<Address> = feeds.values().copied().collect();
+                let cm = self.chain_manager.clone();
+                
+                tokio::spawn(async move {
+                    Self::watch_oracle_mempool(chain_clone, ws_url, feed_set, cm).await;
+                });
+            }
         }
         
         // Start health monitoring
@@ -396,9 +435,12 @@ impl OracleManager {
                                         continue;
                                     }
                                     
-                                    // Update chain state
+                                    // Update chain state; the feed's pending update (if any) is now mined
                                     if let Some(chain_state) = chain_manager.get_chain(chain) {
                                         chain_state.set_price(*token, price);
+                                        if let Some(feed) = feeds.get(token) {
+                                            chain_state.oracle_updates.remove(feed);
+                                        }
                                     }
                                     
                                     chain_manager.stats.write().events += 1;
@@ -444,6 +486,88 @@ impl OracleManager {
         Ok(())
     }
     
+    /// Keep the chain's pending oracle updates current from the mempool
+    async fn watch_oracle_mempool(
+        chain: String,
+        ws_url: String,
+        feeds: HashSet<Address>,
+        chain_manager: Arc<ChainManager>,
+    ) {
+        let mut retry_delay = Duration::from_secs(1);
+        let max_retry_delay = Duration::from_secs(60);
+        let mut aggregators = FeedAggregators::default();
+        
+        loop {
+            match Self::subscribe_oracle_updates(&chain, &ws_url, &feeds, &mut aggregators, &chain_manager).await {
+                Ok(_) => {
+                    retry_delay = Duration::from_secs(1);
+                }
+                Err(e) => {
+                    error!("{}: Mempool subscription error - {}", chain, e);
+                }
+            }
+            
+            debug!("{}: Resubscribing to mempool in {:?}...", chain, retry_delay);
+            tokio::time::sleep(retry_delay).await;
+            
+            retry_delay = (retry_delay * 2).min(max_retry_delay);
+        }
+    }
+    
+    async fn subscribe_oracle_updates(
+        chain: &str,
+        ws_url: &str,
+        feeds: &HashSet<Address>,
+        aggregators: &mut FeedAggregators,
+        chain_manager: &Arc<ChainManager>,
+    ) -> anyhow::Result<()> {
+        let chain_state = chain_manager
+            .get_chain(chain)
+            .ok_or_else(|| anyhow::anyhow!("unknown chain {}", chain))?;
+        aggregators.refresh(chain, chain_state.provider(), feeds).await;
+        
+        let provider = timeout(Duration::from_secs(10), Provider::<Ws>::connect(ws_url)).await??;
+        let mut pending = provider.subscribe_full_pending_txs().await?;
+        
+        info!(
+            "  👁️ {}: Watching mempool for {} oracle feeds ({} aggregators)",
+            chain, feeds.len(), aggregators.proxies.len()
+        );
+        
+        let mut refresh = tokio::time::interval_at(
+            tokio::time::Instant::now() + AGGREGATOR_REFRESH_INTERVAL,
+            AGGREGATOR_REFRESH_INTERVAL,
+        );
+        
+        loop {
+            tokio::select! {
+                tx = pending.next() => {
+                    let tx = match tx {
+                        Some(tx) => tx,
+                        None => break,
+                    };
+                    // Updates are keyed by the feed proxy the chain config knows
+                    let feed = match tx.to.and_then(|to| aggregators.proxies.get(&to)) {
+                        Some(proxy) => *proxy,
+                        None => continue,
+                    };
+                    
+                    debug!("{}: Pending oracle update {:?} for feed {:?}", chain, tx.hash, feed);
+                    chain_state.oracle_updates.insert(feed, PendingOracleUpdate {
+                        hash: tx.hash,
+                        raw: tx.rlp(),
+                        seen_at: Instant::now(),
+                    });
+                }
+                _ = refresh.tick() => {
+                    aggregators.refresh(chain, chain_state.provider(), feeds).await;
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
     /// Validate price against other providers (returns true if valid)
     fn validate_price(
         chain: &str,
@@ -0,0 +1,71 @@
+//! Per-chain execution pipeline.
+//!
+//! Each chain gets its own executor task, so a slow chain never holds up
+//! liquidations on another one. Scans hand their results over and move on.
+//! A chain keeps only its latest scan waiting: a newer one replaces it, since
+//! executing stale positions would only delay the fresh ones.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::{debug, info};
+
+use crate::chains::ChainManager;
+use crate::scanner::Scanner;
+use crate::types::Position;
+
+/// The scan waiting for one chain's executor
+#[derive(Default)]
+struct PendingScan {
+    positions: Mutex<Option<Vec<Position>>>,
+    ready: Notify,
+}
+
+/// Hand-off points of the per-chain executor tasks
+pub struct ExecutionPipeline {
+    pending: HashMap<String, Arc<PendingScan>>,
+    chain_manager: Arc<ChainManager>,
+}
+
+impl ExecutionPipeline {
+    /// Spawn an executor task for every chain
+    pub fn start(scanner: Arc<Scanner>, chain_manager: Arc<ChainManager>) -> Self {
+        let mut pending = HashMap::new();
+
+        for chain_ref in chain_manager.chains.iter() {
+            let scan = Arc::new(PendingScan::default());
+            let worker_scan = scan.clone();
+            let scanner = scanner.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    worker_scan.ready.notified().await;
+                    let positions = worker_scan.positions.lock().take();
+                    if let Some(positions) = positions {
+                        scanner.process_positions(positions).await;
+                    }
+                }
+            });
+            pending.insert(chain_ref.key().clone(), scan);
+        }
+
+        info!("⚙️ Execution pipeline: {} chain executors", pending.len());
+        Self { pending, chain_manager }
+    }
+
+    /// Hand `positions` to `chain`'s executor without waiting on it,
+    /// replacing a scan it hasn't picked up yet
+    pub fn submit(&self, chain: &str, positions: Vec<Position>) {
+        let scan = match self.pending.get(chain) {
+            Some(s) => s,
+            None => return,
+        };
+
+        if scan.positions.lock().replace(positions).is_some() {
+            debug!("{}: executor busy, replacing its queued scan", chain);
+            self.chain_manager.stats.write().dropped_scans += 1;
+        }
+        scan.ready.notify_one();
+    }
+}
@@ -5,8 +5,16 @@ use ethers::providers::{Provider, Http, Middleware};
 use ethers::types::{Address, U256, Bytes};
 use std::sync::Arc;
 use std::collections::HashMap;
+use parking_lot::RwLock;
 use tracing::{debug, info, warn};
 
+use crate::config::ChainConfig;
+use crate::flashloan::{FlashLenders, FlashLoan, SwapPlan, DEFAULT_AAVE_PREMIUM_BPS};
+use crate::multicall;
+use crate::simulator::{
+    base_to_usd, simulate_aave_liquidation, u256_to_f64, AaveLiquidationInput, AaveLiquidationResult,
+};
+use crate::swap::{MultiDexQuoter, SwapQuote};
 use crate::types::{Position, Protocol, Asset};
 
 // Aave Pool ABI
@@ -15,6 +23,32 @@ abigen!(
     r#"[
         function getUserAccountData(address user) external view returns (uint256 totalCollateralBase, uint256 totalDebtBase, uint256 availableBorrowsBase, uint256 currentLiquidationThreshold, uint256 ltv, uint256 healthFactor)
         function getReservesList() external view returns (address[] memory)
+        function ADDRESSES_PROVIDER() external view returns (address)
+        function FLASHLOAN_PREMIUM_TOTAL() external view returns (uint128)
+        function getUserEMode(address user) external view returns (uint256)
+        function getEModeCategoryData(uint8 id) external view returns ((uint16,uint16,uint16,address,string))
+        function getEModeCategoryCollateralBitmap(uint8 id) external view returns (uint128)
+        function getReserveAddressById(uint16 id) external view returns (address)
+        function liquidationCall(address collateralAsset, address debtAsset, address user, uint256 debtToCover, bool receiveAToken) external
+    ]"#
+);
+
+// Aave PoolAddressesProvider ABI
+abigen!(
+    IPoolAddressesProvider,
+    r#"[
+        function getPriceOracle() external view returns (address)
+        function getPoolDataProvider() external view returns (address)
+    ]"#
+);
+
+// Aave Oracle ABI (prices in base currency, USD with 8 decimals)
+abigen!(
+    IAaveOracle,
+    r#"[
+        function getAssetPrice(address asset) external view returns (uint256)
+        function getAssetsPrices(address[] calldata assets) external view returns (uint256[] memory)
+        function BASE_CURRENCY_UNIT() external view returns (uint256)
     ]"#
 );
 
@@ -25,6 +59,9 @@ abigen!(
         function getAllReservesTokens() external view returns (address[] memory)
         function getReserveTokensAddresses(address asset) external view returns (address aTokenAddress, address stableDebtTokenAddress, address variableDebtTokenAddress)
         function getReserveConfigurationData(address asset) external view returns (uint256 decimals, uint256 ltv, uint256 liquidationThreshold, uint256 liquidationBonus, uint256 reserveFactor, bool usageAsCollateralEnabled, bool borrowingEnabled, bool stableBorrowRateEnabled, bool isActive, bool isFrozen)
+        function getLiquidationProtocolFee(address asset) external view returns (uint256)
+        function getReserveEModeCategory(address asset) external view returns (uint256)
+        function getDebtCeiling(address asset) external view returns (uint256)
         function getUserReserveData(address asset, address user) external view returns (uint256 currentATokenBalance, uint256 currentStableDebt, uint256 currentVariableDebt, uint256 principalStableDebt, uint256 scaledVariableDebt, uint256 stableBorrowRate, uint256 liquidityRate, uint40 stableRateLastUpdated, bool usageAsCollateralEnabled)
     ]"#
 );
@@ -36,6 +73,8 @@ abigen!(
         function balanceOf(address account) external view returns (uint256)
         function decimals() external view returns (uint8)
         function symbol() external view returns (string memory)
+        function allowance(address owner, address spender) external view returns (uint256)
+        function approve(address spender, uint256 amount) external returns (bool)
     ]"#
 );
 
@@ -47,8 +86,8 @@ abigen!(
     ]"#
 );
 
-/// Multicall3 address (same on all chains)
-pub const MULTICALL3: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+/// Pairs we fetch swap quotes for (aggregator APIs are rate limited)
+const MAX_QUOTED_PAIRS: usize = 4;
 
 /// User's position details for a specific asset
 #[derive(Debug, Clone)]
@@ -62,51 +101,146 @@ pub struct UserAssetPosition {
     pub collateral_usd: f64,
     pub debt_balance: U256,
     pub debt_usd: f64,
+    /// User has this reserve enabled as collateral
+    pub collateral_enabled: bool,
 }
 
-/// Full liquidation opportunity with all details needed to execute
+/// Full liquidation opportunity with all details needed to execute,
+/// for one (collateral, debt) pair of the user's reserves
 #[derive(Debug, Clone)]
 pub struct LiquidationOpportunity {
     pub user: Address,
     pub chain: String,
+    /// Aave pool the user borrows from
+    pub pool: Address,
     pub health_factor: f64,
     pub total_collateral_usd: f64,
     pub total_debt_usd: f64,
+    /// Raw pool values used by the liquidation simulator
+    pub health_factor_wad: U256,
+    pub total_debt_base: U256,
     pub best_collateral: UserAssetPosition,
     pub best_debt: UserAssetPosition,
+    /// User's E-Mode category (0 = none)
+    pub user_emode: u8,
+    /// Effective liquidation bonus for the collateral (10500 = 5% bonus),
+    /// the E-Mode category's bonus when the collateral belongs to it
     pub liquidation_bonus: u64,
 }
 
+/// A (collateral, debt) pair simulated at oracle prices and checked against a swap quote
+#[derive(Debug, Clone)]
+pub struct PairEvaluation {
+    pub opportunity: LiquidationOpportunity,
+    pub simulation: AaveLiquidationResult,
+    /// Best quote for selling the seized collateral into the debt asset
+    pub swap_quote: Option<SwapQuote>,
+    /// Cheapest flash loan of the debt, whose fee `simulation` includes
+    pub flash_loan: FlashLoan,
+    /// Expected profit in USD: the swap surplus over debt + premium when
+    /// quoted, otherwise the oracle-priced bonus
+    pub profit_usd: f64,
+}
+
 #[derive(Debug, Clone)]
 pub struct AssetConfig {
     pub decimals: u8,
+    /// Liquidation threshold (bps); 0 means the reserve can't be seized
+    pub liquidation_threshold: u64,
+    /// Liquidation bonus including principal (10500 = 5% bonus)
     pub liquidation_bonus: u64,
+    /// Share of the bonus paid to the treasury (bps)
+    pub liquidation_protocol_fee: u64,
     pub a_token: Address,
     pub debt_token: Address,
+    /// Isolation mode debt ceiling; non-zero means the asset is isolated collateral
+    pub debt_ceiling: U256,
+}
+
+impl AssetConfig {
+    pub fn is_isolated(&self) -> bool {
+        !self.debt_ceiling.is_zero()
+    }
+}
+
+/// Oracle prices for every reserve, valid for one block
+#[derive(Debug, Clone, Default)]
+pub struct ReservePrices {
+    pub block: u64,
+    /// Base currency prices (USD, 8 decimals for USD markets)
+    pub prices: HashMap<Address, U256>,
+}
+
+/// E-Mode category: overrides threshold and bonus for its collateral assets
+#[derive(Debug, Clone)]
+pub struct EModeCategory {
+    pub label: String,
+    pub liquidation_threshold: u64,
+    /// Liquidation bonus including principal (10500 = 5% bonus)
+    pub liquidation_bonus: u64,
+    /// Reserves whose collateral gets the category's parameters
+    pub collaterals: Vec<Address>,
 }
 
 #[derive(Clone)]
 pub struct AaveProtocol {
     pub pool_address: Address,
     pub data_provider_address: Address,
+    pub oracle_address: Address,
+    /// Chain's Multicall3 deployment
+    pub multicall_address: Address,
+    /// Oracle base currency unit (1e8 for USD markets)
+    pub base_currency_unit: U256,
+    /// Flash loan premium (bps)
+    pub flash_premium: u64,
     pub assets: Vec<Asset>,
     pub asset_configs: HashMap<Address, AssetConfig>,
+    pub emode_categories: HashMap<u8, EModeCategory>,
+    /// Aave oracle prices shared by all clones, refreshed once per block
+    pub price_cache: Arc<RwLock<ReservePrices>>,
 }
 
 impl AaveProtocol {
-    pub fn new(pool_address: &str, data_provider: &str) -> anyhow::Result<Self> {
-        Ok(Self {
-            pool_address: pool_address.parse()?,
-            data_provider_address: data_provider.parse()?,
+    /// `data_provider_address` is looked up on discovery when `None`
+    pub fn new(
+        pool_address: Address,
+        data_provider_address: Option<Address>,
+        multicall_address: Address,
+    ) -> Self {
+        Self {
+            pool_address,
+            data_provider_address: data_provider_address.unwrap_or_default(),
+            oracle_address: Address::zero(),
+            multicall_address,
+            base_currency_unit: U256::exp10(8),
+            flash_premium: DEFAULT_AAVE_PREMIUM_BPS,
             assets: Vec::new(),
             asset_configs: HashMap::new(),
-        })
+            emode_categories: HashMap::new(),
+            price_cache: Arc::new(RwLock::new(ReservePrices::default())),
+        }
     }
     
     /// Discover all reserve assets and their configurations
     pub async fn discover_assets(&mut self, provider: &Provider<Http>) -> anyhow::Result<()> {
         let pool = IAavePool::new(self.pool_address, Arc::new(provider.clone()));
         
+        // Oracle and flash premium used by the liquidation simulator
+        let addresses_provider = pool.addresses_provider().call().await?;
+        let addresses_provider = IPoolAddressesProvider::new(addresses_provider, Arc::new(provider.clone()));
+        self.oracle_address = addresses_provider.get_price_oracle().call().await?;
+        if self.data_provider_address.is_zero() {
+            self.data_provider_address = addresses_provider.get_pool_data_provider().call().await?;
+        }
+        
+        let oracle = IAaveOracle::new(self.oracle_address, Arc::new(provider.clone()));
+        if let Ok(unit) = oracle.base_currency_unit().call().await {
+            self.base_currency_unit = unit;
+        }
+        if let Ok(premium) = pool.flashloan_premium_total().call().await {
+            self.flash_premium = premium as u64;
+        }
+        
         // Get all reserve addresses
         let reserves = pool.get_reserves_list().call().await?;
         
@@ -136,11 +270,25 @@ impl AaveProtocol {
                     Err(_) => continue,
                 };
             
+            let liquidation_protocol_fee = data_provider
+                .get_liquidation_protocol_fee(token_address)
+                .call()
+                .await
+                .map(|f| f.as_u64())
+                .unwrap_or(0);
+            
             // Get symbol
             let erc20 = IERC20::new(token_address, Arc::new(provider.clone()));
             let symbol = erc20.symbol().call().await.unwrap_or_else(|_| "???".to_string());
             
+            let debt_ceiling = data_provider
+                .get_debt_ceiling(token_address)
+                .call()
+                .await
+                .unwrap_or_default();
+            
             let decimals = config.0.as_u64() as u8;
+            let liquidation_threshold = config.2.as_u64();
             let liquidation_bonus = config.3.as_u64();
             
             self.assets.push(Asset {
@@ -153,32 +301,268 @@ impl AaveProtocol {
             
             self.asset_configs.insert(token_address, AssetConfig {
                 decimals,
+                liquidation_threshold,
                 liquidation_bonus,
+                liquidation_protocol_fee,
                 a_token: token_addrs.0,
                 debt_token: token_addrs.2,
+                debt_ceiling,
             });
             
-            debug!("  {} ({} decimals, {}% bonus)", symbol, decimals, liquidation_bonus / 100);
+            debug!("  {} ({} decimals, {:.2}% bonus{})", 
+                symbol, decimals, liquidation_bonus.saturating_sub(10_000) as f64 / 100.0,
+                if debt_ceiling.is_zero() { "" } else { ", isolated" });
+        }
+        
+        if let Err(e) = self.discover_emode_categories(provider).await {
+            warn!("E-Mode discovery failed: {}", e);
+        }
+        
+        info!("Discovered {} assets, {} E-Mode categories (oracle {:?}, flash premium {} bps)", 
+            self.assets.len(), self.emode_categories.len(), self.oracle_address, self.flash_premium);
+        Ok(())
+    }
+    
+    /// Discover E-Mode categories and their collateral reserves
+    ///
+    /// Governance can leave gaps in category ids, so every id up to `u8::MAX`
+    /// is checked, all through Multicall3. Aave 3.2+ lists collateral in a
+    /// per-category bitmap of reserve ids; older pools tag each reserve with a
+    /// single category instead.
+    async fn discover_emode_categories(&mut self, provider: &Provider<Http>) -> anyhow::Result<()> {
+        let pool = IAavePool::new(self.pool_address, Arc::new(provider.clone()));
+        
+        let (ids, calls): (Vec<u8>, Vec<(Address, Bytes)>) = (1..=u8::MAX)
+            .filter_map(|id| pool
+                .get_e_mode_category_data(id)
+                .calldata()
+                .map(|data| (id, (self.pool_address, data))))
+            .unzip();
+        let results = multicall::aggregate3(provider, self.multicall_address, &calls).await?;
+        // The category comes back as one dynamic struct, hence the outer 1-tuple
+        let categories: Vec<(u8, EModeCategory)> = ids
+            .into_iter()
+            .zip(results.iter())
+            .filter_map(|(id, result)| {
+                let ((_, liquidation_threshold, liquidation_bonus, _, label),) =
+                    multicall::decode::<((u16, u16, u16, Address, String),)>(result)?;
+                (liquidation_threshold != 0).then(|| (id, EModeCategory {
+                    label,
+                    liquidation_threshold: liquidation_threshold as u64,
+                    liquidation_bonus: liquidation_bonus as u64,
+                    collaterals: Vec::new(),
+                }))
+            })
+            .collect();
+        
+        let calls: Vec<(Address, Bytes)> = categories
+            .iter()
+            .filter_map(|(id, _)| pool
+                .get_e_mode_category_collateral_bitmap(*id)
+                .calldata()
+                .map(|data| (self.pool_address, data)))
+            .collect();
+        let bitmaps: Vec<Option<u128>> = multicall::aggregate3(provider, self.multicall_address, &calls)
+            .await?
+            .iter()
+            .map(multicall::decode::<u128>)
+            .collect();
+        let has_bitmaps = bitmaps.len() == categories.len() && bitmaps.iter().all(Option::is_some);
+        
+        // Resolve every reserve id any bitmap references in one batch
+        let mut reserve_ids: Vec<u16> = bitmaps
+            .iter()
+            .flatten()
+            .flat_map(|bitmap| (0..128u16).filter(move |i| bitmap & (1u128 << i) != 0))
+            .collect();
+        reserve_ids.sort_unstable();
+        reserve_ids.dedup();
+        let (reserve_ids, calls): (Vec<u16>, Vec<(Address, Bytes)>) = reserve_ids
+            .into_iter()
+            .filter_map(|reserve_id| pool
+                .get_reserve_address_by_id(reserve_id)
+                .calldata()
+                .map(|data| (reserve_id, (self.pool_address, data))))
+            .unzip();
+        let results = multicall::aggregate3(provider, self.multicall_address, &calls).await?;
+        let reserves: HashMap<u16, Address> = reserve_ids
+            .into_iter()
+            .zip(results.iter())
+            .filter_map(|(reserve_id, result)| multicall::decode::<Address>(result).map(|a| (reserve_id, a)))
+            .filter(|(_, asset)| !asset.is_zero())
+            .collect();
+        
+        for (i, (id, mut category)) in categories.into_iter().enumerate() {
+            if let Some(bitmap) = bitmaps.get(i).copied().flatten() {
+                category.collaterals = (0..128u16)
+                    .filter(|i| bitmap & (1u128 << i) != 0)
+                    .filter_map(|reserve_id| reserves.get(&reserve_id).copied())
+                    .collect();
+            }
+            
+            debug!("  E-Mode {} \"{}\" ({:.2}% bonus, {} collaterals)",
+                id, category.label, category.liquidation_bonus.saturating_sub(10_000) as f64 / 100.0,
+                category.collaterals.len());
+            
+            self.emode_categories.insert(id, category);
+        }
+        
+        if !has_bitmaps && !self.emode_categories.is_empty() {
+            let data_provider = IAaveDataProvider::new(
+                self.data_provider_address,
+                Arc::new(provider.clone()),
+            );
+            for asset in &self.assets {
+                let category = match data_provider.get_reserve_e_mode_category(asset.token).call().await {
+                    Ok(c) => c.low_u64() as u8,
+                    Err(_) => continue,
+                };
+                if let Some(emode) = self.emode_categories.get_mut(&category) {
+                    emode.collaterals.push(asset.token);
+                }
+            }
         }
         
-        info!("Discovered {} assets", self.assets.len());
         Ok(())
     }
     
+    /// Liquidation bonus the pool applies when seizing `collateral` from a user in `user_emode`
+    pub fn effective_liquidation_bonus(&self, collateral: Address, user_emode: u8) -> u64 {
+        if user_emode != 0 {
+            if let Some(emode) = self.emode_categories.get(&user_emode) {
+                if emode.collaterals.contains(&collateral) {
+                    return emode.liquidation_bonus;
+                }
+            }
+        }
+        
+        self.asset_configs
+            .get(&collateral)
+            .map(|c| c.liquidation_bonus)
+            .unwrap_or(10_500)
+    }
+    
+    /// Simulator input for liquidating `opportunity`'s best pair at the given oracle prices
+    pub fn liquidation_input(
+        &self,
+        opportunity: &LiquidationOpportunity,
+        collateral_price: U256,
+        debt_price: U256,
+    ) -> AaveLiquidationInput {
+        let protocol_fee = self.asset_configs
+            .get(&opportunity.best_collateral.asset)
+            .map(|c| c.liquidation_protocol_fee)
+            .unwrap_or(0);
+        
+        AaveLiquidationInput {
+            debt_balance: opportunity.best_debt.debt_balance,
+            collateral_balance: opportunity.best_collateral.collateral_balance,
+            debt_price,
+            collateral_price,
+            debt_decimals: opportunity.best_debt.decimals,
+            collateral_decimals: opportunity.best_collateral.decimals,
+            liquidation_bonus: U256::from(opportunity.liquidation_bonus),
+            liquidation_protocol_fee: U256::from(protocol_fee),
+            health_factor: opportunity.health_factor_wad,
+            total_debt_value: opportunity.total_debt_base,
+            flash_premium: U256::from(self.flash_premium),
+        }
+    }
+    
+    /// Aave oracle prices for all reserves at `block` (the caller's latest).
+    ///
+    /// These are the prices the pool liquidates at, so they are the source of
+    /// truth for valuing positions; one `getAssetsPrices` call per block.
+    pub async fn get_reserve_prices(
+        &self,
+        provider: &Provider<Http>,
+        block: u64,
+    ) -> anyhow::Result<HashMap<Address, U256>> {
+        {
+            let cache = self.price_cache.read();
+            if cache.block == block && !cache.prices.is_empty() {
+                return Ok(cache.prices.clone());
+            }
+        }
+        
+        let assets: Vec<Address> = self.assets.iter().map(|a| a.token).collect();
+        let oracle = IAaveOracle::new(self.oracle_address, Arc::new(provider.clone()));
+        let prices: HashMap<Address, U256> = assets
+            .iter()
+            .copied()
+            .zip(oracle.get_assets_prices(assets.clone()).call().await?)
+            .collect();
+        
+        let mut cache = self.price_cache.write();
+        if block >= cache.block {
+            cache.block = block;
+            cache.prices = prices.clone();
+        }
+        Ok(prices)
+    }
+    
     /// Check user account data
     pub async fn get_user_data(
         &self,
         provider: &Provider<Http>,
         user: Address,
     ) -> anyhow::Result<(f64, f64, f64)> {
+        let (collateral, debt, health_factor) = self.get_user_account_data(provider, user).await?;
+        
+        Ok((
+            base_to_usd(collateral, self.base_currency_unit),
+            base_to_usd(debt, self.base_currency_unit),
+            u256_to_f64(health_factor, 18),
+        ))
+    }
+    
+    /// Raw account data: (total collateral base, total debt base, health factor WAD)
+    pub async fn get_user_account_data(
+        &self,
+        provider: &Provider<Http>,
+        user: Address,
+    ) -> anyhow::Result<(U256, U256, U256)> {
         let pool = IAavePool::new(self.pool_address, Arc::new(provider.clone()));
         let data = pool.get_user_account_data(user).call().await?;
         
-        let collateral = u256_to_f64_safe(data.0, 8);
-        let debt = u256_to_f64_safe(data.1, 8);
-        let health_factor = u256_to_f64_safe(data.5, 18);
+        Ok((data.0, data.1, data.5))
+    }
+    
+    /// User's E-Mode category (0 = none)
+    pub async fn get_user_emode(&self, provider: &Provider<Http>, user: Address) -> anyhow::Result<u8> {
+        let pool = IAavePool::new(self.pool_address, Arc::new(provider.clone()));
+        Ok(pool.get_user_e_mode(user).call().await?.low_u64() as u8)
+    }
+    
+    /// Batch `getUserEMode` lookups via Multicall3 `aggregate3`; users whose
+    /// lookup fails fall back to category 0 (no E-Mode)
+    pub async fn batch_get_user_emodes(
+        &self,
+        provider: &Provider<Http>,
+        users: &[Address],
+    ) -> anyhow::Result<HashMap<Address, u8>> {
+        let mut emodes = HashMap::with_capacity(users.len());
+        if users.is_empty() || self.emode_categories.is_empty() {
+            return Ok(emodes);
+        }
+        
+        let pool = IAavePool::new(self.pool_address, Arc::new(provider.clone()));
+        
+        let (batch_users, calls): (Vec<Address>, Vec<(Address, Bytes)>) = users
+            .iter()
+            .filter_map(|user| pool
+                .get_user_e_mode(*user)
+                .calldata()
+                .map(|data| (*user, (self.pool_address, data))))
+            .unzip();
+        let results = multicall::aggregate3(provider, self.multicall_address, &calls).await?;
         
-        Ok((collateral, debt, health_factor))
+        for (user, result) in batch_users.into_iter().zip(results.iter()) {
+            let category = multicall::decode::<U256>(result).map_or(0, |c| c.low_u64() as u8);
+            emodes.insert(user, category);
+        }
+        
+        Ok(emodes)
     }
     
     /// Batch check multiple users using Multicall3 for efficiency
@@ -193,7 +577,7 @@ impl AaveProtocol {
             return Ok(Vec::new());
         }
         
-        let multicall_addr: Address = MULTICALL3.parse()?;
+        let multicall_addr = self.multicall_address;
         let mut all_positions = Vec::new();
         
         // Process in batches of 100
@@ -213,6 +597,7 @@ impl AaveProtocol {
                                     user: *user,
                                     chain: chain_name.to_string(),
                                     protocol: Protocol::Aave,
+                                    market: self.pool_address,
                                     collateral_usd: collateral,
                                     debt_usd: debt,
                                     health_factor,
@@ -288,17 +673,17 @@ impl AaveProtocol {
             }
             
             let collateral = match &decoded[0] {
-                Token::Uint(v) => u256_to_f64_safe(*v, 8),
+                Token::Uint(v) => base_to_usd(*v, self.base_currency_unit),
                 _ => continue,
             };
             
             let debt = match &decoded[1] {
-                Token::Uint(v) => u256_to_f64_safe(*v, 8),
+                Token::Uint(v) => base_to_usd(*v, self.base_currency_unit),
                 _ => continue,
             };
             
             let health_factor = match &decoded[5] {
-                Token::Uint(v) => u256_to_f64_safe(*v, 18),
+                Token::Uint(v) => u256_to_f64(*v, 18),
                 _ => continue,
             };
             
@@ -312,6 +697,7 @@ impl AaveProtocol {
                 user: users[i],
                 chain: chain_name.to_string(),
                 protocol: Protocol::Aave,
+                market: self.pool_address,
                 collateral_usd: collateral,
                 debt_usd: debt,
                 health_factor,
@@ -322,12 +708,13 @@ impl AaveProtocol {
         Ok(positions)
     }
     
-    /// Get user's full position details (collateral and debt per asset)
+    /// Get user's full position details (collateral and debt per asset),
+    /// valued at the Aave oracle's prices as of `block`
     pub async fn get_user_positions(
         &self,
         provider: &Provider<Http>,
         user: Address,
-        prices: &HashMap<Address, f64>,
+        block: u64,
     ) -> anyhow::Result<Vec<UserAssetPosition>> {
         if self.assets.is_empty() {
             warn!("No assets discovered - call discover_assets first");
@@ -339,29 +726,47 @@ impl AaveProtocol {
             Arc::new(provider.clone()),
         );
         
+        // One getUserReserveData per reserve, all in a single round-trip.
+        // Reserves stay paired with their call so results line up.
+        let (assets, calls): (Vec<_>, Vec<(Address, Bytes)>) = self.assets
+            .iter()
+            .filter_map(|asset| data_provider
+                .get_user_reserve_data(asset.token, user)
+                .calldata()
+                .map(|data| (asset, (self.data_provider_address, data))))
+            .unzip();
+        let results = multicall::aggregate3(provider, self.multicall_address, &calls).await?;
+        if results.len() != assets.len() {
+            anyhow::bail!("Aave: incomplete multicall for {:?}", user);
+        }
+        let prices = self.get_reserve_prices(provider, block).await?;
+        
         let mut positions = Vec::new();
         
-        for asset in &self.assets {
-            let user_data = match data_provider
-                .get_user_reserve_data(asset.token, user)
-                .call()
-                .await {
-                    Ok(d) => d,
-                    Err(_) => continue,
-                };
+        for (asset, result) in assets.into_iter().zip(results.iter()) {
+            let user_data: i_aave_data_provider::GetUserReserveDataReturn = match multicall::decode(result) {
+                Some(d) => d,
+                None => continue,
+            };
             
-            let collateral_balance = user_data.0; // currentATokenBalance
-            let debt_balance = user_data.2; // currentVariableDebt
+            let collateral_balance = user_data.current_a_token_balance;
+            let debt_balance = user_data.current_stable_debt + user_data.current_variable_debt;
             
             if collateral_balance.is_zero() && debt_balance.is_zero() {
                 continue;
             }
             
-            let price = prices.get(&asset.token).copied().unwrap_or(1.0);
-            let decimals_factor = 10_f64.powi(asset.decimals as i32);
+            let price = match prices.get(&asset.token) {
+                Some(p) if !p.is_zero() => *p,
+                _ => {
+                    debug!("Aave: no oracle price for {}", asset.symbol);
+                    continue;
+                }
+            };
+            let unit = U256::exp10(asset.decimals as usize);
             
-            let collateral_usd = u256_to_f64_safe(collateral_balance, 0) / decimals_factor * price;
-            let debt_usd = u256_to_f64_safe(debt_balance, 0) / decimals_factor * price;
+            let collateral_usd = base_to_usd(collateral_balance * price / unit, self.base_currency_unit);
+            let debt_usd = base_to_usd(debt_balance * price / unit, self.base_currency_unit);
             
             positions.push(UserAssetPosition {
                 asset: asset.token,
@@ -373,79 +778,214 @@ impl AaveProtocol {
                 collateral_usd,
                 debt_balance,
                 debt_usd,
+                collateral_enabled: user_data.usage_as_collateral_enabled,
             });
         }
         
         Ok(positions)
     }
     
-    /// Find the best collateral and debt assets for liquidation
-    pub async fn find_liquidation_opportunity(
+    /// Every (collateral, debt) pair that can be liquidated for a user,
+    /// roughly ordered by the bonus value each could earn.
+    ///
+    /// `user_emode` is the user's E-Mode category if already known (e.g. from
+    /// [`Self::batch_get_user_emodes`]); otherwise it is read from the pool.
+    /// Reserves are valued at oracle prices as of `block`.
+    pub async fn find_liquidation_opportunities(
         &self,
         provider: &Provider<Http>,
         user: Address,
         chain: &str,
-        prices: &HashMap<Address, f64>,
-    ) -> anyhow::Result<Option<LiquidationOpportunity>> {
-        let (total_collateral_usd, total_debt_usd, health_factor) = 
-            self.get_user_data(provider, user).await?;
+        user_emode: Option<u8>,
+        block: u64,
+    ) -> anyhow::Result<Vec<LiquidationOpportunity>> {
+        let (total_collateral_base, total_debt_base, health_factor_wad) = 
+            self.get_user_account_data(provider, user).await?;
+        let total_collateral_usd = base_to_usd(total_collateral_base, self.base_currency_unit);
+        let total_debt_usd = base_to_usd(total_debt_base, self.base_currency_unit);
+        let health_factor = u256_to_f64(health_factor_wad, 18);
         
         if health_factor >= 1.0 || health_factor <= 0.0 {
-            return Ok(None);
+            return Ok(Vec::new());
         }
         
-        let positions = self.get_user_positions(provider, user, prices).await?;
+        let user_emode = match user_emode {
+            Some(e) => e,
+            None if self.emode_categories.is_empty() => 0,
+            None => self.get_user_emode(provider, user).await.unwrap_or(0),
+        };
         
-        let best_collateral = positions
-            .iter()
-            .filter(|p| p.collateral_usd > 0.0)
-            .max_by(|a, b| a.collateral_usd.partial_cmp(&b.collateral_usd).unwrap());
+        let positions = self.get_user_positions(provider, user, block).await?;
         
-        let best_debt = positions
+        // Only enabled collateral with a non-zero threshold can be seized; in
+        // isolation mode that is just the isolated asset
+        let collaterals: Vec<&UserAssetPosition> = positions
+            .iter()
+            .filter(|p| p.collateral_usd > 0.0 && p.collateral_enabled)
+            .filter(|p| self.asset_configs
+                .get(&p.asset)
+                .map(|c| c.liquidation_threshold > 0)
+                .unwrap_or(true))
+            .collect();
+        let debts: Vec<&UserAssetPosition> = positions
             .iter()
             .filter(|p| p.debt_usd > 0.0)
-            .max_by(|a, b| a.debt_usd.partial_cmp(&b.debt_usd).unwrap());
-        
-        match (best_collateral, best_debt) {
-            (Some(collateral), Some(debt)) => {
-                let bonus = self.asset_configs
-                    .get(&collateral.asset)
-                    .map(|c| c.liquidation_bonus)
-                    .unwrap_or(500);
+            .collect();
+        
+        let mut scored = Vec::with_capacity(collaterals.len() * debts.len());
+        for debt in &debts {
+            for collateral in &collaterals {
+                let bonus = self.effective_liquidation_bonus(collateral.asset, user_emode);
+                let bonus_factor = bonus as f64 / 10_000.0;
+                // Bonus value we could take: capped by the collateral balance
+                let seizable = collateral.collateral_usd.min(debt.debt_usd * bonus_factor);
+                let score = seizable * (bonus_factor - 1.0) / bonus_factor;
                 
-                Ok(Some(LiquidationOpportunity {
+                scored.push((score, LiquidationOpportunity {
                     user,
                     chain: chain.to_string(),
+                    pool: self.pool_address,
                     health_factor,
                     total_collateral_usd,
                     total_debt_usd,
-                    best_collateral: collateral.clone(),
-                    best_debt: debt.clone(),
+                    health_factor_wad,
+                    total_debt_base,
+                    best_collateral: (*collateral).clone(),
+                    best_debt: (*debt).clone(),
+                    user_emode,
                     liquidation_bonus: bonus,
-                }))
+                }));
             }
-            _ => Ok(None),
         }
+        
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(scored.into_iter().map(|(_, opportunity)| opportunity).collect())
     }
-}
-
-/// Safely convert U256 to f64 with decimal scaling
-fn u256_to_f64_safe(value: U256, decimals: u32) -> f64 {
-    let max_safe = U256::from(1u128 << 100);
     
-    if value >= max_safe {
-        return 1_000_000.0;
+    /// Flash loan of this pool's reserves
+    pub fn flash_loan(&self) -> FlashLoan {
+        FlashLoan::aave(self.pool_address, self.flash_premium)
     }
     
-    if value > U256::from(u128::MAX) {
-        let shifted = value >> 64;
-        let shifted_low = shifted.low_u128();
-        let divisor = 10u128.pow(decimals);
-        let result = (shifted_low as f64) * (2.0_f64.powi(64)) / (divisor as f64);
-        return result.min(1_000_000.0);
+    /// Simulate every candidate pair at oracle prices as of `block`, quote the
+    /// most promising ones on `quoter`, pick their cheapest flash loan from
+    /// `flash`, and return them ranked by expected profit (best first).
+    ///
+    /// Pairs whose swap can't repay the debt plus flash fee, or whose quote
+    /// failed, are dropped. Pairs past the quoted ones keep their oracle-priced
+    /// profit and rank below every quoted pair.
+    pub async fn rank_liquidation_pairs(
+        &self,
+        provider: &Provider<Http>,
+        chain: &ChainConfig,
+        quoter: &MultiDexQuoter,
+        flash: &FlashLenders,
+        opportunities: Vec<LiquidationOpportunity>,
+        block: u64,
+    ) -> anyhow::Result<Vec<PairEvaluation>> {
+        let prices = self.get_reserve_prices(provider, block).await?;
+        
+        let mut evaluations: Vec<PairEvaluation> = opportunities
+            .into_iter()
+            .filter_map(|opportunity| {
+                let collateral_price = *prices.get(&opportunity.best_collateral.asset)?;
+                let debt_price = *prices.get(&opportunity.best_debt.asset)?;
+                let input = self.liquidation_input(&opportunity, collateral_price, debt_price);
+                let simulation = simulate_aave_liquidation(&input, U256::MAX)?;
+                let profit_usd = simulation.profit_usd(self.base_currency_unit);
+                Some(PairEvaluation {
+                    opportunity,
+                    simulation,
+                    swap_quote: None,
+                    flash_loan: self.flash_loan(),
+                    profit_usd,
+                })
+            })
+            .collect();
+        evaluations.sort_by(|a, b| b.profit_usd.partial_cmp(&a.profit_usd).unwrap_or(std::cmp::Ordering::Equal));
+        let unquoted = evaluations.split_off(evaluations.len().min(MAX_QUOTED_PAIRS));
+        
+        let quotes = futures::future::join_all(
+            evaluations
+                .iter()
+                .map(|e| async move {
+                    let collateral = e.opportunity.best_collateral.asset;
+                    let debt = e.opportunity.best_debt.asset;
+                    let quote = if collateral == debt {
+                        None
+                    } else {
+                        Some(quoter.get_best_quote(
+                            provider,
+                            chain,
+                            collateral,
+                            debt,
+                            e.simulation.collateral_seized,
+                        ).await)
+                    };
+                    let swap = match &quote {
+                        None => SwapPlan::NoSwap,
+                        Some(Ok(Some(q))) => SwapPlan::Quoted(q),
+                        Some(_) => SwapPlan::Unknown,
+                    };
+                    let flash_loan = flash
+                        .select(provider, debt, e.simulation.debt_to_cover, self.flash_loan(), swap)
+                        .await;
+                    (collateral, debt, quote, flash_loan)
+                }),
+        ).await;
+        
+        for (collateral, debt, quote, flash_loan) in quotes {
+            let evaluation = match evaluations.iter_mut().find(|e| {
+                e.opportunity.best_collateral.asset == collateral && e.opportunity.best_debt.asset == debt
+            }) {
+                Some(e) => e,
+                None => continue,
+            };
+            
+            evaluation.simulation = evaluation.simulation
+                .with_flash_fee(flash_loan.fee_on(evaluation.simulation.debt_to_cover));
+            evaluation.flash_loan = flash_loan;
+            evaluation.profit_usd = evaluation.simulation.profit_usd(self.base_currency_unit);
+            
+            let quote = match quote {
+                Some(q) => q,
+                None => continue,
+            };
+            
+            match quote {
+                Ok(Some(q)) => {
+                    let repay = evaluation.simulation.repay_amount();
+                    if q.amount_out < repay {
+                        debug!("  {} -> {}: swap returns {} < {} needed",
+                            evaluation.opportunity.best_collateral.symbol,
+                            evaluation.opportunity.best_debt.symbol, q.amount_out, repay);
+                        evaluation.profit_usd = f64::NEG_INFINITY;
+                    } else {
+                        let debt_unit = U256::exp10(evaluation.opportunity.best_debt.decimals as usize);
+                        let debt_price = prices.get(&debt).copied().unwrap_or_default();
+                        let surplus = (q.amount_out - repay) * debt_price / debt_unit;
+                        evaluation.profit_usd = base_to_usd(surplus, self.base_currency_unit);
+                    }
+                    evaluation.swap_quote = Some(q);
+                }
+                Ok(None) => {
+                    debug!("  {} -> {}: no swap path",
+                        evaluation.opportunity.best_collateral.symbol,
+                        evaluation.opportunity.best_debt.symbol);
+                    evaluation.profit_usd = f64::NEG_INFINITY;
+                }
+                Err(e) => {
+                    debug!("  {} -> {}: swap quote failed ({})",
+                        evaluation.opportunity.best_collateral.symbol,
+                        evaluation.opportunity.best_debt.symbol, e);
+                    evaluation.profit_usd = f64::NEG_INFINITY;
+                }
+            }
+        }
+        
+        evaluations.retain(|e| e.profit_usd.is_finite());
+        evaluations.sort_by(|a, b| b.profit_usd.partial_cmp(&a.profit_usd).unwrap_or(std::cmp::Ordering::Equal));
+        evaluations.extend(unquoted);
+        Ok(evaluations)
     }
-    
-    let low = value.low_u128();
-    let divisor = 10u128.pow(decimals);
-    low as f64 / divisor as f64
 }
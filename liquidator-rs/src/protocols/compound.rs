@@ -10,6 +10,8 @@ use std::sync::Arc;
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
 
+use crate::multicall;
+use crate::simulator::u256_to_f64;
 use crate::types::{Position, Protocol};
 
 // Comet (Compound V3) ABI
@@ -27,6 +29,11 @@ abigen!(
         function getPrice(address priceFeed) external view returns (uint256)
         function absorb(address absorber, address[] calldata accounts) external
         function quoteCollateral(address asset, uint256 baseAmount) external view returns (uint256)
+        function buyCollateral(address asset, uint256 minAmount, uint256 baseAmount, address recipient) external
+        function getCollateralReserves(address asset) external view returns (uint256)
+        function getReserves() external view returns (int256)
+        function targetReserves() external view returns (uint256)
+        function baseScale() external view returns (uint256)
     ]"#
 );
 
@@ -38,24 +45,6 @@ abigen!(
     ]"#
 );
 
-/// Compound V3 Comet addresses by chain
-pub fn get_comet_addresses(chain: &str) -> Vec<(&'static str, &'static str, Address)> {
-    match chain {
-        "base" => vec![
-            ("USDC", "USDbC", "0x9c4ec768c28520B50860ea7a15bd7213a9fF58bf".parse().unwrap()),
-            ("WETH", "WETH", "0x46e6b214b524310239732D51387075E0e70970bf".parse().unwrap()),
-        ],
-        "polygon" => vec![
-            ("USDC", "USDC.e", "0xF25212E676D1F7F89Cd72fFEe66158f541246445".parse().unwrap()),
-        ],
-        "arbitrum" => vec![
-            ("USDC", "USDC.e", "0xA5EDBDD9646f8dFF606d7448e414884C7d905dCA".parse().unwrap()),
-            ("USDC", "USDC", "0x9c4ec768c28520B50860ea7a15bd7213a9fF58bf".parse().unwrap()),
-        ],
-        _ => vec![],
-    }
-}
-
 /// Collateral asset info
 #[derive(Debug, Clone)]
 pub struct CollateralAsset {
@@ -65,6 +54,14 @@ pub struct CollateralAsset {
     pub liquidation_factor: u64,
 }
 
+/// A discounted collateral purchase from the Comet's reserves
+#[derive(Debug, Clone)]
+pub struct CollateralPurchase {
+    pub asset: Address,
+    pub base_amount: U256,
+    pub collateral_amount: U256,
+}
+
 /// User's Compound position
 #[derive(Debug, Clone)]
 pub struct CompoundPosition {
@@ -80,15 +77,24 @@ pub struct CompoundPosition {
 #[derive(Clone)]
 pub struct CompoundProtocol {
     pub comet_address: Address,
+    /// Chain's Multicall3 deployment
+    pub multicall_address: Address,
     pub base_token_name: String,
+    pub base_token: Address,
+    pub base_price_feed: Address,
+    pub base_scale: U256,
     pub collateral_assets: Vec<CollateralAsset>,
 }
 
 impl CompoundProtocol {
-    pub fn new(comet_address: Address, base_token_name: &str) -> Self {
+    pub fn new(comet_address: Address, base_token_name: &str, multicall_address: Address) -> Self {
         Self {
             comet_address,
+            multicall_address,
             base_token_name: base_token_name.to_string(),
+            base_token: Address::zero(),
+            base_price_feed: Address::zero(),
+            base_scale: U256::exp10(6),
             collateral_assets: Vec::new(),
         }
     }
@@ -97,6 +103,10 @@ impl CompoundProtocol {
     pub async fn discover_assets(&mut self, provider: &Provider<Http>) -> anyhow::Result<()> {
         let comet = IComet::new(self.comet_address, Arc::new(provider.clone()));
         
+        self.base_token = comet.base_token().call().await?;
+        self.base_price_feed = comet.base_token_price_feed().call().await?;
+        self.base_scale = comet.base_scale().call().await?;
+        
         let num_assets = comet.num_assets().call().await?;
         info!("Compound {} market has {} collateral assets", self.base_token_name, num_assets);
         
@@ -140,8 +150,9 @@ impl CompoundProtocol {
             return Ok(None);
         }
         
-        // Estimate USD value (assuming base token is a stablecoin with 6 decimals)
-        let borrow_usd = borrow_balance.as_u128() as f64 / 1e6;
+        // Value the borrow using the Comet's own base price feed
+        let base_price = comet.get_price(self.base_price_feed).call().await.unwrap_or(U256::zero());
+        let borrow_usd = self.base_to_usd(borrow_balance, base_price);
         
         // Skip small positions
         if borrow_usd < 100.0 {
@@ -173,6 +184,7 @@ impl CompoundProtocol {
             user,
             chain: chain.to_string(),
             protocol: Protocol::Compound,
+            market: self.comet_address,
             collateral_usd: total_collateral_usd,
             debt_usd: borrow_usd,
             health_factor,
@@ -191,7 +203,7 @@ impl CompoundProtocol {
             return Ok(Vec::new());
         }
         
-        let multicall_addr: Address = "0xcA11bde05977b3631167028862bE2a173976CA11".parse()?;
+        let multicall_addr = self.multicall_address;
         let mut all_positions = Vec::new();
         
         // Process in batches of 100
@@ -290,19 +302,50 @@ impl CompoundProtocol {
     ) -> anyhow::Result<Option<CompoundPosition>> {
         let comet = IComet::new(self.comet_address, Arc::new(provider.clone()));
         
-        let is_liquidatable = comet.is_liquidatable(user).call().await?;
+        // Liquidatability, borrow and every collateral balance/price in a single round-trip
+        let mut calls = vec![
+            (self.comet_address, comet.is_liquidatable(user).calldata()),
+            (self.comet_address, comet.borrow_balance_of(user).calldata()),
+            (self.comet_address, comet.get_price(self.base_price_feed).calldata()),
+        ];
+        for collateral in &self.collateral_assets {
+            calls.push((self.comet_address, comet.collateral_balance_of(user, collateral.asset).calldata()));
+            calls.push((self.comet_address, comet.get_price(collateral.price_feed).calldata()));
+        }
+        let calls: Vec<(Address, Bytes)> = calls
+            .into_iter()
+            .filter_map(|(target, data)| data.map(|d| (target, d)))
+            .collect();
+        
+        let results = multicall::aggregate3(provider, self.multicall_address, &calls).await?;
+        if results.len() != 3 + self.collateral_assets.len() * 2 {
+            anyhow::bail!("Compound: incomplete multicall for {:?}", user);
+        }
+        
+        let is_liquidatable = multicall::decode::<i_comet::IsLiquidatableReturn>(&results[0])
+            .ok_or_else(|| anyhow::anyhow!("isLiquidatable failed"))?
+            .0;
         if !is_liquidatable {
             return Ok(None);
         }
         
-        let borrow_balance = comet.borrow_balance_of(user).call().await?;
-        let borrow_usd = borrow_balance.as_u128() as f64 / 1e6;
+        let borrow_balance = multicall::decode::<i_comet::BorrowBalanceOfReturn>(&results[1])
+            .ok_or_else(|| anyhow::anyhow!("borrowBalanceOf failed"))?
+            .0;
+        let base_price = multicall::decode::<i_comet::GetPriceReturn>(&results[2])
+            .map(|r| r.0)
+            .unwrap_or(U256::zero());
+        let borrow_usd = self.base_to_usd(borrow_balance, base_price);
         
         let mut collaterals = Vec::new();
-        for collateral in &self.collateral_assets {
-            let balance = comet.collateral_balance_of(user, collateral.asset).call().await?;
+        for (collateral, collateral_results) in self.collateral_assets.iter().zip(results[3..].chunks(2)) {
+            let balance = multicall::decode::<i_comet::CollateralBalanceOfReturn>(&collateral_results[0])
+                .ok_or_else(|| anyhow::anyhow!("collateralBalanceOf failed"))?
+                .0;
             if balance > 0 {
-                let price = comet.get_price(collateral.price_feed).call().await.unwrap_or(U256::zero());
+                let price = multicall::decode::<i_comet::GetPriceReturn>(&collateral_results[1])
+                    .map(|r| r.0)
+                    .unwrap_or(U256::zero());
                 let price_usd = price.as_u128() as f64 / 1e8;
                 let balance_normalized = balance as f64 / (collateral.scale as f64);
                 let usd_value = balance_normalized * price_usd;
@@ -320,6 +363,83 @@ impl CompoundProtocol {
             is_liquidatable,
         }))
     }
+    
+    /// Get the base token price from the Comet (8 decimals)
+    pub async fn get_base_price(&self, provider: &Provider<Http>) -> anyhow::Result<U256> {
+        let comet = IComet::new(self.comet_address, Arc::new(provider.clone()));
+        Ok(comet.get_price(self.base_price_feed).call().await?)
+    }
+    
+    /// Convert a base token amount to USD using a Comet price (8 decimals)
+    pub fn base_to_usd(&self, amount: U256, base_price: U256) -> f64 {
+        let units = u256_to_f64(amount, 0) / u256_to_f64(self.base_scale, 0);
+        units * u256_to_f64(base_price, 8)
+    }
+    
+    /// Base token amount needed to buy `collateral_amount` of `asset` at the
+    /// store-front discount, derived from `quoteCollateral` (which is linear in base).
+    pub async fn base_for_collateral(
+        &self,
+        provider: &Provider<Http>,
+        asset: Address,
+        collateral_amount: U256,
+    ) -> anyhow::Result<U256> {
+        let comet = IComet::new(self.comet_address, Arc::new(provider.clone()));
+        
+        let probe = self.base_scale;
+        let quoted = comet.quote_collateral(asset, probe).call().await?;
+        if quoted.is_zero() {
+            anyhow::bail!("quoteCollateral returned zero for {:?}", asset);
+        }
+        
+        // Round down so the purchase never asks for more than is available
+        Ok(collateral_amount * probe / quoted)
+    }
+    
+    /// Whether the Comet sells collateral: `buyCollateral` reverts with
+    /// NotForSale once protocol reserves reach target
+    pub async fn collateral_for_sale(&self, provider: &Provider<Http>) -> anyhow::Result<bool> {
+        let comet = IComet::new(self.comet_address, Arc::new(provider.clone()));
+        
+        let reserves = comet.get_reserves().call().await?;
+        let target = comet.target_reserves().call().await?;
+        if !reserves.is_negative() && reserves.into_raw() >= target {
+            debug!("Compound {}: reserves at target, collateral not for sale", self.base_token_name);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+    
+    /// Plan a purchase of everything the Comet holds in `asset` reserves.
+    /// Returns None when the Comet is not selling (reserves at target) or holds none.
+    pub async fn plan_collateral_purchase(
+        &self,
+        provider: &Provider<Http>,
+        asset: Address,
+    ) -> anyhow::Result<Option<CollateralPurchase>> {
+        if !self.collateral_for_sale(provider).await? {
+            return Ok(None);
+        }
+        
+        let comet = IComet::new(self.comet_address, Arc::new(provider.clone()));
+        let available = comet.get_collateral_reserves(asset).call().await?;
+        if available.is_zero() {
+            return Ok(None);
+        }
+        
+        let base_amount = self.base_for_collateral(provider, asset, available).await?;
+        if base_amount.is_zero() {
+            return Ok(None);
+        }
+        
+        let collateral_amount = comet.quote_collateral(asset, base_amount).call().await?;
+        
+        Ok(Some(CollateralPurchase {
+            asset,
+            base_amount,
+            collateral_amount: collateral_amount.min(available),
+        }))
+    }
 }
 
 /// Discover Compound borrowers from Withdraw events (indicates active borrowers)
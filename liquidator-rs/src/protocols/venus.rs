@@ -10,6 +10,8 @@ use std::sync::Arc;
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
 
+use crate::multicall;
+use crate::simulator::u256_to_f64;
 use crate::types::{Position, Protocol};
 
 // Venus Comptroller ABI
@@ -21,6 +23,23 @@ abigen!(
         function markets(address vToken) external view returns (bool isListed, uint256 collateralFactorMantissa, bool isVenus)
         function closeFactorMantissa() external view returns (uint256)
         function liquidationIncentiveMantissa() external view returns (uint256)
+        function oracle() external view returns (address)
+    ]"#
+);
+
+// Venus price oracle ABI (prices scaled to 1e(36 - underlying decimals))
+abigen!(
+    IVenusOracle,
+    r#"[
+        function getUnderlyingPrice(address vToken) external view returns (uint256)
+    ]"#
+);
+
+// Minimal ERC20 ABI for underlying metadata
+abigen!(
+    IVenusUnderlying,
+    r#"[
+        function decimals() external view returns (uint8)
     ]"#
 );
 
@@ -35,11 +54,20 @@ abigen!(
         function symbol() external view returns (string memory)
         function decimals() external view returns (uint8)
         function exchangeRateStored() external view returns (uint256)
+        function liquidateBorrow(address borrower, uint256 repayAmount, address vTokenCollateral) external returns (uint256)
     ]"#
 );
 
-/// Venus Comptroller address on BNB Chain
-pub const VENUS_COMPTROLLER: &str = "0xfD36E2c2a6789Db23113685031d7F16329158384";
+/// Wrapped BNB - the flash-loanable stand-in for the native vBNB market
+pub const WBNB: &str = "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c";
+
+/// Multicall reads per market in `get_liquidation_details`
+const MARKET_CALLS: usize = 4;
+
+/// 1e18 mantissa used by the Comptroller and vTokens
+fn mantissa() -> U256 {
+    U256::exp10(18)
+}
 
 /// Venus market info
 #[derive(Debug, Clone)]
@@ -47,10 +75,45 @@ pub struct VenusMarket {
     pub v_token: Address,
     pub underlying: Address,
     pub symbol: String,
+    /// Decimals of the underlying asset (18 for native BNB)
     pub decimals: u8,
     pub collateral_factor: f64,
 }
 
+impl VenusMarket {
+    /// Native BNB market (vBNB) has no underlying ERC20
+    pub fn is_native(&self) -> bool {
+        self.underlying.is_zero()
+    }
+    
+    /// Convert an underlying amount to whole-token units (for logging)
+    pub fn to_units(&self, amount: U256) -> f64 {
+        u256_to_f64(amount, self.decimals as u32)
+    }
+    
+    /// Token that is flash loaned / swapped for this market
+    pub fn repay_token(&self) -> Address {
+        if self.is_native() {
+            WBNB.parse().unwrap()
+        } else {
+            self.underlying
+        }
+    }
+}
+
+/// User's balances in a single Venus market
+#[derive(Debug, Clone)]
+pub struct VenusMarketPosition {
+    pub market: VenusMarket,
+    pub v_token_balance: U256,
+    pub supply_balance: U256,
+    pub borrow_balance: U256,
+    /// Oracle price scaled to 1e(36 - decimals)
+    pub price: U256,
+    pub supply_usd: f64,
+    pub borrow_usd: f64,
+}
+
 /// User's Venus position
 #[derive(Debug, Clone)]
 pub struct VenusPosition {
@@ -58,21 +121,28 @@ pub struct VenusPosition {
     pub total_collateral_usd: f64,
     pub total_borrow_usd: f64,
     pub shortfall: f64,
-    pub markets: Vec<(Address, f64, f64)>, // (vToken, supply_usd, borrow_usd)
+    pub markets: Vec<VenusMarketPosition>,
 }
 
 #[derive(Clone)]
 pub struct VenusProtocol {
+    pub chain: String,
     pub comptroller: Address,
+    /// Chain's Multicall3 deployment
+    pub multicall_address: Address,
+    pub oracle: Address,
     pub markets: Vec<VenusMarket>,
     pub liquidation_incentive: f64,
     pub close_factor: f64,
 }
 
 impl VenusProtocol {
-    pub fn new() -> Self {
+    pub fn new(chain: &str, comptroller: Address, multicall_address: Address) -> Self {
         Self {
-            comptroller: VENUS_COMPTROLLER.parse().unwrap(),
+            chain: chain.to_string(),
+            comptroller,
+            multicall_address,
+            oracle: Address::zero(),
             markets: Vec::new(),
             liquidation_incentive: 1.1, // 10% bonus default
             close_factor: 0.5, // 50% default
@@ -92,6 +162,8 @@ impl VenusProtocol {
             self.close_factor = close_factor.as_u128() as f64 / 1e18;
         }
         
+        self.oracle = comptroller.oracle().call().await?;
+        
         // Get all markets
         let market_addresses = comptroller.get_all_markets().call().await?;
         info!("Venus has {} markets", market_addresses.len());
@@ -113,11 +185,21 @@ impl VenusProtocol {
             
             // Get token details
             let symbol = v_token.symbol().call().await.unwrap_or_else(|_| "???".to_string());
-            let decimals = v_token.decimals().call().await.unwrap_or(18);
             
             // Get underlying (vBNB doesn't have underlying)
             let underlying = v_token.underlying().call().await.unwrap_or(Address::zero());
             
+            // Balances are tracked in underlying units, so use the underlying's decimals
+            let decimals = if underlying.is_zero() {
+                18
+            } else {
+                IVenusUnderlying::new(underlying, Arc::new(provider.clone()))
+                    .decimals()
+                    .call()
+                    .await
+                    .unwrap_or(18)
+            };
+            
             self.markets.push(VenusMarket {
                 v_token: v_token_addr,
                 underlying,
@@ -126,7 +208,7 @@ impl VenusProtocol {
                 collateral_factor,
             });
             
-            debug!("  {} (CF: {:.0}%)", symbol, collateral_factor * 100.0);
+            debug!("  {} ({} decimals, CF: {:.0}%)", symbol, decimals, collateral_factor * 100.0);
         }
         
         info!("Discovered {} Venus markets", self.markets.len());
@@ -172,8 +254,9 @@ impl VenusProtocol {
         
         Ok(Some(Position {
             user,
-            chain: "bnb".to_string(),
+            chain: self.chain.clone(),
             protocol: Protocol::Venus,
+            market: self.comptroller,
             collateral_usd: total_collateral,
             debt_usd: total_borrow,
             health_factor,
@@ -190,26 +273,31 @@ impl VenusProtocol {
         let mut total_collateral = 0.0;
         let mut total_borrow = 0.0;
         
+        let oracle = IVenusOracle::new(self.oracle, Arc::new(provider.clone()));
+        
         for market in &self.markets {
             let v_token = IVToken::new(market.v_token, Arc::new(provider.clone()));
             
-            // Get supply balance
             let v_token_balance = v_token.balance_of(user).call().await.unwrap_or(U256::zero());
+            let borrow_balance = v_token.borrow_balance_stored(user).call().await.unwrap_or(U256::zero());
+            
+            if v_token_balance.is_zero() && borrow_balance.is_zero() {
+                continue;
+            }
+            
+            let price = oracle.get_underlying_price(market.v_token).call().await.unwrap_or(U256::zero());
+            
             if !v_token_balance.is_zero() {
                 // Convert vToken balance to underlying using exchange rate
-                let exchange_rate = v_token.exchange_rate_stored().call().await.unwrap_or(U256::from(1e18 as u64));
-                let underlying_balance = v_token_balance * exchange_rate / U256::from(1e18 as u64);
+                let exchange_rate = v_token.exchange_rate_stored().call().await.unwrap_or(mantissa());
+                let underlying_balance = v_token_balance * exchange_rate / mantissa();
                 
-                // Assume $1 per token for now (would need oracle for accurate pricing)
-                let supply_usd = underlying_balance.as_u128() as f64 / 10_f64.powi(market.decimals as i32);
+                let supply_usd = underlying_to_usd(underlying_balance, price);
                 total_collateral += supply_usd * market.collateral_factor;
             }
             
-            // Get borrow balance
-            let borrow_balance = v_token.borrow_balance_stored(user).call().await.unwrap_or(U256::zero());
             if !borrow_balance.is_zero() {
-                let borrow_usd = borrow_balance.as_u128() as f64 / 10_f64.powi(market.decimals as i32);
-                total_borrow += borrow_usd;
+                total_borrow += underlying_to_usd(borrow_balance, price);
             }
         }
         
@@ -226,7 +314,7 @@ impl VenusProtocol {
             return Ok(Vec::new());
         }
         
-        let multicall_addr: Address = "0xcA11bde05977b3631167028862bE2a173976CA11".parse()?;
+        let multicall_addr = self.multicall_address;
         let mut all_positions = Vec::new();
         
         // Process in batches of 100
@@ -338,41 +426,82 @@ impl VenusProtocol {
         user: Address,
     ) -> anyhow::Result<Option<VenusPosition>> {
         let comptroller = IVenusComptroller::new(self.comptroller, Arc::new(provider.clone()));
+        let oracle = IVenusOracle::new(self.oracle, Arc::new(provider.clone()));
         
-        let (error, _liquidity, shortfall) = comptroller.get_account_liquidity(user).call().await?;
+        // Account liquidity, then balances, exchange rate and price per market,
+        // all in a single round-trip
+        let mut calls: Vec<(Address, Bytes)> = Vec::with_capacity(1 + self.markets.len() * MARKET_CALLS);
+        if let Some(data) = comptroller.get_account_liquidity(user).calldata() {
+            calls.push((self.comptroller, data));
+        }
+        for market in &self.markets {
+            let v_token = IVToken::new(market.v_token, Arc::new(provider.clone()));
+            calls.extend([
+                (market.v_token, v_token.balance_of(user).calldata()),
+                (market.v_token, v_token.borrow_balance_stored(user).calldata()),
+                (market.v_token, v_token.exchange_rate_stored().calldata()),
+                (self.oracle, oracle.get_underlying_price(market.v_token).calldata()),
+            ].into_iter().filter_map(|(target, data)| data.map(|d| (target, d))));
+        }
+        
+        let results = multicall::aggregate3(provider, self.multicall_address, &calls).await?;
+        if results.len() != 1 + self.markets.len() * MARKET_CALLS {
+            anyhow::bail!("Venus: incomplete multicall for {:?}", user);
+        }
         
-        if !error.is_zero() || shortfall.is_zero() {
+        let liquidity: i_venus_comptroller::GetAccountLiquidityReturn = multicall::decode(&results[0])
+            .ok_or_else(|| anyhow::anyhow!("getAccountLiquidity failed"))?;
+        
+        if !liquidity.error.is_zero() || liquidity.shortfall.is_zero() {
             return Ok(None);
         }
         
-        let shortfall_usd = shortfall.as_u128() as f64 / 1e18;
+        let shortfall_usd = liquidity.shortfall.as_u128() as f64 / 1e18;
         
         let mut markets_data = Vec::new();
         let mut total_collateral = 0.0;
         let mut total_borrow = 0.0;
         
-        for market in &self.markets {
-            let v_token = IVToken::new(market.v_token, Arc::new(provider.clone()));
-            
-            let v_token_balance = v_token.balance_of(user).call().await.unwrap_or(U256::zero());
-            let borrow_balance = v_token.borrow_balance_stored(user).call().await.unwrap_or(U256::zero());
+        for (market, market_results) in self.markets.iter().zip(results[1..].chunks(MARKET_CALLS)) {
+            let v_token_balance = multicall::decode::<iv_token::BalanceOfReturn>(&market_results[0])
+                .map(|r| r.0)
+                .unwrap_or(U256::zero());
+            let borrow_balance = multicall::decode::<iv_token::BorrowBalanceStoredReturn>(&market_results[1])
+                .map(|r| r.0)
+                .unwrap_or(U256::zero());
             
             if v_token_balance.is_zero() && borrow_balance.is_zero() {
                 continue;
             }
             
-            let exchange_rate = v_token.exchange_rate_stored().call().await.unwrap_or(U256::from(1e18 as u64));
-            let underlying_balance = v_token_balance * exchange_rate / U256::from(1e18 as u64);
+            let exchange_rate = multicall::decode::<iv_token::ExchangeRateStoredReturn>(&market_results[2])
+                .map(|r| r.0)
+                .unwrap_or(mantissa());
+            let supply_balance = v_token_balance * exchange_rate / mantissa();
+            
+            let price = multicall::decode::<i_venus_oracle::GetUnderlyingPriceReturn>(&market_results[3])
+                .map(|r| r.0)
+                .unwrap_or(U256::zero());
+            if price.is_zero() {
+                debug!("Venus: no oracle price for {}", market.symbol);
+                continue;
+            }
             
-            let supply_usd = underlying_balance.as_u128() as f64 / 10_f64.powi(market.decimals as i32);
-            let borrow_usd = borrow_balance.as_u128() as f64 / 10_f64.powi(market.decimals as i32);
+            let supply_usd = underlying_to_usd(supply_balance, price);
+            let borrow_usd = underlying_to_usd(borrow_balance, price);
             
             total_collateral += supply_usd;
             total_borrow += borrow_usd;
             
-            if supply_usd > 0.0 || borrow_usd > 0.0 {
-                markets_data.push((market.v_token, supply_usd, borrow_usd));
-            }
+            markets_data.push(VenusMarketPosition {
+                market: market.clone(),
+                v_token_balance,
+                supply_balance,
+                borrow_balance,
+                price,
+                supply_usd,
+                borrow_usd,
+            });
         }
         
         Ok(Some(VenusPosition {
@@ -383,11 +512,48 @@ impl VenusProtocol {
             markets: markets_data,
         }))
     }
+    
+    /// Maximum repay amount (in repay underlying units) for a repay/seize pair.
+    /// Bounded by the close factor on the borrow and by the collateral that can
+    /// be seized at the liquidation incentive, with 1% headroom for interest accrual.
+    pub fn max_repay_amount(&self, repay: &VenusMarketPosition, seize: &VenusMarketPosition) -> U256 {
+        let close_factor = U256::from((self.close_factor * 1e18) as u128);
+        let incentive = U256::from((self.liquidation_incentive * 1e18) as u128);
+        
+        let by_close_factor = repay.borrow_balance * close_factor / mantissa();
+        
+        if repay.price.is_zero() || incentive.is_zero() {
+            return U256::zero();
+        }
+        
+        // seizeValue = repay * priceBorrowed * incentive <= supply * priceCollateral
+        let by_collateral = seize.supply_balance * seize.price / repay.price * mantissa() / incentive;
+        
+        by_close_factor.min(by_collateral) * 99 / 100
+    }
+    
+    /// Underlying collateral seized for a given repay amount (before redemption rounding)
+    pub fn seize_amount(&self, repay: &VenusMarketPosition, seize: &VenusMarketPosition, repay_amount: U256) -> U256 {
+        if seize.price.is_zero() {
+            return U256::zero();
+        }
+        
+        let incentive = U256::from((self.liquidation_incentive * 1e18) as u128);
+        repay_amount * repay.price / seize.price * incentive / mantissa()
+    }
+}
+
+/// Convert an underlying amount to USD using a Venus oracle price
+pub fn underlying_to_usd(amount: U256, price: U256) -> f64 {
+    // price is scaled to 1e(36 - decimals), so amount * price is USD * 1e36
+    let scaled = amount * price / mantissa();
+    u256_to_f64(scaled, 18)
 }
 
 /// Discover Venus borrowers from Borrow events
 pub async fn discover_venus_borrowers(
     provider: &Provider<Http>,
+    comptroller: Address,
     from_block: u64,
     to_block: Option<u64>,
 ) -> anyhow::Result<Vec<Address>> {
@@ -400,7 +566,6 @@ pub async fn discover_venus_borrowers(
     // Borrow(address borrower, uint256 borrowAmount, uint256 accountBorrows, uint256 totalBorrows)
     let borrow_topic: H256 = "0x13ed6866d4e1ee6da46f845c46d7e54120883d75c5ea9a2dacc1c4ca8984ab80".parse()?;
     
-    let comptroller: Address = VENUS_COMPTROLLER.parse()?;
     let comptroller_contract = IVenusComptroller::new(comptroller, Arc::new(provider.clone()));
     
     // Get all markets to scan
@@ -0,0 +1,306 @@
+//! Asynchronous receipt tracking.
+//!
+//! Liquidations are handed to the tracker as soon as a node or relay accepts
+//! them, so execution moves on to the next opportunity instead of waiting for
+//! inclusion. The tracker follows each transaction through the nonce sweep's
+//! fee-bump replacements (bundles through their target blocks), credits the
+//! relays that landed it and reports the outcome to stats and the circuit
+//! breaker. Until then its positions stay in the chain's in-flight set so
+//! later scans don't liquidate them again.
+
+use ethers::providers::Middleware;
+use ethers::signers::Signer;
+use ethers::types::{Address, TransactionReceipt, TxHash, U64};
+use futures::future::join_all;
+use reqwest::Client;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::chains::{ChainManager, ChainState};
+use crate::executor::{decode_batch_outcome, BatchItemResult};
+use crate::relays::{self, Relay};
+use crate::simulator::u256_to_f64;
+use crate::types::Position;
+
+/// How often tracked transactions are checked for receipts
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Stop following a transaction that has no receipt after this long
+const TRACK_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Give up on a bundle if blocks stop arriving
+const BUNDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Why a transaction was sent, which decides how its outcome is reported
+#[derive(Debug, Clone)]
+pub enum TxPurpose {
+    /// Liquidation of one position
+    Liquidation { market: Address, user: Address },
+    /// Batched Aave liquidation; per-position results come from its events
+    AaveBatch {
+        pool: Address,
+        users: Vec<Address>,
+        liquidator: Address,
+        debt_decimals: u8,
+        debt_price_usd: f64,
+    },
+    /// Approval or absorb a follow-up call depends on. Its sender waits for
+    /// the receipt instead of handing it to the tracker.
+    Prerequisite,
+}
+
+impl TxPurpose {
+    /// Liquidation of `position`
+    pub fn liquidation(position: &Position) -> Self {
+        TxPurpose::Liquidation { market: position.market, user: position.user }
+    }
+
+    /// (market, user) of every position the transaction liquidates
+    pub fn positions(&self) -> Vec<(Address, Address)> {
+        match self {
+            TxPurpose::Liquidation { market, user } => vec![(*market, *user)],
+            TxPurpose::AaveBatch { pool, users, .. } => users.iter().map(|user| (*pool, *user)).collect(),
+            TxPurpose::Prerequisite => Vec::new(),
+        }
+    }
+}
+
+/// Builder submissions of a bundle, which can only land in its target blocks
+#[derive(Debug)]
+pub struct BundleTargets {
+    pub last_target: u64,
+    /// (builder, target block, bundle hash), by target block
+    pub submitted: Vec<(Arc<Relay>, u64, Option<String>)>,
+}
+
+/// A sent transaction to follow
+#[derive(Debug)]
+pub struct TrackedTx {
+    pub chain: String,
+    pub hash: TxHash,
+    pub nonce: u64,
+    /// Relays that accepted the transaction, credited when it lands
+    pub relays: Vec<Arc<Relay>>,
+    pub purpose: TxPurpose,
+    /// Set when the transaction went out as a bundle
+    pub bundle: Option<BundleTargets>,
+}
+
+/// Handle for handing sent transactions to the tracker
+#[derive(Debug, Clone)]
+pub struct ReceiptTracker {
+    sender: mpsc::UnboundedSender<TrackedTx>,
+}
+
+impl ReceiptTracker {
+    /// A tracker handle and the receiver [`run`] consumes
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<TrackedTx>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+
+    pub fn track(&self, tracked: TrackedTx) {
+        let hash = tracked.hash;
+        if self.sender.send(tracked).is_err() {
+            warn!("Receipt tracker stopped, {:?} is not followed", hash);
+        }
+    }
+}
+
+/// A tracked transaction and every hash its nonce was sent under
+struct InFlight {
+    tracked: TrackedTx,
+    hashes: Vec<TxHash>,
+    sent_at: Instant,
+    /// Bundle target blocks whose builder stats were logged
+    reported: usize,
+}
+
+/// Follow tracked transactions until they're mined or time out
+pub async fn run(chain_manager: Arc<ChainManager>, mut receiver: mpsc::UnboundedReceiver<TrackedTx>) {
+    let mut in_flight: Vec<InFlight> = Vec::new();
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    let client = Client::new();
+
+    loop {
+        tokio::select! {
+            tracked = receiver.recv() => match tracked {
+                Some(tracked) => in_flight.push(InFlight {
+                    hashes: vec![tracked.hash],
+                    tracked,
+                    sent_at: Instant::now(),
+                    reported: 0,
+                }),
+                None => break,
+            },
+            _ = poll.tick() => {
+                if in_flight.is_empty() {
+                    continue;
+                }
+                let checks = in_flight.drain(..).map(|tx| check(&chain_manager, &client, tx));
+                in_flight = join_all(checks).await.into_iter().flatten().collect();
+            }
+        }
+    }
+}
+
+/// Settle `tx` if any of its hashes was mined; hands it back while pending
+async fn check(chain_manager: &ChainManager, client: &Client, mut tx: InFlight) -> Option<InFlight> {
+    let chain = chain_manager.get_chain(&tx.tracked.chain)?;
+    if tx.tracked.bundle.is_some() {
+        return check_bundle(chain_manager, client, &chain, tx).await;
+    }
+
+    // The nonce sweep may have bumped or cancelled it under a new hash
+    if let Some(hash) = chain.nonces.pending_hash(tx.tracked.nonce) {
+        if !tx.hashes.contains(&hash) {
+            tx.hashes.push(hash);
+        }
+    }
+
+    for hash in &tx.hashes {
+        if let Ok(Some(receipt)) = chain.provider().get_transaction_receipt(*hash).await {
+            settle(chain_manager, &chain, &tx.tracked, &receipt).await;
+            return None;
+        }
+    }
+
+    if tx.sent_at.elapsed() > TRACK_TIMEOUT {
+        warn!(
+            "⏰ {}: no receipt for {:?} after {:?}, no longer tracked",
+            tx.tracked.chain, tx.tracked.hash, TRACK_TIMEOUT
+        );
+        release_positions(&chain, &tx.tracked);
+        chain_manager.stats.write().receipt_timeouts += 1;
+        return None;
+    }
+    Some(tx)
+}
+
+/// Follow a bundle through its target blocks, logging builder stats as each
+/// passes; it has missed once the last target is mined without it
+async fn check_bundle(
+    chain_manager: &ChainManager,
+    client: &Client,
+    chain: &ChainState,
+    mut tx: InFlight,
+) -> Option<InFlight> {
+    // Block first, so a missing receipt after the last target means it missed
+    let block = match chain.provider().get_block_number().await {
+        Ok(b) => b.as_u64(),
+        Err(_) => return Some(tx),
+    };
+    if let Ok(Some(receipt)) = chain.provider().get_transaction_receipt(tx.tracked.hash).await {
+        info!("📦 {}: bundle landed in block {}", tx.tracked.chain, receipt.block_number.unwrap_or_default());
+        settle(chain_manager, chain, &tx.tracked, &receipt).await;
+        return None;
+    }
+    
+    let bundle = tx.tracked.bundle.as_ref()?;
+    while let Some((relay, target, bundle_hash)) = bundle.submitted.get(tx.reported) {
+        if *target > block {
+            break;
+        }
+        if let (true, Some(bundle_hash)) = (relay.endpoint.flashbots_auth, bundle_hash) {
+            relays::log_bundle_stats(client, relay, &chain.wallet, bundle_hash, *target).await;
+        }
+        tx.reported += 1;
+    }
+    
+    if block < bundle.last_target && tx.sent_at.elapsed() < BUNDLE_TIMEOUT {
+        return Some(tx);
+    }
+    
+    // Bundles are only valid for their target blocks, so the nonce is free again
+    warn!("⏰ {}: bundle {:?} not included by block {}", tx.tracked.chain, tx.tracked.hash, bundle.last_target);
+    chain.nonces.release(tx.tracked.nonce);
+    release_positions(chain, &tx.tracked);
+    chain_manager.record_failure();
+    None
+}
+
+/// Let later scans liquidate the transaction's positions again
+fn release_positions(chain: &ChainState, tracked: &TrackedTx) {
+    for position in tracked.purpose.positions() {
+        chain.in_flight.remove(&position);
+    }
+}
+
+/// Report a mined transaction's outcome
+async fn settle(chain_manager: &ChainManager, chain: &ChainState, tracked: &TrackedTx, receipt: &TransactionReceipt) {
+    release_positions(chain, tracked);
+    if !tracked.relays.is_empty() {
+        credit_relays(chain, &tracked.relays, receipt).await;
+    }
+
+    // Cancellations are zero-value self-transfers at the same nonce
+    if receipt.to == Some(chain.wallet.address()) {
+        warn!("🚫 {}: {:?} was cancelled by the pending tx sweep", tracked.chain, tracked.hash);
+        chain_manager.stats.write().cancelled += 1;
+        return;
+    }
+
+    let block = receipt.block_number.unwrap_or_default();
+    if receipt.status != Some(U64::from(1)) {
+        error!("❌ {}: TX {:?} reverted in block {}", tracked.chain, receipt.transaction_hash, block);
+        chain_manager.record_failure();
+        return;
+    }
+
+    info!(
+        "✅ {}: TX {:?} mined in block {} (gas {})",
+        tracked.chain, receipt.transaction_hash, block, receipt.gas_used.unwrap_or_default()
+    );
+    match &tracked.purpose {
+        TxPurpose::Liquidation { .. } | TxPurpose::Prerequisite => chain_manager.record_success(),
+        TxPurpose::AaveBatch { liquidator, debt_decimals, debt_price_usd, .. } => {
+            report_batch(chain_manager, receipt, *liquidator, *debt_decimals, *debt_price_usd);
+        }
+    }
+}
+
+/// Per-position results of a landed batch, with each position's share of profit
+fn report_batch(
+    chain_manager: &ChainManager,
+    receipt: &TransactionReceipt,
+    liquidator: Address,
+    debt_decimals: u8,
+    debt_price_usd: f64,
+) {
+    let outcome = decode_batch_outcome(&receipt.logs, liquidator);
+    let to_usd = |amount| u256_to_f64(amount, debt_decimals as u32) * debt_price_usd;
+
+    let mut liquidated = 0;
+    for (user, result) in &outcome.items {
+        match result {
+            BatchItemResult::Liquidated { debt_repaid, collateral_seized } => {
+                let profit = outcome.attributed_profit(*debt_repaid);
+                info!(
+                    "   ✅ {:?}: repaid {}, seized {}, profit {} (~${:.2})",
+                    user, debt_repaid, collateral_seized, profit, to_usd(profit)
+                );
+                chain_manager.record_success();
+                liquidated += 1;
+            }
+            BatchItemResult::Failed { reason } => {
+                info!("   ⏭️ {:?} skipped in batch: {}", user, reason);
+                chain_manager.stats.write().batch_item_failures += 1;
+            }
+        }
+    }
+    info!(
+        "   📊 Batch liquidated {}/{} positions, profit {} (~${:.2})",
+        liquidated, outcome.items.len(), outcome.profit, to_usd(outcome.profit)
+    );
+}
+
+/// Credit a landed transaction to the relays that accepted it, by the block's builder
+pub async fn credit_relays(chain: &ChainState, accepted: &[Arc<Relay>], receipt: &TransactionReceipt) {
+    let author = match receipt.block_hash {
+        Some(hash) => chain.provider().get_block(hash).await.ok().flatten().and_then(|b| b.author),
+        None => None,
+    };
+    chain.relays.credit_inclusion(accepted, author);
+}
@@ -0,0 +1,248 @@
+//! Private orderflow relays and block builders.
+//!
+//! Each chain lists its relays in the registry. Signed transactions and
+//! bundles fan out to several relays in parallel; every relay keeps its own
+//! submission and inclusion counts, and relays that land more of our
+//! transactions are picked first.
+
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::Address;
+use ethers::utils::keccak256;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use crate::config::{RelayEndpoint, RelayKind};
+
+/// Most relays one transaction or bundle is sent to
+const MAX_RELAYS_PER_SUBMISSION: usize = 4;
+
+/// Relays are always included until they've had this many submissions
+const MIN_SUBMISSIONS: u64 = 5;
+
+/// Relay JSON-RPC request
+#[derive(Debug, Serialize)]
+struct RelayRequest<P> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'static str,
+    params: Vec<P>,
+}
+
+/// Relay JSON-RPC response
+#[derive(Debug, Deserialize)]
+struct RelayResponse<R> {
+    #[serde(default = "Option::default")]
+    result: Option<R>,
+    #[serde(default)]
+    error: Option<RelayError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelayError {
+    message: String,
+    #[serde(default)]
+    code: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleStatsParams {
+    bundle_hash: String,
+    block_number: String,
+}
+
+/// `flashbots_getBundleStatsV2` result
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleStats {
+    #[serde(default)]
+    is_simulated: bool,
+    #[serde(default)]
+    is_high_priority: bool,
+    #[serde(default)]
+    considered_by_builders_at: Vec<serde_json::Value>,
+    #[serde(default)]
+    sealed_by_builders_at: Vec<serde_json::Value>,
+}
+
+/// A relay with its inclusion record
+#[derive(Debug)]
+pub struct Relay {
+    pub endpoint: RelayEndpoint,
+    submitted: AtomicU64,
+    accepted: AtomicU64,
+    included: AtomicU64,
+}
+
+impl Relay {
+    fn new(endpoint: RelayEndpoint) -> Self {
+        Self {
+            endpoint,
+            submitted: AtomicU64::new(0),
+            accepted: AtomicU64::new(0),
+            included: AtomicU64::new(0),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.endpoint.name
+    }
+
+    /// Record a submission and whether the relay accepted it
+    pub fn record_submission(&self, accepted: bool) {
+        self.submitted.fetch_add(1, Ordering::Relaxed);
+        if accepted {
+            self.accepted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_inclusion(&self) {
+        self.included.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Share of submissions that landed, smoothed so new relays start at 50%
+    pub fn inclusion_rate(&self) -> f64 {
+        let submitted = self.submitted.load(Ordering::Relaxed) as f64;
+        let included = self.included.load(Ordering::Relaxed) as f64;
+        (included + 1.0) / (submitted + 2.0)
+    }
+
+    fn is_untested(&self) -> bool {
+        self.submitted.load(Ordering::Relaxed) < MIN_SUBMISSIONS
+    }
+
+    pub fn status(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.endpoint.name,
+            "kind": format!("{:?}", self.endpoint.kind),
+            "submitted": self.submitted.load(Ordering::Relaxed),
+            "accepted": self.accepted.load(Ordering::Relaxed),
+            "included": self.included.load(Ordering::Relaxed),
+            "inclusion_rate": self.inclusion_rate(),
+        })
+    }
+}
+
+/// A chain's relays
+#[derive(Debug)]
+pub struct RelaySet {
+    relays: Vec<Arc<Relay>>,
+}
+
+impl RelaySet {
+    pub fn new(endpoints: &[RelayEndpoint]) -> Self {
+        Self {
+            relays: endpoints.iter().cloned().map(|e| Arc::new(Relay::new(e))).collect(),
+        }
+    }
+
+    pub fn has(&self, kind: RelayKind) -> bool {
+        self.relays.iter().any(|r| r.endpoint.kind == kind)
+    }
+
+    /// Relays of `kind` to submit to: untested ones first, then by inclusion rate
+    pub fn select(&self, kind: RelayKind) -> Vec<Arc<Relay>> {
+        let mut relays: Vec<Arc<Relay>> = self.relays
+            .iter()
+            .filter(|r| r.endpoint.kind == kind)
+            .cloned()
+            .collect();
+
+        relays.sort_by(|a, b| {
+            b.is_untested()
+                .cmp(&a.is_untested())
+                .then(b.inclusion_rate().total_cmp(&a.inclusion_rate()))
+        });
+        relays.truncate(MAX_RELAYS_PER_SUBMISSION);
+        relays
+    }
+
+    /// Credit a landed submission to the relay whose builder produced the
+    /// block, or to every relay that accepted it when that can't be told
+    pub fn credit_inclusion(&self, accepted: &[Arc<Relay>], block_author: Option<Address>) {
+        let builder = accepted
+            .iter()
+            .find(|r| r.endpoint.builder.is_some() && r.endpoint.builder == block_author);
+
+        match builder {
+            Some(relay) => relay.record_inclusion(),
+            None => accepted.iter().for_each(|r| r.record_inclusion()),
+        }
+    }
+
+    pub fn status(&self) -> Vec<serde_json::Value> {
+        self.relays.iter().map(|r| r.status()).collect()
+    }
+}
+
+/// JSON-RPC call to a relay, with the Flashbots signature and/or
+/// `Authorization` header the relay is configured for
+pub async fn request<P: Serialize, R: DeserializeOwned>(
+    client: &Client,
+    relay: &RelayEndpoint,
+    wallet: &LocalWallet,
+    method: &'static str,
+    params: P,
+) -> anyhow::Result<R> {
+    let request = RelayRequest {
+        jsonrpc: "2.0",
+        id: 1,
+        method,
+        params: vec![params],
+    };
+    let body = serde_json::to_string(&request)?;
+
+    let mut http = client
+        .post(&relay.url)
+        .header("Content-Type", "application/json");
+
+    if relay.flashbots_auth {
+        // The relay identifies the sender by a signature over the body's hash
+        let body_hash = format!("0x{}", hex::encode(keccak256(body.as_bytes())));
+        let signature = wallet.sign_message(body_hash).await?;
+        http = http.header(
+            "X-Flashbots-Signature",
+            format!("{:?}:0x{}", wallet.address(), hex::encode(signature.to_vec())),
+        );
+    }
+    if let Some(auth) = &relay.auth {
+        http = http.header("Authorization", auth);
+    }
+
+    let response: RelayResponse<R> = http.body(body).send().await?.json().await?;
+
+    if let Some(error) = response.error {
+        anyhow::bail!("{} {}: {} (code: {})", relay.name, method, error.message, error.code);
+    }
+    response.result.ok_or_else(|| anyhow::anyhow!("{} {} returned no result", relay.name, method))
+}
+
+/// Log what builders did with a bundle for one target block
+pub async fn log_bundle_stats(
+    client: &Client,
+    relay: &Relay,
+    wallet: &LocalWallet,
+    bundle_hash: &str,
+    target_block: u64,
+) {
+    let params = BundleStatsParams {
+        bundle_hash: bundle_hash.to_string(),
+        block_number: format!("0x{:x}", target_block),
+    };
+    match request::<_, BundleStats>(client, &relay.endpoint, wallet, "flashbots_getBundleStatsV2", params).await {
+        Ok(stats) => info!(
+            "📊 {} block {}: simulated: {}, high priority: {}, considered by {} builder(s), sealed by {}",
+            relay.name(),
+            target_block,
+            stats.is_simulated,
+            stats.is_high_priority,
+            stats.considered_by_builders_at.len(),
+            stats.sealed_by_builders_at.len()
+        ),
+        Err(e) => debug!("Bundle stats for block {} unavailable: {}", target_block, e),
+    }
+}
@@ -0,0 +1,84 @@
+//! Revert reason decoding for pre-flight liquidation simulations.
+//!
+//! Our flash liquidators revert with `require` strings, the Aave pool with
+//! numeric `Errors.sol` codes, and Comet with custom errors.
+
+use ethers::abi::AbiDecode;
+use ethers::types::U256;
+use thiserror::Error;
+
+/// `Error(string)` selector
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// `Panic(uint256)` selector
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Comet custom errors a liquidation can hit
+const COMET_ERRORS: [&str; 7] = [
+    "NotLiquidatable()",
+    "NotForSale()",
+    "InsufficientReserves()",
+    "TooMuchSlippage()",
+    "Paused()",
+    "BadPrice()",
+    "Unauthorized()",
+];
+
+/// A liquidation whose pre-flight `eth_call` reverted, so it was never sent
+#[derive(Error, Debug)]
+#[error("simulation reverted: {reason}")]
+pub struct SimulationRevert {
+    pub reason: String,
+}
+
+/// Aave V3 (3.3) `Errors.sol` codes relevant to liquidations
+fn aave_error_name(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "26" => "INVALID_AMOUNT",
+        "27" => "RESERVE_INACTIVE",
+        "28" => "RESERVE_FROZEN",
+        "29" => "RESERVE_PAUSED",
+        "32" => "NOT_ENOUGH_AVAILABLE_USER_BALANCE",
+        "34" => "COLLATERAL_BALANCE_IS_ZERO",
+        "43" => "UNDERLYING_BALANCE_ZERO",
+        "45" => "HEALTH_FACTOR_NOT_BELOW_THRESHOLD",
+        "46" => "COLLATERAL_CANNOT_BE_LIQUIDATED",
+        "47" => "SPECIFIED_CURRENCY_NOT_BORROWED_BY_USER",
+        "49" => "INCONSISTENT_FLASHLOAN_PARAMS",
+        "59" => "PRICE_ORACLE_SENTINEL_CHECK_FAILED",
+        "103" => "MUST_NOT_LEAVE_DUST",
+        _ => return None,
+    })
+}
+
+/// Human-readable reason from `eth_call` revert data
+pub fn decode_revert_reason(data: &[u8]) -> String {
+    if data.len() < 4 {
+        return "reverted without reason".to_string();
+    }
+    let (selector, args) = data.split_at(4);
+
+    if selector == ERROR_STRING_SELECTOR {
+        if let Ok(reason) = String::decode(args) {
+            return match aave_error_name(&reason) {
+                Some(name) => format!("Aave error {} ({})", reason, name),
+                None => reason,
+            };
+        }
+    }
+
+    if selector == PANIC_SELECTOR {
+        if let Ok(code) = U256::decode(args) {
+            return format!("panic 0x{:x}", code);
+        }
+    }
+
+    if let Some(error) = COMET_ERRORS
+        .iter()
+        .find(|sig| ethers::utils::id(sig)[..] == *selector)
+    {
+        return format!("Comet {}", error);
+    }
+
+    format!("unknown error 0x{}", hex::encode(selector))
+}
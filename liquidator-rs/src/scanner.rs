@@ -2,24 +2,31 @@
 //! Supports Aave V3, Compound V3, and Venus.
 //! 
 //! Features priority queue for crash days:
-//! - Sort positions by profit potential (biggest first)
+//! - Sort positions by debt (biggest first)
 //! - Time-box scanning (stop after max time)
-//! - Skip positions below profit threshold
+//! - Leave profitability to the exact liquidation simulation
 
+use ethers::providers::Middleware;
 use ethers::types::{Address, U256};
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
-use crate::chains::ChainManager;
-use crate::protocols::aave::{AaveProtocol, LiquidationOpportunity};
-use crate::protocols::compound::{CompoundProtocol, get_comet_addresses};
-use crate::protocols::venus::VenusProtocol;
+use crate::chains::{ChainManager, ChainState};
+use crate::protocols::aave::{AaveProtocol, LiquidationOpportunity, PairEvaluation};
+use crate::protocols::compound::CompoundProtocol;
+use crate::protocols::venus::{VenusProtocol, VenusMarketPosition, underlying_to_usd};
 use crate::types::{Position, Protocol};
-use crate::config::Config;
-use crate::executor::Executor;
-use crate::swap;
+use crate::config::{Config, LiquidatorAbi};
+use crate::borrowers::{BorrowerSource, BorrowerStore};
+use crate::receipts::ReceiptTracker;
+use crate::executor::{group_aave_batches, AaveBatch, AaveLiquidation, BatchItem, Executor};
+use crate::revert::SimulationRevert;
+use crate::flashloan::{FlashLoan, SwapPlan, DEFAULT_AAVE_PREMIUM_BPS};
+use crate::inventory::Funding;
+use crate::swap::{self, MultiDexQuoter, SwapQuote, SwapRoute};
+use crate::simulator::{base_to_usd, CloseFactorRegime};
 
 /// Maximum time to spend scanning per chain (seconds)
 const MAX_SCAN_TIME_SECS: u64 = 10;
@@ -30,49 +37,88 @@ const MAX_POSITIONS_PER_CYCLE: usize = 50;
 /// Minimum debt to even consider (skip tiny positions)
 const MIN_DEBT_THRESHOLD: f64 = 100.0;
 
+/// Ranked Aave pairs to try before giving up on a position
+const MAX_AAVE_PAIR_ATTEMPTS: usize = 3;
+
+/// Outcome of an Aave liquidation attempt
+enum AaveExecution {
+    Executed,
+    /// Position is no longer liquidatable - other pairs won't work either
+    PositionGone,
+    /// This pair failed; the next-best pair may still work
+    Failed,
+}
+
 /// Scanner handles position checking and liquidation execution
 pub struct Scanner {
     pub chain_manager: Arc<ChainManager>,
     pub executor: Executor,
     pub min_profit_usd: f64,
-    /// Cached Aave protocols per chain
-    pub aave_protocols: tokio::sync::RwLock<HashMap<String, AaveProtocol>>,
+    /// Swap quoter used to rank Aave collateral/debt pairs
+    pub quoter: MultiDexQuoter,
+    /// Cached Aave protocols per chain (one per pool)
+    pub aave_protocols: tokio::sync::RwLock<HashMap<String, Vec<AaveProtocol>>>,
+    /// Aave E-Mode category of liquidatable users, per (chain, pool)
+    pub aave_user_emodes: tokio::sync::RwLock<HashMap<(String, Address, Address), u8>>,
     /// Cached Compound protocols per chain (multiple markets per chain)
     pub compound_protocols: tokio::sync::RwLock<HashMap<String, Vec<CompoundProtocol>>>,
-    /// Cached Venus protocol (BNB only)
-    pub venus_protocol: tokio::sync::RwLock<Option<VenusProtocol>>,
+    /// Cached Venus protocols per chain
+    pub venus_protocols: tokio::sync::RwLock<HashMap<String, VenusProtocol>>,
 }
 
 impl Scanner {
-    pub fn new(chain_manager: Arc<ChainManager>, config: &Config) -> Self {
+    pub fn new(chain_manager: Arc<ChainManager>, config: &Config, receipts: ReceiptTracker) -> Self {
         Self {
             chain_manager,
             executor: Executor::new(
                 config.dry_run,
                 config.min_profit_usd,
                 config.mev_threshold_usd,
+                config.gas_profit_share,
+                config.self_funded,
+                config.receive_a_token,
+                receipts,
             ),
             min_profit_usd: config.min_profit_usd,
+            quoter: MultiDexQuoter::new(None),
             aave_protocols: tokio::sync::RwLock::new(HashMap::new()),
+            aave_user_emodes: tokio::sync::RwLock::new(HashMap::new()),
             compound_protocols: tokio::sync::RwLock::new(HashMap::new()),
-            venus_protocol: tokio::sync::RwLock::new(None),
+            venus_protocols: tokio::sync::RwLock::new(HashMap::new()),
         }
     }
     
-    /// Initialize Aave protocol for a chain
+    /// Initialize Aave protocols for a chain (one per pool)
     pub async fn init_aave(&self, chain_name: &str) -> anyhow::Result<()> {
         let chain = match self.chain_manager.get_chain(chain_name) {
             Some(c) => c,
             None => return Ok(()),
         };
         
-        let mut aave = AaveProtocol::new(
-            &chain.config.pool_address,
-            &chain.config.data_provider,
-        )?;
+        if chain.config.aave_markets.is_empty() {
+            return Ok(());
+        }
+        
+        let mut protocols = Vec::new();
+        
+        for market in &chain.config.aave_markets {
+            let mut aave = AaveProtocol::new(market.pool, market.data_provider, chain.config.multicall);
+            
+            match aave.discover_assets(chain.provider()).await {
+                Ok(_) => {
+                    info!("{}: Initialized Aave {} market", chain_name, market.name);
+                    protocols.push(aave);
+                }
+                Err(e) => {
+                    warn!("{}: Failed to init Aave {} - {}", chain_name, market.name, e);
+                }
+            }
+        }
         
-        aave.discover_assets(&chain.provider()).await?;
-        self.aave_protocols.write().await.insert(chain_name.to_string(), aave);
+        if protocols.is_empty() {
+            anyhow::bail!("no Aave market could be initialized");
+        }
+        self.aave_protocols.write().await.insert(chain_name.to_string(), protocols);
         
         Ok(())
     }
@@ -84,23 +130,22 @@ impl Scanner {
             None => return Ok(()),
         };
         
-        let comet_addresses = get_comet_addresses(chain_name);
-        if comet_addresses.is_empty() {
+        if chain.config.comet_markets.is_empty() {
             return Ok(());
         }
         
         let mut protocols = Vec::new();
         
-        for (market_name, base_token, comet_addr) in comet_addresses {
-            let mut compound = CompoundProtocol::new(comet_addr, base_token);
+        for market in &chain.config.comet_markets {
+            let mut compound = CompoundProtocol::new(market.address, &market.base_token, chain.config.multicall);
             
             match compound.discover_assets(&chain.provider()).await {
                 Ok(_) => {
-                    info!("{}: Initialized Compound {} market", chain_name, market_name);
+                    info!("{}: Initialized Compound {} market", chain_name, market.name);
                     protocols.push(compound);
                 }
                 Err(e) => {
-                    warn!("{}: Failed to init Compound {} - {}", chain_name, market_name, e);
+                    warn!("{}: Failed to init Compound {} - {}", chain_name, market.name, e);
                 }
             }
         }
@@ -112,21 +157,43 @@ impl Scanner {
         Ok(())
     }
     
-    /// Get or create Aave protocol for a chain
-    async fn get_aave_protocol(&self, chain_name: &str) -> Option<AaveProtocol> {
+    /// Flash loan the Compound and Venus liquidators take from the chain's
+    /// core Aave pool, at its premium once the pool has been discovered
+    async fn core_aave_flash_loan(&self, chain_name: &str) -> FlashLoan {
+        self.aave_protocols
+            .read()
+            .await
+            .get(chain_name)
+            .and_then(|aaves| aaves.first())
+            .map(|aave| aave.flash_loan())
+            .unwrap_or_else(|| FlashLoan::aave(Address::zero(), DEFAULT_AAVE_PREMIUM_BPS))
+    }
+    
+    /// Get or create Aave protocols for a chain
+    async fn get_aave_protocols(&self, chain_name: &str) -> Vec<AaveProtocol> {
         {
             let protocols = self.aave_protocols.read().await;
-            if let Some(aave) = protocols.get(chain_name) {
-                return Some(aave.clone());
+            if let Some(aaves) = protocols.get(chain_name) {
+                return aaves.clone();
             }
         }
         
         if let Err(e) = self.init_aave(chain_name).await {
             error!("{}: Failed to initialize Aave - {}", chain_name, e);
-            return None;
+            return Vec::new();
         }
         
-        self.aave_protocols.read().await.get(chain_name).cloned()
+        self.aave_protocols.read().await
+            .get(chain_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+    
+    /// Get the Aave protocol for one pool on a chain
+    async fn get_aave_protocol(&self, chain_name: &str, pool: Address) -> Option<AaveProtocol> {
+        self.get_aave_protocols(chain_name).await
+            .into_iter()
+            .find(|aave| aave.pool_address == pool)
     }
     
     /// Get Compound protocols for a chain
@@ -149,84 +216,114 @@ impl Scanner {
             .unwrap_or_default()
     }
     
-    /// Initialize Venus protocol (BNB only)
-    pub async fn init_venus(&self) -> anyhow::Result<()> {
-        let chain = match self.chain_manager.get_chain("bnb") {
+    /// Initialize Venus protocol for a chain
+    pub async fn init_venus(&self, chain_name: &str) -> anyhow::Result<()> {
+        let chain = match self.chain_manager.get_chain(chain_name) {
             Some(c) => c,
             None => return Ok(()),
         };
         
-        let mut venus = VenusProtocol::new();
-        venus.discover_markets(&chain.provider()).await?;
+        let deployment = match &chain.config.venus {
+            Some(d) => d,
+            None => return Ok(()),
+        };
         
-        *self.venus_protocol.write().await = Some(venus);
-        info!("bnb: Venus protocol initialized");
+        let mut venus = VenusProtocol::new(chain_name, deployment.comptroller, chain.config.multicall);
+        venus.discover_markets(chain.provider()).await?;
+        
+        self.venus_protocols.write().await.insert(chain_name.to_string(), venus);
+        info!("{}: Venus protocol initialized", chain_name);
         
         Ok(())
     }
     
-    /// Get Venus protocol
-    async fn get_venus_protocol(&self) -> Option<VenusProtocol> {
+    /// Get Venus protocol for a chain
+    async fn get_venus_protocol(&self, chain_name: &str) -> Option<VenusProtocol> {
         {
-            let protocol = self.venus_protocol.read().await;
-            if protocol.is_some() {
-                return protocol.clone();
+            let protocols = self.venus_protocols.read().await;
+            if let Some(venus) = protocols.get(chain_name) {
+                return Some(venus.clone());
             }
         }
         
-        if let Err(e) = self.init_venus().await {
-            debug!("Failed to initialize Venus - {}", e);
+        if let Err(e) = self.init_venus(chain_name).await {
+            debug!("{}: Failed to initialize Venus - {}", chain_name, e);
             return None;
         }
         
-        self.venus_protocol.read().await.clone()
+        self.venus_protocols.read().await.get(chain_name).cloned()
     }
     
     /// Scan a chain for liquidatable positions (Aave + Compound + Venus)
-    pub async fn scan_chain(&self, chain_name: &str, borrowers: &[Address]) -> Vec<Position> {
+    ///
+    /// Each protocol only checks borrowers discovered from its own markets.
+    pub async fn scan_chain(&self, chain_name: &str, store: &BorrowerStore) -> Vec<Position> {
         let mut all_positions = Vec::new();
         
-        // Scan Aave
-        let aave_positions = self.scan_aave(chain_name, borrowers).await;
-        all_positions.extend(aave_positions);
-        
-        // Scan Compound (uses same borrower list for now)
-        let compound_positions = self.scan_compound(chain_name, borrowers).await;
-        all_positions.extend(compound_positions);
+        let protocols = match self.chain_manager.get_chain(chain_name) {
+            Some(chain) => chain.config.protocols(),
+            None => return all_positions,
+        };
         
-        // Scan Venus (BNB only)
-        if chain_name == "bnb" {
-            let venus_positions = self.scan_venus(borrowers).await;
-            all_positions.extend(venus_positions);
+        // Only scan protocols deployed on this chain
+        for protocol in protocols {
+            let positions = match protocol {
+                // Per-pool and per-Comet borrower sets
+                Protocol::Aave => self.scan_aave(chain_name, store).await,
+                Protocol::Compound => self.scan_compound(chain_name, store).await,
+                Protocol::Venus => {
+                    let borrowers = store.get_protocol_borrowers(chain_name, Protocol::Venus);
+                    self.scan_venus(chain_name, &borrowers).await
+                }
+            };
+            all_positions.extend(positions);
         }
         
         all_positions
     }
     
     /// Scan Aave positions
-    async fn scan_aave(&self, chain_name: &str, borrowers: &[Address]) -> Vec<Position> {
+    async fn scan_aave(&self, chain_name: &str, store: &BorrowerStore) -> Vec<Position> {
         let chain = match self.chain_manager.get_chain(chain_name) {
             Some(c) => c,
             None => return Vec::new(),
         };
         
-        if borrowers.is_empty() {
+        if store.get_protocol_borrowers(chain_name, Protocol::Aave).is_empty() {
             return Vec::new();
         }
         
-        let aave = match self.get_aave_protocol(chain_name).await {
-            Some(a) => a,
-            None => {
-                match AaveProtocol::new(&chain.config.pool_address, &chain.config.data_provider) {
-                    Ok(a) => a,
-                    Err(e) => {
-                        error!("{}: Failed to create Aave protocol - {}", chain_name, e);
-                        return Vec::new();
-                    }
-                }
+        let mut aaves = self.get_aave_protocols(chain_name).await;
+        if aaves.is_empty() {
+            aaves = chain.config.aave_markets
+                .iter()
+                .map(|m| AaveProtocol::new(m.pool, m.data_provider, chain.config.multicall))
+                .collect();
+        }
+        
+        let mut all_positions = Vec::new();
+        
+        for aave in aaves {
+            let source = BorrowerSource::new(chain_name, Protocol::Aave, aave.pool_address);
+            let borrowers = store.get_borrowers(&source);
+            if borrowers.is_empty() {
+                continue;
             }
-        };
+            
+            all_positions.extend(self.scan_aave_pool(&chain, &aave, &borrowers).await);
+        }
         
+        all_positions
+    }
+    
+    /// Check one Aave pool's borrowers and cache E-Modes of the liquidatable ones
+    async fn scan_aave_pool(
+        &self,
+        chain: &ChainState,
+        aave: &AaveProtocol,
+        borrowers: &[Address],
+    ) -> Vec<Position> {
+        let chain_name = chain.config.name.as_str();
         let batch_size = 100;
         let mut all_positions = Vec::new();
         
@@ -241,17 +338,35 @@ impl Scanner {
             }
         }
         
+        // E-Mode changes the bonus we can earn, so look it up for everyone liquidatable
+        let liquidatable: Vec<Address> = all_positions
+            .iter()
+            .filter(|p| p.liquidatable)
+            .map(|p| p.user)
+            .collect();
+        match aave.batch_get_user_emodes(chain.provider(), &liquidatable).await {
+            Ok(emodes) => {
+                let mut cache = self.aave_user_emodes.write().await;
+                for (user, emode) in emodes {
+                    cache.insert((chain_name.to_string(), aave.pool_address, user), emode);
+                }
+            }
+            Err(e) => {
+                debug!("{}: Aave E-Mode lookup failed - {}", chain_name, e);
+            }
+        }
+        
         all_positions
     }
     
     /// Scan Compound positions
-    async fn scan_compound(&self, chain_name: &str, borrowers: &[Address]) -> Vec<Position> {
+    async fn scan_compound(&self, chain_name: &str, store: &BorrowerStore) -> Vec<Position> {
         let chain = match self.chain_manager.get_chain(chain_name) {
             Some(c) => c,
             None => return Vec::new(),
         };
         
-        if borrowers.is_empty() {
+        if store.get_protocol_borrowers(chain_name, Protocol::Compound).is_empty() {
             return Vec::new();
         }
         
@@ -263,7 +378,13 @@ impl Scanner {
         let mut all_positions = Vec::new();
         
         for compound in compounds {
-            match compound.batch_check_users(&chain.provider(), borrowers, chain_name).await {
+            let source = BorrowerSource::new(chain_name, Protocol::Compound, compound.comet_address);
+            let borrowers = store.get_borrowers(&source);
+            if borrowers.is_empty() {
+                continue;
+            }
+            
+            match compound.batch_check_users(chain.provider(), &borrowers, chain_name).await {
                 Ok(positions) => {
                     all_positions.extend(positions);
                 }
@@ -276,9 +397,9 @@ impl Scanner {
         all_positions
     }
     
-    /// Scan Venus positions (BNB only)
-    async fn scan_venus(&self, borrowers: &[Address]) -> Vec<Position> {
-        let chain = match self.chain_manager.get_chain("bnb") {
+    /// Scan Venus positions
+    async fn scan_venus(&self, chain_name: &str, borrowers: &[Address]) -> Vec<Position> {
+        let chain = match self.chain_manager.get_chain(chain_name) {
             Some(c) => c,
             None => return Vec::new(),
         };
@@ -287,7 +408,7 @@ impl Scanner {
             return Vec::new();
         }
         
-        let venus = match self.get_venus_protocol().await {
+        let venus = match self.get_venus_protocol(chain_name).await {
             Some(v) => v,
             None => return Vec::new(),
         };
@@ -295,7 +416,7 @@ impl Scanner {
         match venus.batch_check_users(&chain.provider(), borrowers).await {
             Ok(positions) => positions,
             Err(e) => {
-                debug!("Venus check failed - {}", e);
+                debug!("{}: Venus check failed - {}", chain_name, e);
                 Vec::new()
             }
         }
@@ -303,35 +424,29 @@ impl Scanner {
     
     /// Process detected positions
     /// Process positions with priority queue
-    /// - Sort by estimated profit (debt * bonus - gas)
-    /// - Process biggest opportunities first
+    /// - Sort by debt (biggest first); profit is decided per position by the
+    ///   exact simulation, not estimated here
+    /// - Skip positions with a liquidation already sent and unmined
     /// - Time-box to avoid missing opportunities
     pub async fn process_positions(&self, positions: Vec<Position>) {
         let start_time = Instant::now();
         let max_duration = Duration::from_secs(MAX_SCAN_TIME_SECS);
         
-        // Filter and score positions
-        let mut scored_positions: Vec<(f64, &Position)> = positions.iter()
+        let mut sorted_positions: Vec<&Position> = positions.iter()
             .filter(|p| p.liquidatable && p.debt_usd >= MIN_DEBT_THRESHOLD)
-            .map(|p| {
-                // Estimate profit score: debt * liquidation_bonus - estimated_gas
-                // Higher score = higher priority
-                let bonus = match p.protocol {
-                    Protocol::Aave => 0.05,     // 5% typical Aave bonus
-                    Protocol::Compound => 0.08, // 8% Compound discount
-                    Protocol::Venus => 0.10,    // 10% Venus incentive
-                };
-                let estimated_gas = 5.0; // Rough $5 gas estimate
-                let profit_score = (p.debt_usd * 0.5 * bonus) - estimated_gas; // 50% close factor
-                (profit_score, p)
+            .filter(|p| {
+                let in_flight = self.chain_manager.is_in_flight(p);
+                if in_flight {
+                    debug!("⏳ {} {:?}: liquidation in flight, skipping", p.chain, p.user);
+                }
+                !in_flight
             })
-            .filter(|(score, _)| *score > self.min_profit_usd)
             .collect();
         
-        // Sort by profit score descending (highest first)
-        scored_positions.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        // Sort by debt descending (largest first)
+        sorted_positions.sort_by(|a, b| b.debt_usd.partial_cmp(&a.debt_usd).unwrap_or(std::cmp::Ordering::Equal));
         
-        let total_liquidatable = scored_positions.len();
+        let total_liquidatable = sorted_positions.len();
         
         if total_liquidatable == 0 {
             // Still log critical positions for monitoring
@@ -349,23 +464,35 @@ impl Scanner {
         }
         
         info!(
-            "🔥 {} LIQUIDATABLE (sorted by profit, processing top {})",
+            "🔥 {} LIQUIDATABLE (sorted by debt, processing top {})",
             total_liquidatable,
             MAX_POSITIONS_PER_CYCLE.min(total_liquidatable)
         );
         
         // Log top opportunities
-        for (i, (score, pos)) in scored_positions.iter().take(5).enumerate() {
+        for (i, pos) in sorted_positions.iter().take(5).enumerate() {
             info!(
-                "  #{}: {} {} ${:.0} debt | ~${:.2} profit | HF {:.4}",
-                i + 1, pos.chain, pos.protocol, pos.debt_usd, score, pos.health_factor
+                "  #{}: {} {} ${:.0} debt | HF {:.4}",
+                i + 1, pos.chain, pos.protocol, pos.debt_usd, pos.health_factor
             );
         }
         
-        let mut processed = 0;
-        let mut successful = 0;
+        let candidates: Vec<&Position> = sorted_positions
+            .into_iter()
+            .take(MAX_POSITIONS_PER_CYCLE)
+            .collect();
+        
+        // Aave positions that can share a flash loan and swap go out batched first
+        let (batched, batch_successful) = self.process_aave_batches(&candidates).await;
+        
+        let mut processed = batched.len();
+        let mut successful = batch_successful;
         
-        for (score, pos) in scored_positions.into_iter().take(MAX_POSITIONS_PER_CYCLE) {
+        for pos in candidates {
+            if batched.contains(&(pos.chain.clone(), pos.market, pos.user)) {
+                continue;
+            }
+            
             // Time-box check
             if start_time.elapsed() > max_duration {
                 warn!(
@@ -382,8 +509,8 @@ impl Scanner {
             }
             
             debug!(
-                "Processing #{}: {:?} | ${:.0} | ~${:.2} profit",
-                processed + 1, pos.user, pos.debt_usd, score
+                "Processing #{}: {:?} | ${:.0} debt",
+                processed + 1, pos.user, pos.debt_usd
             );
             
             let result = self.process_liquidatable_prioritized(pos).await;
@@ -402,9 +529,203 @@ impl Scanner {
         }
     }
     
+    /// Plan Aave positions on pools with v2 liquidators together and send
+    /// those sharing a (collateral, debt) pair as one batched transaction;
+    /// the rest of them are liquidated one by one on the spot. Returns the
+    /// (chain, pool, user) of every position handled and how many executed.
+    /// Batches always flash borrow, so self-funded mode skips them.
+    async fn process_aave_batches(&self, positions: &[&Position]) -> (HashSet<(String, Address, Address)>, usize) {
+        let mut handled = HashSet::new();
+        let mut successful = 0;
+        if self.executor.self_funded {
+            return (handled, successful);
+        }
+        
+        let mut pools: HashMap<(String, Address), Vec<&Position>> = HashMap::new();
+        for pos in positions.iter().filter(|p| p.protocol == Protocol::Aave) {
+            pools.entry((pos.chain.clone(), pos.market)).or_default().push(pos);
+        }
+        
+        for ((chain_name, pool), group) in pools {
+            if group.len() < 2 || self.chain_manager.is_circuit_open() {
+                continue;
+            }
+            let chain = match self.chain_manager.get_chain(&chain_name) {
+                Some(c) => c,
+                None => continue,
+            };
+            if chain.config.aave_liquidator_abi != LiquidatorAbi::V2 || chain.config.aave_liquidator(pool).is_none() {
+                continue;
+            }
+            let aave = match self.get_aave_protocol(&chain_name, pool).await {
+                Some(a) => a,
+                None => continue,
+            };
+            
+            let mut locked = Vec::new();
+            for pos in group {
+                handled.insert((pos.chain.clone(), pos.market, pos.user));
+                let key = lock_key(pos);
+                if self.chain_manager.acquire_lock(&key, &pos.chain, pos.protocol) {
+                    locked.push((pos, key));
+                } else {
+                    debug!("Lock already held for {}", key);
+                }
+            }
+            
+            let rankings = futures::future::join_all(
+                locked.iter().map(|(pos, _)| self.rank_aave_pairs(&chain, &aave, pos)),
+            ).await;
+            let ranked: Vec<(&Position, Vec<PairEvaluation>)> = locked
+                .iter()
+                .zip(rankings)
+                .filter_map(|((pos, _), ranked)| Some((*pos, ranked?)))
+                .collect();
+            
+            let groups = group_aave_batches(ranked, |(_, ranked)| {
+                (ranked[0].opportunity.best_collateral.asset, ranked[0].opportunity.best_debt.asset)
+            });
+            for group in groups {
+                if group.len() > 1 {
+                    if let Some(executed) = self.liquidate_aave_batch(&chain, &aave, &group).await {
+                        successful += executed;
+                        continue;
+                    }
+                }
+                for (pos, ranked) in &group {
+                    if self.liquidate_aave_ranked(&chain, &aave, pos, ranked).await {
+                        successful += 1;
+                    }
+                }
+            }
+            
+            for (_, key) in locked {
+                self.chain_manager.release_lock(&key);
+            }
+        }
+        
+        (handled, successful)
+    }
+    
+    /// Liquidate positions whose best pair is the same (collateral, debt) in
+    /// one flash loan and swap. Returns how many were liquidated, or `None`
+    /// when the batch wasn't sent and they should be tried one by one.
+    async fn liquidate_aave_batch(
+        &self,
+        chain: &ChainState,
+        aave: &AaveProtocol,
+        group: &[(&Position, Vec<PairEvaluation>)],
+    ) -> Option<usize> {
+        let evaluations: Vec<&PairEvaluation> = group.iter().map(|(_, ranked)| &ranked[0]).collect();
+        let first = &evaluations[0].opportunity;
+        let (pool, collateral, debt) = (first.pool, first.best_collateral.asset, first.best_debt.asset);
+        
+        let total_debt = evaluations.iter().fold(U256::zero(), |sum, e| sum + e.simulation.debt_to_cover);
+        let total_seized = evaluations.iter().fold(U256::zero(), |sum, e| sum + e.simulation.collateral_seized);
+        let total_debt_value = evaluations.iter().fold(U256::zero(), |sum, e| sum + e.simulation.debt_value);
+        let debt_usd: f64 = group.iter().map(|(pos, _)| pos.debt_usd).sum();
+        if total_debt.is_zero() {
+            return None;
+        }
+        // Debt asset amount -> USD at the oracle price the pairs were ranked with
+        let to_usd = |amount: U256| base_to_usd(amount * total_debt_value / total_debt, aave.base_currency_unit);
+        
+        let quote = match self.quoter
+            .get_best_quote(chain.provider(), &chain.config, collateral, debt, total_seized)
+            .await
+        {
+            Ok(Some(q)) => q,
+            _ => {
+                debug!("   ⚠️ No swap quote for a batch of {} {} -> {}",
+                    group.len(), first.best_collateral.symbol, first.best_debt.symbol);
+                return None;
+            }
+        };
+        let flash = chain.flash.select(chain.provider(), debt, total_debt, aave.flash_loan(), SwapPlan::Quoted(&quote)).await;
+        let repay = total_debt + flash.fee_on(total_debt);
+        let (route, swap_out) = self.aave_swap_route(
+            chain, pool, &quote, (first.best_collateral.decimals, first.best_debt.decimals)
+        ).await;
+        if swap_out < repay {
+            debug!("   ⏭️ Batch swap returns {} < {} needed", swap_out, repay);
+            return None;
+        }
+        let surplus = swap_out - repay;
+        let profit_usd = to_usd(surplus);
+        
+        let mut batch = AaveBatch {
+            collateral_asset: collateral,
+            debt_asset: debt,
+            items: evaluations
+                .iter()
+                .map(|e| BatchItem { user: e.opportunity.user, debt_to_cover: e.simulation.debt_to_cover })
+                .collect(),
+            route,
+            min_profit: U256::zero(),
+            flash,
+            debt_decimals: first.best_debt.decimals,
+            debt_price_usd: to_usd(U256::exp10(first.best_debt.decimals as usize)),
+        };
+        
+        let call = self.executor.aave_batch_call(chain, pool, &batch);
+        let gas_cost = self.executor
+            .estimate_gas_cost_usd(chain, call.as_ref(), 400_000 * group.len() as u64)
+            .await
+            .unwrap_or(5.0);
+        batch.min_profit = self.executor.profit_guard(surplus, profit_usd, gas_cost);
+        
+        let net_profit = profit_usd - gas_cost;
+        if net_profit < self.min_profit_usd {
+            debug!("   ⏭️ Batch unprofitable (net: ${:.2}, gas: ${:.2})", net_profit, gas_cost);
+            return None;
+        }
+        
+        info!(
+            "📦 {} batch of {} Aave liquidations: {} -> {} | ~${:.2} net (gas ${:.2}, {}, route {})",
+            chain.config.name, group.len(), first.best_collateral.symbol, first.best_debt.symbol,
+            net_profit, gas_cost, flash, batch.route
+        );
+        for (pos, ranked) in group {
+            debug!("   • {:?}: repay {} (~${:.2} alone)", pos.user, ranked[0].simulation.debt_to_cover, ranked[0].profit_usd);
+        }
+        
+        {
+            let mut stats = self.chain_manager.stats.write();
+            stats.attempted += group.len() as u64;
+            stats.batches += 1;
+        }
+        
+        if self.executor.dry_run {
+            info!("   🧪 DRY RUN: Would batch-liquidate {} positions", group.len());
+            for _ in group {
+                self.chain_manager.record_success();
+            }
+            return Some(group.len());
+        }
+        
+        match self.executor.execute_aave_batch(chain, pool, &batch, debt_usd, profit_usd).await {
+            Ok(Some(tx_hash)) => {
+                // Per-position results are reported once the receipt tracker sees it mined
+                info!("   📤 Batch TX sent: {:?}", tx_hash);
+                Some(group.len())
+            }
+            Ok(None) => {
+                warn!("   ⚠️ Batch not executed");
+                self.chain_manager.record_failure();
+                Some(0)
+            }
+            Err(e) => {
+                error!("   ❌ Batch failed: {}", e);
+                self.chain_manager.record_execution_error(&e);
+                // A reverting batch never reached the chain; its items may still go alone
+                if e.downcast_ref::<SimulationRevert>().is_some() { None } else { Some(0) }
+            }
+        }
+    }
+    
     /// Process a single liquidatable position (returns true if successful)
     async fn process_liquidatable_prioritized(&self, pos: &Position) -> bool {
-        let lock_key = format!("{}-{}-{:?}", pos.protocol, pos.chain, pos.user);
+        let lock_key = lock_key(pos);
         
         if !self.chain_manager.acquire_lock(&lock_key, &pos.chain, pos.protocol) {
             debug!("Lock already held for {}", lock_key);
@@ -413,14 +734,8 @@ impl Scanner {
         
         let result = match pos.protocol {
             Protocol::Aave => self.process_aave_liquidation(pos).await,
-            Protocol::Compound => {
-                self.process_compound_liquidation(pos).await;
-                false // Compound doesn't return success yet
-            }
-            Protocol::Venus => {
-                self.process_venus_liquidation(pos).await;
-                false // Venus doesn't return success yet
-            }
+            Protocol::Compound => self.process_compound_liquidation(pos).await,
+            Protocol::Venus => self.process_venus_liquidation(pos).await,
         };
         
         self.chain_manager.release_lock(&lock_key);
@@ -434,252 +749,672 @@ impl Scanner {
             None => return false,
         };
         
-        let aave = match self.get_aave_protocol(&pos.chain).await {
+        let aave = match self.get_aave_protocol(&pos.chain, pos.market).await {
             Some(a) => a,
             None => {
-                warn!("Could not get Aave protocol for {}", pos.chain);
+                warn!("Could not get Aave protocol for {} pool {:?}", pos.chain, pos.market);
                 return false;
             }
         };
         
-        let prices: HashMap<Address, f64> = chain.prices
-            .iter()
-            .map(|entry| (*entry.key(), entry.value().price_usd))
-            .collect();
+        let ranked = match self.rank_aave_pairs(&chain, &aave, pos).await {
+            Some(r) => r,
+            None => return false,
+        };
         
-        let opportunity = match aave.find_liquidation_opportunity(
-            &chain.provider(),
+        self.liquidate_aave_ranked(&chain, &aave, pos, &ranked).await
+    }
+    
+    /// Liquidation pairs of an Aave position ranked by swap-checked profit,
+    /// best first (`None` when nothing can be liquidated profitably)
+    async fn rank_aave_pairs(
+        &self,
+        chain: &ChainState,
+        aave: &AaveProtocol,
+        pos: &Position,
+    ) -> Option<Vec<PairEvaluation>> {
+        let user_emode = self.aave_user_emodes
+            .read()
+            .await
+            .get(&(pos.chain.clone(), pos.market, pos.user))
+            .copied();
+        
+        // One block for both price reads, so they share the oracle cache
+        let block = match chain.provider().get_block_number().await {
+            Ok(b) => b.as_u64(),
+            Err(e) => {
+                warn!("  ❌ Failed to get block number: {}", e);
+                return None;
+            }
+        };
+        
+        let opportunities = match aave.find_liquidation_opportunities(
+            chain.provider(),
             pos.user,
             &pos.chain,
-            &prices,
+            user_emode,
+            block,
         ).await {
-            Ok(Some(opp)) => opp,
-            Ok(None) => {
+            Ok(opps) if !opps.is_empty() => opps,
+            Ok(_) => {
                 debug!("  ⚠️ No liquidation opportunity found");
-                return false;
+                return None;
             }
             Err(e) => {
                 warn!("  ❌ Failed to analyze position: {}", e);
-                return false;
+                return None;
             }
         };
         
-        info!(
-            "💰 {} {:?} | {} ${:.0} -> {} ${:.0} | HF {:.4}",
-            pos.chain, pos.user,
-            opportunity.best_collateral.symbol,
-            opportunity.best_collateral.collateral_usd,
-            opportunity.best_debt.symbol,
-            opportunity.best_debt.debt_usd,
-            pos.health_factor
-        );
-        
-        let gas_cost = self.executor
-            .estimate_gas_cost_usd(&chain, 800_000)
-            .await
-            .unwrap_or(5.0);
-        
-        let bonus_pct = opportunity.liquidation_bonus as f64 / 10000.0;
-        let debt_to_cover = opportunity.best_debt.debt_usd * 0.5;
-        let collateral_received = debt_to_cover * (1.0 + bonus_pct);
-        let gross_profit = collateral_received - debt_to_cover;
-        let flash_fee = debt_to_cover * 0.0009;
-        let net_profit = gross_profit - flash_fee - gas_cost;
-        
-        if net_profit < self.min_profit_usd {
-            debug!(
-                "   ⏭️ Skipping unprofitable (net: ${:.2}, gas: ${:.2})",
-                net_profit, gas_cost
-            );
-            self.chain_manager.stats.write().skipped_unprofitable += 1;
-            return false;
+        // Simulate every pair at oracle prices and rank by swap-checked profit
+        match aave.rank_liquidation_pairs(
+            chain.provider(),
+            &chain.config,
+            &self.quoter,
+            &chain.flash,
+            opportunities,
+            block,
+        ).await {
+            Ok(r) if !r.is_empty() => Some(r),
+            Ok(_) => {
+                debug!("   ⏭️ No pair can be liquidated profitably through a swap");
+                self.chain_manager.stats.write().skipped_unprofitable += 1;
+                None
+            }
+            Err(e) => {
+                warn!("   ❌ Failed to read Aave oracle: {}", e);
+                None
+            }
         }
-        
-        info!(
-            "   ✅ Profitable! ~${:.2} (bonus: {:.1}%, gas: ${:.2})",
-            net_profit, bonus_pct * 100.0, gas_cost
-        );
-        
-        // Validate swap path
-        let debt_to_cover_wei = U256::from((debt_to_cover * 10_f64.powi(opportunity.best_debt.decimals as i32)) as u128);
-        
-        let swap_quote = swap::validate_liquidation_swap(
-            &chain.provider(),
-            &pos.chain,
-            opportunity.best_collateral.asset,
-            opportunity.best_debt.asset,
-            opportunity.best_collateral.collateral_balance,
-            debt_to_cover_wei,
-            opportunity.liquidation_bonus,
-        ).await;
-        
-        match swap_quote {
-            Ok(Some(quote)) => {
-                debug!(
-                    "   🔄 Swap path: {} hop(s)",
-                    quote.path.len() - 1
+    }
+    
+    /// Try the top ranked pairs of an Aave position until one executes
+    async fn liquidate_aave_ranked(
+        &self,
+        chain: &ChainState,
+        aave: &AaveProtocol,
+        pos: &Position,
+        ranked: &[PairEvaluation],
+    ) -> bool {
+        for (rank, evaluation) in ranked.iter().take(MAX_AAVE_PAIR_ATTEMPTS).enumerate() {
+            let opportunity = &evaluation.opportunity;
+            let simulation = &evaluation.simulation;
+            
+            info!(
+                "💰 {} {:?} | pair #{} of {}: {} ${:.0} -> {} ${:.0} | HF {:.4}",
+                pos.chain, pos.user, rank + 1, ranked.len(),
+                opportunity.best_collateral.symbol,
+                opportunity.best_collateral.collateral_usd,
+                opportunity.best_debt.symbol,
+                opportunity.best_debt.debt_usd,
+                pos.health_factor
+            );
+            if let Some(emode) = aave.emode_categories.get(&opportunity.user_emode) {
+                info!(
+                    "   E-Mode {} \"{}\" (LT {:.1}%, effective bonus {:.1}%)",
+                    opportunity.user_emode, emode.label,
+                    emode.liquidation_threshold as f64 / 100.0,
+                    opportunity.liquidation_bonus.saturating_sub(10_000) as f64 / 100.0
                 );
             }
-            Ok(None) => {
-                warn!("   ❌ No swap path - skipping");
+            if aave.asset_configs
+                .get(&opportunity.best_collateral.asset)
+                .is_some_and(|c| c.is_isolated())
+            {
+                info!("   Isolated collateral: {}", opportunity.best_collateral.symbol);
+            }
+            
+            // Inventory already holding the debt asset saves the flash loan fee
+            let contract = match chain.config.aave_liquidator_abi {
+                LiquidatorAbi::V1 => None,
+                LiquidatorAbi::V2 => chain.config.aave_liquidator(opportunity.pool),
+            };
+            let funding = self.executor
+                .funding(chain, opportunity.best_debt.asset, simulation.debt_to_cover, contract)
+                .await;
+            let (simulation, profit_usd) = match funding {
+                Funding::FlashLoan => (simulation.clone(), evaluation.profit_usd),
+                _ => (
+                    simulation.with_flash_fee(U256::zero()),
+                    evaluation.profit_usd + base_to_usd(simulation.premium_value, aave.base_currency_unit),
+                ),
+            };
+            
+            match funding {
+                Funding::FlashLoan => debug!(
+                    "   🧮 Simulated: repay {} + {} flash fee {}, seize {} (protocol fee {})",
+                    simulation.debt_to_cover, evaluation.flash_loan, simulation.flash_premium,
+                    simulation.collateral_seized, simulation.protocol_fee
+                ),
+                _ => debug!(
+                    "   🧮 Simulated: repay {} from {}, seize {} (protocol fee {})",
+                    simulation.debt_to_cover, funding,
+                    simulation.collateral_seized, simulation.protocol_fee
+                ),
+            }
+            
+            let mut liquidation = AaveLiquidation {
+                collateral_asset: opportunity.best_collateral.asset,
+                debt_asset: opportunity.best_debt.asset,
+                debt_to_cover: simulation.debt_to_cover,
+                route: SwapRoute::BestDex,
+                min_profit: U256::zero(),
+                flash: evaluation.flash_loan,
+                funding,
+            };
+            
+            // v2 liquidators execute the quoted swap instead of picking their own.
+            // Wallet liquidations and kept aTokens sell nothing.
+            let sells_collateral = match funding {
+                Funding::FlashLoan => true,
+                Funding::Wallet => false,
+                Funding::Contract(_) => !self.executor.receive_a_token,
+            };
+            let mut swap_out = U256::zero();
+            if chain.config.aave_liquidator_abi == LiquidatorAbi::V2 && sells_collateral {
+                if liquidation.collateral_asset == liquidation.debt_asset {
+                    swap_out = simulation.collateral_seized;
+                } else if let Some(quote) = &evaluation.swap_quote {
+                    (liquidation.route, swap_out) = self.aave_swap_route(
+                        chain, opportunity.pool, quote,
+                        (opportunity.best_collateral.decimals, opportunity.best_debt.decimals),
+                    ).await;
+                }
+            }
+            
+            let call = self.executor
+                .aave_liquidation_call(chain, opportunity.pool, opportunity.user, &liquidation)
+                .unwrap_or_default();
+            let gas_cost = self.executor
+                .estimate_gas_cost_usd(chain, call.as_ref(), chain.config.gas_limit)
+                .await
+                .unwrap_or(5.0);
+            
+            // Revert on-chain if the swap leaves less than gas + minimum profit
+            let surplus = swap_out.saturating_sub(simulation.repay_amount());
+            liquidation.min_profit = self.executor.profit_guard(surplus, profit_usd, gas_cost);
+            
+            // Ranked best first, so nothing further down pays either
+            let net_profit = profit_usd - gas_cost;
+            if net_profit < self.min_profit_usd {
+                debug!(
+                    "   ⏭️ Skipping unprofitable (net: ${:.2}, gas: ${:.2})",
+                    net_profit, gas_cost
+                );
                 self.chain_manager.stats.write().skipped_unprofitable += 1;
                 return false;
             }
-            Err(e) => {
-                debug!("   ⚠️ Swap check failed: {} - attempting anyway", e);
+            
+            let bonus_pct = opportunity.liquidation_bonus.saturating_sub(10_000) as f64 / 10000.0;
+            info!(
+                "   ✅ Profitable! ~${:.2} ({}, bonus: {:.1}%, repay {} {}, seize {} {}, gas: ${:.2})",
+                net_profit, simulation.regime, bonus_pct * 100.0,
+                simulation.debt_to_cover, opportunity.best_debt.symbol,
+                simulation.collateral_seized, opportunity.best_collateral.symbol,
+                gas_cost
+            );
+            match &evaluation.swap_quote {
+                Some(quote) => debug!(
+                    "   🔄 Swap via {}: {} hop(s), {} out",
+                    quote.source, quote.path.len().saturating_sub(1), quote.amount_out
+                ),
+                None => debug!("   ⚠️ No swap quote - attempting anyway"),
+            }
+            if chain.config.aave_liquidator_abi == LiquidatorAbi::V2 && sells_collateral {
+                debug!("   🛡️ Route {}, min profit {}", liquidation.route, liquidation.min_profit);
+            }
+            if funding != Funding::FlashLoan {
+                info!("   💼 Funding from {}", funding);
+            }
+            
+            {
+                let mut stats = self.chain_manager.stats.write();
+                stats.attempted += 1;
+                match simulation.regime {
+                    CloseFactorRegime::Default => stats.close_factor_default += 1,
+                    CloseFactorRegime::LowHealthFactor => stats.close_factor_low_hf += 1,
+                    CloseFactorRegime::SmallPosition => stats.close_factor_small_position += 1,
+                }
+            }
+            
+            match self.execute_aave_liquidation(opportunity, &liquidation, profit_usd).await {
+                AaveExecution::Executed => return true,
+                AaveExecution::PositionGone => return false,
+                AaveExecution::Failed => {
+                    if rank + 1 < ranked.len().min(MAX_AAVE_PAIR_ATTEMPTS) {
+                        info!("   ↪️ Falling back to the next pair");
+                    }
+                }
             }
         }
         
-        self.chain_manager.stats.write().attempted += 1;
-        self.execute_aave_liquidation(&opportunity, debt_to_cover_wei).await
+        false
     }
     
-    /// Process Compound liquidation
-    async fn process_compound_liquidation(&self, pos: &Position) {
+    /// Process Compound liquidation (returns true if executed)
+    /// Absorbs the account, then buys the absorbed collateral at the Comet's
+    /// store-front discount and swaps it back to the base token.
+    async fn process_compound_liquidation(&self, pos: &Position) -> bool {
         let chain = match self.chain_manager.get_chain(&pos.chain) {
             Some(c) => c,
-            None => return,
+            None => return false,
         };
         
         let compounds = self.get_compound_protocols(&pos.chain).await;
+        let flash_loan = self.core_aave_flash_loan(&pos.chain).await;
         
         // Find which Comet market this user is in
         for compound in compounds {
-            if let Ok(Some(details)) = compound.get_liquidation_details(&chain.provider(), pos.user).await {
-                info!(
-                    "   📊 Compound {} market: ${:.0} borrow, {} collateral assets",
-                    details.base_token,
-                    details.borrow_usd,
-                    details.collaterals.len()
-                );
-                
-                // Estimate profit (Compound uses absorb mechanism - liquidator gets collateral discount)
-                let gas_cost = self.executor
-                    .estimate_gas_cost_usd(&chain, 500_000)
-                    .await
-                    .unwrap_or(3.0);
-                
-                // Compound typically offers ~5-8% discount on collateral
-                let discount = 0.05;
-                let gross_profit = pos.collateral_usd * discount;
-                let net_profit = gross_profit - gas_cost;
-                
-                if net_profit < self.min_profit_usd {
-                    info!("   ⏭️ Skipping unprofitable Compound (net: ${:.2})", net_profit);
+            let details = match compound.get_liquidation_details(chain.provider(), pos.user).await {
+                Ok(Some(d)) => d,
+                _ => continue,
+            };
+            
+            info!(
+                "   📊 Compound {} market: ${:.0} borrow, ${:.0} collateral ({} assets)",
+                details.base_token,
+                details.borrow_usd,
+                pos.collateral_usd,
+                details.collaterals.len()
+            );
+            
+            // Absorbing only pays off if the absorbed collateral can be bought
+            match compound.collateral_for_sale(chain.provider()).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    info!("   ⏭️ Compound reserves at target - collateral not for sale");
                     self.chain_manager.stats.write().skipped_unprofitable += 1;
-                    return;
+                    return false;
+                }
+                Err(e) => {
+                    warn!("   ❌ Failed to read Compound reserves: {}", e);
+                    return false;
                 }
+            }
+            
+            let base_price = match compound.get_base_price(chain.provider()).await {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("   ❌ Failed to price Compound base token: {}", e);
+                    return false;
+                }
+            };
+            
+            // Pick the collateral whose discounted purchase swaps back for the best margin
+            let mut best: Option<(Address, U256, U256)> = None; // (asset, base_amount, margin)
+            
+            for (asset, balance, _usd) in &details.collaterals {
+                let base_amount = match compound
+                    .base_for_collateral(chain.provider(), *asset, *balance)
+                    .await
+                {
+                    Ok(b) if !b.is_zero() => b,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        debug!("   ⚠️ quoteCollateral failed for {:?}: {}", asset, e);
+                        continue;
+                    }
+                };
                 
-                info!("   ✅ Compound profitable! Expected: ${:.2}", net_profit);
+                let repay = base_amount + flash_loan.fee_on(base_amount);
                 
-                self.chain_manager.stats.write().attempted += 1;
+                let quote = match swap::validate_liquidation_swap(
+                    chain.provider(),
+                    &chain.config,
+                    *asset,
+                    compound.base_token,
+                    *balance,
+                    repay,
+                    0, // quoteCollateral already includes the discount
+                ).await {
+                    Ok(Some(q)) => q,
+                    Ok(None) => {
+                        debug!("   ❌ No profitable swap path for {:?}", asset);
+                        continue;
+                    }
+                    Err(e) => {
+                        debug!("   ⚠️ Swap check failed for {:?}: {}", asset, e);
+                        continue;
+                    }
+                };
                 
-                if self.executor.dry_run {
-                    info!("   🧪 DRY RUN: Would absorb Compound position");
-                    info!("      Comet: {:?}", compound.comet_address);
-                    info!("      User: {:?}", pos.user);
-                    self.chain_manager.record_success();
-                } else {
-                    // TODO: Implement actual Compound absorb call
-                    warn!("   ⚠️ Compound execution not yet implemented");
-                    self.chain_manager.record_failure();
+                let margin = quote.amount_out - repay;
+                if best.as_ref().is_none_or(|(_, _, m)| margin > *m) {
+                    best = Some((*asset, base_amount, margin));
                 }
-                
-                return;
             }
+            
+            let (collateral_asset, base_amount, margin) = match best {
+                Some(b) => b,
+                None => {
+                    info!("   ⏭️ No Compound collateral can be bought back profitably");
+                    self.chain_manager.stats.write().skipped_unprofitable += 1;
+                    return false;
+                }
+            };
+            
+            // The flash buy can't be estimated until the absorb has landed
+            let absorb = self.executor
+                .compound_absorb_call(&chain, compound.comet_address, vec![pos.user])
+                .ok();
+            let gas_cost = match tokio::try_join!(
+                self.executor.estimate_gas_cost_usd(&chain, absorb.as_ref(), 500_000),
+                self.executor.estimate_gas_cost_usd(&chain, None, chain.config.gas_limit),
+            ) {
+                Ok((absorb_cost, buy_cost)) => absorb_cost + buy_cost,
+                Err(_) => 3.0,
+            };
+            
+            let gross_profit = compound.base_to_usd(margin, base_price);
+            let net_profit = gross_profit - gas_cost;
+            
+            if net_profit < self.min_profit_usd {
+                info!("   ⏭️ Skipping unprofitable Compound (net: ${:.2})", net_profit);
+                self.chain_manager.stats.write().skipped_unprofitable += 1;
+                return false;
+            }
+            
+            info!(
+                "   ✅ Compound profitable! Expected: ${:.2} (collateral {:?}, gas: ${:.2})",
+                net_profit, collateral_asset, gas_cost
+            );
+            
+            self.chain_manager.stats.write().attempted += 1;
+            
+            if self.executor.dry_run {
+                info!("   🧪 DRY RUN: Would absorb Compound position");
+                info!("      Comet: {:?}", compound.comet_address);
+                info!("      User: {:?}", pos.user);
+                info!("      Buy: {:?} for {} base", collateral_asset, base_amount);
+                self.chain_manager.record_success();
+                return true;
+            }
+            
+            return self.execute_compound_liquidation(
+                &chain,
+                &compound,
+                pos,
+                collateral_asset,
+                base_amount,
+                gross_profit,
+            ).await;
         }
         
         warn!("   Could not find Compound market for user");
+        false
+    }
+    
+    /// Execute Compound absorb + buyCollateral (returns true if successful)
+    async fn execute_compound_liquidation(
+        &self,
+        chain: &ChainState,
+        compound: &CompoundProtocol,
+        pos: &Position,
+        collateral_asset: Address,
+        base_amount: U256,
+        expected_profit_usd: f64,
+    ) -> bool {
+        // Step 1: absorb. If a competitor absorbed first the collateral is
+        // still sitting in the Comet's reserves, so carry on to the purchase.
+        match self.executor.execute_compound_absorb(
+            chain,
+            compound.comet_address,
+            vec![pos.user],
+            expected_profit_usd,
+        ).await {
+            Ok(Some(tx_hash)) => info!("   🧽 Absorb TX: {:?}", tx_hash),
+            Ok(None) => warn!("   ⚠️ Absorb not confirmed - checking reserves anyway"),
+            Err(e) => warn!("   ⚠️ Absorb failed: {} - checking reserves anyway", e),
+        }
+        
+        // Step 2: buy what is actually in reserves now, capped at what we validated
+        let purchase = match compound
+            .plan_collateral_purchase(chain.provider(), collateral_asset)
+            .await
+        {
+            Ok(Some(p)) => p,
+            Ok(None) => {
+                warn!("   ⚠️ No Compound collateral for sale after absorb");
+                self.chain_manager.record_failure();
+                return false;
+            }
+            Err(e) => {
+                error!("   ❌ Failed to plan collateral purchase: {}", e);
+                self.chain_manager.record_failure();
+                return false;
+            }
+        };
+        
+        let base_amount = purchase.base_amount.min(base_amount);
+        debug!(
+            "   🛒 Buying up to {} of {:?} for {} base",
+            purchase.collateral_amount, purchase.asset, base_amount
+        );
+        
+        match self.executor.execute_compound_liquidation(
+            chain,
+            pos,
+            compound.comet_address,
+            purchase.asset,
+            base_amount,
+            expected_profit_usd,
+        ).await {
+            Ok(Some(tx_hash)) => {
+                // The receipt tracker reports the outcome once it's mined
+                info!("   📤 TX sent: {:?}", tx_hash);
+                true
+            }
+            Ok(None) => {
+                warn!("   ⚠️ Not executed");
+                self.chain_manager.record_failure();
+                false
+            }
+            Err(e) => {
+                error!("   ❌ Failed: {}", e);
+                self.chain_manager.record_execution_error(&e);
+                false
+            }
+        }
     }
     
-    /// Process Venus liquidation
-    async fn process_venus_liquidation(&self, pos: &Position) {
-        let chain = match self.chain_manager.get_chain("bnb") {
+    /// Process Venus liquidation (returns true if executed)
+    async fn process_venus_liquidation(&self, pos: &Position) -> bool {
+        let chain = match self.chain_manager.get_chain(&pos.chain) {
             Some(c) => c,
-            None => return,
+            None => return false,
         };
         
-        let venus = match self.get_venus_protocol().await {
+        let venus = match self.get_venus_protocol(&pos.chain).await {
             Some(v) => v,
             None => {
                 warn!("Could not get Venus protocol");
-                return;
+                return false;
             }
         };
         
         // Get detailed position info
-        if let Ok(Some(details)) = venus.get_liquidation_details(&chain.provider(), pos.user).await {
-            info!(
-                "   📊 Venus: ${:.0} collateral, ${:.0} borrow, ${:.0} shortfall",
-                details.total_collateral_usd,
-                details.total_borrow_usd,
-                details.shortfall
-            );
-            
-            // Find best market to liquidate (highest borrow)
-            let best_borrow_market = details.markets.iter()
-                .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
-            
-            let best_collateral_market = details.markets.iter()
-                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-            
-            if let (Some(borrow), Some(collateral)) = (best_borrow_market, best_collateral_market) {
-                info!(
-                    "   Best borrow: {:?} ${:.0} | Best collateral: {:?} ${:.0}",
-                    borrow.0, borrow.2, collateral.0, collateral.1
-                );
+        let details = match venus.get_liquidation_details(chain.provider(), pos.user).await {
+            Ok(Some(d)) => d,
+            _ => {
+                warn!("   Could not get Venus liquidation details");
+                return false;
             }
-            
-            // Estimate profit
-            let gas_cost = self.executor
-                .estimate_gas_cost_usd(&chain, 600_000)
-                .await
-                .unwrap_or(2.0);
-            
-            // Venus liquidation incentive (typically 10%)
-            let incentive = venus.liquidation_incentive - 1.0;
-            let repay_amount = pos.debt_usd * venus.close_factor;
-            let gross_profit = repay_amount * incentive;
-            let net_profit = gross_profit - gas_cost;
-            
-            if net_profit < self.min_profit_usd {
-                info!("   ⏭️ Skipping unprofitable Venus (net: ${:.2})", net_profit);
-                self.chain_manager.stats.write().skipped_unprofitable += 1;
-                return;
+        };
+        
+        info!(
+            "   📊 Venus: ${:.0} collateral, ${:.0} borrow, ${:.0} shortfall",
+            details.total_collateral_usd,
+            details.total_borrow_usd,
+            details.shortfall
+        );
+        
+        // Venus pays the same incentive on every market, so the best pair is the
+        // one that allows the largest repay within close factor and seizable collateral
+        let mut best: Option<(&VenusMarketPosition, &VenusMarketPosition, U256, f64)> = None;
+        
+        for repay in details.markets.iter().filter(|m| !m.borrow_balance.is_zero()) {
+            for seize in details.markets.iter().filter(|m| !m.supply_balance.is_zero()) {
+                let repay_amount = venus.max_repay_amount(repay, seize);
+                let repay_usd = underlying_to_usd(repay_amount, repay.price);
+                
+                if best.as_ref().is_none_or(|(_, _, _, usd)| repay_usd > *usd) {
+                    best = Some((repay, seize, repay_amount, repay_usd));
+                }
             }
-            
+        }
+        
+        let (repay, seize, repay_amount, repay_usd) = match best {
+            Some(b) if !b.2.is_zero() => b,
+            _ => {
+                info!("   ⏭️ No Venus market pair can be liquidated");
+                return false;
+            }
+        };
+        
+        info!(
+            "   Repay: {} ${:.0} of ${:.0} | Seize: {} ${:.0} ({} vTokens held)",
+            repay.market.symbol, repay_usd, repay.borrow_usd,
+            seize.market.symbol, seize.supply_usd, seize.v_token_balance
+        );
+        
+        // Seized collateral must swap back to cover the flash loan
+        let flash_loan = self.core_aave_flash_loan(&pos.chain).await;
+        let owed = repay_amount + flash_loan.fee_on(repay_amount);
+        let seized = venus.seize_amount(repay, seize, repay_amount);
+        
+        let proceeds = if seize.market.repay_token() == repay.market.repay_token() {
+            seized
+        } else {
+            match swap::validate_liquidation_swap(
+                chain.provider(),
+                &chain.config,
+                seize.market.repay_token(),
+                repay.market.repay_token(),
+                seized,
+                owed,
+                0, // seize_amount already includes the incentive
+            ).await {
+                Ok(Some(quote)) => quote.amount_out,
+                Ok(None) => {
+                    warn!("   ❌ No swap path - skipping");
+                    self.chain_manager.stats.write().skipped_unprofitable += 1;
+                    return false;
+                }
+                Err(e) => {
+                    warn!("   ❌ Swap check failed: {}", e);
+                    return false;
+                }
+            }
+        };
+        
+        if proceeds < owed {
+            info!("   ⏭️ Seized collateral does not cover repay + flash fee");
+            self.chain_manager.stats.write().skipped_unprofitable += 1;
+            return false;
+        }
+        
+        // Estimate profit
+        let call = self.executor
+            .venus_liquidation_call(&chain, pos.user, &repay.market, &seize.market, repay_amount)
+            .unwrap_or_default();
+        let gas_cost = self.executor
+            .estimate_gas_cost_usd(&chain, call.as_ref(), 600_000)
+            .await
+            .unwrap_or(2.0);
+        
+        let gross_profit = underlying_to_usd(proceeds - owed, repay.price);
+        let net_profit = gross_profit - gas_cost;
+        
+        if net_profit < self.min_profit_usd {
+            info!("   ⏭️ Skipping unprofitable Venus (net: ${:.2})", net_profit);
+            self.chain_manager.stats.write().skipped_unprofitable += 1;
+            return false;
+        }
+        
+        info!(
+            "   ✅ Venus profitable! Expected: ${:.2} (incentive: {:.0}%)",
+            net_profit, (venus.liquidation_incentive - 1.0) * 100.0
+        );
+        
+        self.chain_manager.stats.write().attempted += 1;
+        
+        if self.executor.dry_run {
+            info!("   🧪 DRY RUN: Would execute Venus liquidation");
+            info!("      User: {:?}", pos.user);
             info!(
-                "   ✅ Venus profitable! Expected: ${:.2} (incentive: {:.0}%)",
-                net_profit, incentive * 100.0
+                "      Repay: {:.4} {} (${:.0})",
+                repay.market.to_units(repay_amount), repay.market.symbol, repay_usd
             );
-            
-            self.chain_manager.stats.write().attempted += 1;
-            
-            if self.executor.dry_run {
-                info!("   🧪 DRY RUN: Would execute Venus liquidation");
-                info!("      User: {:?}", pos.user);
-                info!("      Repay: ${:.0}", repay_amount);
-                self.chain_manager.record_success();
-            } else {
-                // TODO: Implement actual Venus liquidateBorrow call
-                warn!("   ⚠️ Venus execution not yet implemented");
+            self.chain_manager.record_success();
+            return true;
+        }
+        
+        match self.executor.execute_venus_liquidation(
+            &chain,
+            pos,
+            &repay.market,
+            &seize.market,
+            repay_amount,
+            gross_profit,
+        ).await {
+            Ok(Some(tx_hash)) => {
+                // The receipt tracker reports the outcome once it's mined
+                info!("   📤 TX sent: {:?}", tx_hash);
+                true
+            }
+            Ok(None) => {
+                warn!("   ⚠️ Not executed");
                 self.chain_manager.record_failure();
+                false
+            }
+            Err(e) => {
+                error!("   ❌ Failed: {}", e);
+                self.chain_manager.record_execution_error(&e);
+                false
             }
-        } else {
-            warn!("   Could not get Venus liquidation details");
         }
     }
     
-    /// Execute Aave liquidation (returns true if successful)
-    async fn execute_aave_liquidation(&self, opportunity: &LiquidationOpportunity, debt_to_cover: U256) -> bool {
+    /// Swap route a v2 Aave liquidator should execute for `quote`, and the
+    /// output it's expected to return. Aggregator quotes are re-requested with
+    /// calldata for the market's liquidator; without it the contract falls
+    /// back to its own DEX choice. `decimals` are the collateral's and debt's.
+    async fn aave_swap_route(
+        &self,
+        chain: &ChainState,
+        pool: Address,
+        quote: &SwapQuote,
+        decimals: (u8, u8),
+    ) -> (SwapRoute, U256) {
+        let liquidator = match chain.config.aave_liquidator(pool) {
+            Some(l) => l,
+            None => return (SwapRoute::from_quote(quote), quote.amount_out),
+        };
+        
+        match self.quoter.with_swap_calldata(chain.config.chain_id, quote.clone(), liquidator, decimals).await {
+            Ok(q) => (SwapRoute::from_quote(&q), q.amount_out),
+            Err(e) => {
+                debug!("   ⚠️ {} swap calldata failed: {}", quote.source, e);
+                (SwapRoute::from_quote(quote), quote.amount_out)
+            }
+        }
+    }
+    
+    /// Execute Aave liquidation for one pair
+    async fn execute_aave_liquidation(
+        &self,
+        opportunity: &LiquidationOpportunity,
+        liquidation: &AaveLiquidation,
+        expected_profit_usd: f64,
+    ) -> AaveExecution {
         let chain = match self.chain_manager.get_chain(&opportunity.chain) {
             Some(c) => c,
-            None => return false,
+            None => return AaveExecution::Failed,
         };
         
-        let aave = match self.get_aave_protocol(&opportunity.chain).await {
+        let aave = match self.get_aave_protocol(&opportunity.chain, opportunity.pool).await {
             Some(a) => a,
-            None => return false,
+            None => return AaveExecution::Failed,
         };
         
         match aave.get_user_data(&chain.provider(), opportunity.user).await {
@@ -687,7 +1422,7 @@ impl Scanner {
                 if hf >= 1.0 {
                     info!("   👀 Competitor beat us (HF now: {:.4})", hf);
                     self.chain_manager.stats.write().competitor_beats += 1;
-                    return false;
+                    return AaveExecution::PositionGone;
                 }
             }
             Err(e) => {
@@ -698,7 +1433,7 @@ impl Scanner {
         if self.executor.dry_run {
             info!("   🧪 DRY RUN: Would liquidate {:?}", opportunity.user);
             self.chain_manager.record_success();
-            return true;
+            return AaveExecution::Executed;
         }
         
         match self.executor.execute_aave_liquidation(
@@ -707,30 +1442,35 @@ impl Scanner {
                 user: opportunity.user,
                 chain: opportunity.chain.clone(),
                 protocol: Protocol::Aave,
+                market: opportunity.pool,
                 collateral_usd: opportunity.total_collateral_usd,
                 debt_usd: opportunity.total_debt_usd,
                 health_factor: opportunity.health_factor,
                 liquidatable: true,
             },
-            opportunity.best_collateral.asset,
-            opportunity.best_debt.asset,
-            debt_to_cover,
+            liquidation,
+            expected_profit_usd,
         ).await {
             Ok(Some(tx_hash)) => {
-                info!("   ✅ TX: {:?}", tx_hash);
-                self.chain_manager.record_success();
-                true
+                // The receipt tracker reports the outcome once it's mined
+                info!("   📤 TX sent: {:?}", tx_hash);
+                AaveExecution::Executed
             }
             Ok(None) => {
                 warn!("   ⚠️ Not executed");
                 self.chain_manager.record_failure();
-                false
+                AaveExecution::Failed
             }
             Err(e) => {
                 error!("   ❌ Failed: {}", e);
-                self.chain_manager.record_failure();
-                false
+                self.chain_manager.record_execution_error(&e);
+                AaveExecution::Failed
             }
         }
     }
 }
+
+/// Execution lock key of a position
+fn lock_key(pos: &Position) -> String {
+    format!("{}-{}-{:?}", pos.protocol, pos.chain, pos.user)
+}
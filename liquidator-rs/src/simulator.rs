@@ -0,0 +1,405 @@
+//! Exact-integer liquidation simulation.
+//!
+//! Reproduces Aave V3 `LiquidationLogic` with `U256` math so the debt we
+//! pass to `executeLiquidation` and the profit we decide on are the amounts
+//! the pool will actually use:
+//! - close factor regimes (see [`CloseFactorRegime`])
+//! - per-reserve liquidation bonus (e.g. 10500 = 5% bonus)
+//! - protocol fee taken from the bonus part of the seized collateral
+//! - collateral cap: if the bonus-adjusted seize exceeds the user's balance,
+//!   the whole balance is seized and the debt repaid shrinks to match
+//! - dust rule: a partial liquidation must leave at least `MIN_LEFTOVER_BASE`
+//!   of both debt and collateral, or the pool reverts
+//!
+//! Prices are the Aave oracle's base-currency prices (USD, 8 decimals).
+
+use ethers::types::U256;
+
+/// 100.00% in Aave's percentage math
+pub const PERCENTAGE_FACTOR: u64 = 10_000;
+
+/// Default close factor (50%)
+pub const DEFAULT_LIQUIDATION_CLOSE_FACTOR: u64 = 5_000;
+
+/// Below this health factor the whole reserve debt can be liquidated (0.95 WAD)
+pub const CLOSE_FACTOR_HF_THRESHOLD: u128 = 950_000_000_000_000_000;
+
+/// Reserve debt or collateral below this (base currency) can be fully liquidated
+pub const MIN_BASE_MAX_CLOSE_FACTOR_THRESHOLD: u128 = 2_000 * 100_000_000;
+
+/// Minimum debt and collateral a partial liquidation must leave (base currency)
+pub const MIN_LEFTOVER_BASE: u128 = MIN_BASE_MAX_CLOSE_FACTOR_THRESHOLD / 2;
+
+/// Which close factor rule the pool applies to a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseFactorRegime {
+    /// 50% of the user's total debt
+    Default,
+    /// Health factor below 0.95: 100% of the reserve debt
+    LowHealthFactor,
+    /// Reserve debt or collateral below $2,000: 100% of the reserve debt
+    SmallPosition,
+}
+
+impl CloseFactorRegime {
+    /// Determine the regime from the pool's account data
+    pub fn for_position(
+        health_factor: U256,
+        reserve_debt_value: U256,
+        reserve_collateral_value: U256,
+    ) -> Self {
+        let threshold = U256::from(MIN_BASE_MAX_CLOSE_FACTOR_THRESHOLD);
+        if reserve_debt_value < threshold || reserve_collateral_value < threshold {
+            CloseFactorRegime::SmallPosition
+        } else if health_factor <= U256::from(CLOSE_FACTOR_HF_THRESHOLD) {
+            CloseFactorRegime::LowHealthFactor
+        } else {
+            CloseFactorRegime::Default
+        }
+    }
+
+    /// Close factor in bps
+    pub fn close_factor(&self) -> u64 {
+        match self {
+            CloseFactorRegime::Default => DEFAULT_LIQUIDATION_CLOSE_FACTOR,
+            CloseFactorRegime::LowHealthFactor | CloseFactorRegime::SmallPosition => PERCENTAGE_FACTOR,
+        }
+    }
+}
+
+impl std::fmt::Display for CloseFactorRegime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CloseFactorRegime::Default => write!(f, "50% close factor"),
+            CloseFactorRegime::LowHealthFactor => write!(f, "100% close factor (HF < 0.95)"),
+            CloseFactorRegime::SmallPosition => write!(f, "100% close factor (small position)"),
+        }
+    }
+}
+
+/// Aave `PercentageMath.percentMul` (rounds half up)
+pub fn percent_mul(value: U256, percentage: U256) -> U256 {
+    (value * percentage + U256::from(PERCENTAGE_FACTOR / 2)) / U256::from(PERCENTAGE_FACTOR)
+}
+
+/// Aave `PercentageMath.percentDiv` (rounds half up)
+pub fn percent_div(value: U256, percentage: U256) -> U256 {
+    if percentage.is_zero() {
+        return U256::zero();
+    }
+    (value * U256::from(PERCENTAGE_FACTOR) + percentage / 2) / percentage
+}
+
+/// Convert a scaled integer to f64 without losing the high limbs
+pub fn u256_to_f64(value: U256, decimals: u32) -> f64 {
+    let mut result = 0.0;
+    for (i, limb) in value.0.iter().enumerate() {
+        result += *limb as f64 * 2f64.powi(64 * i as i32);
+    }
+    result / 10f64.powi(decimals as i32)
+}
+
+/// One (collateral, debt) reserve pair of a user, in on-chain units
+#[derive(Debug, Clone)]
+pub struct AaveLiquidationInput {
+    /// User's debt in the debt reserve (variable + stable)
+    pub debt_balance: U256,
+    /// User's aToken balance in the collateral reserve
+    pub collateral_balance: U256,
+    /// Oracle price of the debt asset in base currency
+    pub debt_price: U256,
+    /// Oracle price of the collateral asset in base currency
+    pub collateral_price: U256,
+    pub debt_decimals: u8,
+    pub collateral_decimals: u8,
+    /// Collateral reserve liquidation bonus (10500 = 5% bonus)
+    pub liquidation_bonus: U256,
+    /// Collateral reserve protocol fee, as a share of the bonus (bps)
+    pub liquidation_protocol_fee: U256,
+    /// Pool health factor (WAD)
+    pub health_factor: U256,
+    /// User's total debt across reserves in base currency
+    pub total_debt_value: U256,
+    /// Flash loan premium charged on the borrowed debt (bps)
+    pub flash_premium: U256,
+}
+
+/// What the pool will do for a given `debtToCover`
+#[derive(Debug, Clone)]
+pub struct AaveLiquidationResult {
+    /// Close factor rule the pool applied
+    pub regime: CloseFactorRegime,
+    /// Debt actually repaid (pass this as `debtToCover`)
+    pub debt_to_cover: U256,
+    /// Collateral sent to the liquidator (after protocol fee)
+    pub collateral_seized: U256,
+    /// Collateral sent to the treasury
+    pub protocol_fee: U256,
+    /// Flash loan premium owed on `debt_to_cover`
+    pub flash_premium: U256,
+    /// `debt_to_cover` in base currency
+    pub debt_value: U256,
+    /// `collateral_seized` in base currency
+    pub collateral_value: U256,
+    /// `flash_premium` in base currency
+    pub premium_value: U256,
+}
+
+impl AaveLiquidationResult {
+    /// Gross profit in base currency (zero if the trade loses money)
+    pub fn profit_value(&self) -> U256 {
+        self.collateral_value
+            .saturating_sub(self.debt_value)
+            .saturating_sub(self.premium_value)
+    }
+
+    /// Gross profit in USD
+    pub fn profit_usd(&self, base_currency_unit: U256) -> f64 {
+        base_to_usd(self.profit_value(), base_currency_unit)
+    }
+
+    /// Debt repaid plus flash premium - what the swap must return
+    pub fn repay_amount(&self) -> U256 {
+        self.debt_to_cover + self.flash_premium
+    }
+
+    /// The same liquidation funded by a flash loan charging `flash_fee`
+    pub fn with_flash_fee(&self, flash_fee: U256) -> Self {
+        let premium_value = if self.debt_to_cover.is_zero() {
+            U256::zero()
+        } else {
+            flash_fee * self.debt_value / self.debt_to_cover
+        };
+        Self {
+            flash_premium: flash_fee,
+            premium_value,
+            ..self.clone()
+        }
+    }
+}
+
+/// Convert a base-currency value to USD
+pub fn base_to_usd(value: U256, base_currency_unit: U256) -> f64 {
+    let decimals = base_currency_unit.to_string().len().saturating_sub(1) as u32;
+    u256_to_f64(value, decimals)
+}
+
+/// Simulate `liquidationCall(collateral, debt, user, debt_to_cover, false)`.
+///
+/// Returns `None` when nothing can be liquidated (no debt, no collateral or
+/// missing prices) or when the pool would revert with `MUST_NOT_LEAVE_DUST`.
+pub fn simulate_aave_liquidation(
+    input: &AaveLiquidationInput,
+    debt_to_cover: U256,
+) -> Option<AaveLiquidationResult> {
+    if input.debt_balance.is_zero()
+        || input.collateral_balance.is_zero()
+        || input.debt_price.is_zero()
+        || input.collateral_price.is_zero()
+        || input.liquidation_bonus.is_zero()
+    {
+        return None;
+    }
+
+    let debt_unit = U256::exp10(input.debt_decimals as usize);
+    let collateral_unit = U256::exp10(input.collateral_decimals as usize);
+
+    let reserve_debt_value = input.debt_balance * input.debt_price / debt_unit;
+    let reserve_collateral_value = input.collateral_balance * input.collateral_price / collateral_unit;
+    let regime = CloseFactorRegime::for_position(
+        input.health_factor,
+        reserve_debt_value,
+        reserve_collateral_value,
+    );
+
+    // The whole reserve debt, unless the close factor caps it at a share
+    // of the user's total debt
+    let mut max_liquidatable_debt = input.debt_balance;
+    let close_factor = regime.close_factor();
+    if close_factor < PERCENTAGE_FACTOR {
+        let liquidatable_value = percent_mul(input.total_debt_value, U256::from(close_factor));
+        if reserve_debt_value > liquidatable_value {
+            max_liquidatable_debt = liquidatable_value * debt_unit / input.debt_price;
+        }
+    }
+    let actual_debt = debt_to_cover.min(max_liquidatable_debt);
+
+    // _calculateAvailableCollateralToLiquidate
+    let base_collateral = (input.debt_price * actual_debt * collateral_unit)
+        / (input.collateral_price * debt_unit);
+    let max_collateral = percent_mul(base_collateral, input.liquidation_bonus);
+
+    let (collateral_amount, debt_needed) = if max_collateral > input.collateral_balance {
+        let debt_needed = percent_div(
+            (input.collateral_price * input.collateral_balance * debt_unit)
+                / (input.debt_price * collateral_unit),
+            input.liquidation_bonus,
+        );
+        (input.collateral_balance, debt_needed)
+    } else {
+        (max_collateral, actual_debt)
+    };
+
+    let protocol_fee = if input.liquidation_protocol_fee.is_zero() {
+        U256::zero()
+    } else {
+        let bonus_collateral = collateral_amount - percent_div(collateral_amount, input.liquidation_bonus);
+        percent_mul(bonus_collateral, input.liquidation_protocol_fee)
+    };
+    let collateral_seized = collateral_amount - protocol_fee;
+
+    if debt_needed.is_zero() || collateral_seized.is_zero() {
+        return None;
+    }
+
+    // MUST_NOT_LEAVE_DUST: partial liquidations must leave enough behind
+    if debt_needed < input.debt_balance && collateral_amount < input.collateral_balance {
+        let min_leftover = U256::from(MIN_LEFTOVER_BASE);
+        let debt_left = (input.debt_balance - debt_needed) * input.debt_price / debt_unit;
+        let collateral_left = (input.collateral_balance - collateral_amount) * input.collateral_price / collateral_unit;
+        if debt_left < min_leftover || collateral_left < min_leftover {
+            return None;
+        }
+    }
+
+    let flash_premium = percent_mul(debt_needed, input.flash_premium);
+
+    Some(AaveLiquidationResult {
+        regime,
+        debt_to_cover: debt_needed,
+        collateral_seized,
+        protocol_fee,
+        flash_premium,
+        debt_value: debt_needed * input.debt_price / debt_unit,
+        collateral_value: collateral_seized * input.collateral_price / collateral_unit,
+        premium_value: flash_premium * input.debt_price / debt_unit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAD: u128 = 1_000_000_000_000_000_000;
+    const USDC: u128 = 1_000_000;
+    const BASE: u128 = 100_000_000;
+
+    /// 10,000 USDC debt against 10 WETH at $2,000, 5% bonus, HF 0.98
+    fn input() -> AaveLiquidationInput {
+        AaveLiquidationInput {
+            debt_balance: U256::from(10_000 * USDC),
+            collateral_balance: U256::from(10 * WAD),
+            debt_price: U256::from(BASE),
+            collateral_price: U256::from(2_000 * BASE),
+            debt_decimals: 6,
+            collateral_decimals: 18,
+            liquidation_bonus: U256::from(10_500),
+            liquidation_protocol_fee: U256::zero(),
+            health_factor: U256::from(980_000_000_000_000_000u128),
+            total_debt_value: U256::from(10_000 * BASE),
+            flash_premium: U256::from(5),
+        }
+    }
+
+    #[test]
+    fn percent_math_rounds_half_up() {
+        assert_eq!(percent_mul(U256::from(10_001), U256::from(5_000)), U256::from(5_001));
+        assert_eq!(percent_div(U256::from(100), U256::from(10_500)), U256::from(95));
+        assert_eq!(percent_div(U256::from(100), U256::zero()), U256::zero());
+    }
+
+    #[test]
+    fn regime_follows_health_factor_and_reserve_size() {
+        let large = U256::from(2_000 * BASE);
+        let small = U256::from(1_999 * BASE);
+        let threshold = U256::from(CLOSE_FACTOR_HF_THRESHOLD);
+
+        assert_eq!(CloseFactorRegime::for_position(threshold + 1, large, large), CloseFactorRegime::Default);
+        assert_eq!(CloseFactorRegime::for_position(threshold, large, large), CloseFactorRegime::LowHealthFactor);
+        assert_eq!(CloseFactorRegime::for_position(threshold + 1, small, large), CloseFactorRegime::SmallPosition);
+        assert_eq!(CloseFactorRegime::for_position(threshold + 1, large, small), CloseFactorRegime::SmallPosition);
+    }
+
+    #[test]
+    fn default_close_factor_caps_at_half_the_debt() {
+        let result = simulate_aave_liquidation(&input(), U256::MAX).unwrap();
+
+        assert_eq!(result.regime, CloseFactorRegime::Default);
+        assert_eq!(result.debt_to_cover, U256::from(5_000 * USDC));
+        // $5,000 of WETH plus the 5% bonus
+        assert_eq!(result.collateral_seized, U256::from(2_625 * WAD / 1_000));
+        assert_eq!(result.flash_premium, U256::from(2_500_000));
+        assert_eq!(result.profit_value(), U256::from(250 * BASE - 25 * BASE / 10));
+    }
+
+    #[test]
+    fn low_health_factor_liquidates_the_whole_reserve_debt() {
+        let mut input = input();
+        input.health_factor = U256::from(CLOSE_FACTOR_HF_THRESHOLD);
+        let result = simulate_aave_liquidation(&input, U256::MAX).unwrap();
+
+        assert_eq!(result.regime, CloseFactorRegime::LowHealthFactor);
+        assert_eq!(result.debt_to_cover, input.debt_balance);
+        assert_eq!(result.collateral_seized, U256::from(525 * WAD / 100));
+    }
+
+    #[test]
+    fn small_reserve_liquidates_the_whole_reserve_debt() {
+        let mut input = input();
+        input.debt_balance = U256::from(1_500 * USDC);
+        input.total_debt_value = U256::from(1_500 * BASE);
+        let result = simulate_aave_liquidation(&input, U256::MAX).unwrap();
+
+        assert_eq!(result.regime, CloseFactorRegime::SmallPosition);
+        assert_eq!(result.debt_to_cover, input.debt_balance);
+    }
+
+    #[test]
+    fn collateral_cap_shrinks_the_debt_repaid() {
+        let mut input = input();
+        input.collateral_balance = U256::from(WAD);
+        input.health_factor = U256::from(WAD / 2);
+        let result = simulate_aave_liquidation(&input, U256::MAX).unwrap();
+
+        // All 1 WETH is seized for $2,000 / 1.05 of debt
+        assert_eq!(result.collateral_seized, U256::from(WAD));
+        assert_eq!(result.debt_to_cover, U256::from(1_904_761_905u64));
+    }
+
+    #[test]
+    fn protocol_fee_comes_out_of_the_bonus() {
+        let mut input = input();
+        input.liquidation_protocol_fee = U256::from(1_000);
+        let result = simulate_aave_liquidation(&input, U256::MAX).unwrap();
+
+        // 10% of the 0.125 WETH bonus goes to the treasury
+        assert_eq!(result.protocol_fee, U256::from(125 * WAD / 10_000));
+        assert_eq!(result.collateral_seized, U256::from(26_125 * WAD / 10_000));
+    }
+
+    #[test]
+    fn partial_liquidation_must_not_leave_dust() {
+        let mut input = input();
+        input.collateral_balance = U256::from(3 * WAD);
+
+        // Seizing 2.625 WETH would leave $750 of collateral
+        assert!(simulate_aave_liquidation(&input, U256::MAX).is_none());
+        // A smaller repay leaves enough of both behind
+        let result = simulate_aave_liquidation(&input, U256::from(1_000 * USDC)).unwrap();
+        assert_eq!(result.debt_to_cover, U256::from(1_000 * USDC));
+    }
+
+    #[test]
+    fn with_flash_fee_reprices_the_premium() {
+        let result = simulate_aave_liquidation(&input(), U256::MAX).unwrap();
+
+        let free = result.with_flash_fee(U256::zero());
+        assert_eq!(free.premium_value, U256::zero());
+        assert_eq!(free.profit_value(), U256::from(250 * BASE));
+
+        let one_usdc = result.with_flash_fee(U256::from(USDC));
+        assert_eq!(one_usdc.flash_premium, U256::from(USDC));
+        assert_eq!(one_usdc.premium_value, U256::from(BASE));
+        assert_eq!(one_usdc.repay_amount(), U256::from(5_001 * USDC));
+    }
+}
@@ -17,6 +17,8 @@ use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
+use crate::config::ChainConfig;
+
 // Uniswap V3 Quoter ABI
 abigen!(
     IQuoterV2,
@@ -25,38 +27,15 @@ abigen!(
     ]"#
 );
 
-/// Chain IDs for aggregator APIs
-fn get_chain_id(chain: &str) -> Option<u64> {
-    match chain {
-        "ethereum" => Some(1),
-        "base" => Some(8453),
-        "polygon" => Some(137),
-        "arbitrum" => Some(42161),
-        "avalanche" => Some(43114),
-        "bnb" => Some(56),
-        _ => None,
-    }
-}
-
-/// Uniswap V3 Quoter addresses by chain
-fn get_quoter_address(chain: &str) -> Option<Address> {
-    let addr = match chain {
-        "base" => "0x3d4e44Eb1374240CE5F1B871ab261CD16335B76a",
-        "polygon" => "0x61fFE014bA17989E743c5F6cB21bF9697530B21e",
-        "arbitrum" => "0x61fFE014bA17989E743c5F6cB21bF9697530B21e",
-        "avalanche" => "0xbe0F5544EC67e9B3b2D979aaA43f18Fd87E6257F",
-        "bnb" => "0x78D78E420Da98ad378D7799bE8f4AF69033EB077",
-        _ => return None,
-    };
-    addr.parse().ok()
-}
-
 /// Common pool fees for Uniswap V3
 pub const FEE_LOWEST: u32 = 100;
 pub const FEE_LOW: u32 = 500;
 pub const FEE_MEDIUM: u32 = 3000;
 pub const FEE_HIGH: u32 = 10000;
 
+/// Slippage allowed on aggregator swap calldata (basis points)
+pub const AGGREGATOR_SLIPPAGE_BPS: u32 = 100;
+
 /// DEX source for the quote
 #[derive(Debug, Clone, PartialEq)]
 pub enum DexSource {
@@ -83,6 +62,8 @@ pub struct SwapQuote {
     pub amount_in: U256,
     pub amount_out: U256,
     pub fee: u32,
+    /// Uniswap V3 pool fee of each hop in `path` (empty for aggregators)
+    pub fees: Vec<u32>,
     pub price_impact: f64,
     pub path: Vec<Address>,
     pub source: DexSource,
@@ -92,6 +73,59 @@ pub struct SwapQuote {
     pub to: Option<Address>,
 }
 
+impl SwapQuote {
+    /// Packed Uniswap V3 path (`token | fee | token ...`) for `exactInput`
+    pub fn uniswap_path(&self) -> Option<Bytes> {
+        if self.source != DexSource::UniswapV3 || self.fees.len() + 1 != self.path.len() {
+            return None;
+        }
+        
+        let mut path = self.path[0].as_bytes().to_vec();
+        for (fee, token) in self.fees.iter().zip(&self.path[1..]) {
+            path.extend_from_slice(&fee.to_be_bytes()[1..]);
+            path.extend_from_slice(token.as_bytes());
+        }
+        Some(Bytes::from(path))
+    }
+}
+
+/// How the flash liquidator sells seized collateral
+/// (`SwapRoute` in contracts/FlashLiquidatorV2.sol)
+#[derive(Debug, Clone, Default)]
+pub enum SwapRoute {
+    /// The contract picks a DEX itself
+    #[default]
+    BestDex,
+    /// `exactInput` along a packed path on the contract's Uniswap V3 router
+    UniswapV3 { path: Bytes },
+    /// Aggregator swap calldata, called on `target`
+    Aggregator { target: Address, calldata: Bytes },
+}
+
+impl std::fmt::Display for SwapRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SwapRoute::BestDex => write!(f, "best DEX"),
+            SwapRoute::UniswapV3 { path } => write!(f, "Uniswap V3 path ({} bytes)", path.len()),
+            SwapRoute::Aggregator { target, .. } => write!(f, "aggregator {:?}", target),
+        }
+    }
+}
+
+impl SwapRoute {
+    /// Route executing `quote`: its aggregator calldata or Uniswap path,
+    /// falling back to the contract's own DEX choice
+    pub fn from_quote(quote: &SwapQuote) -> Self {
+        if let (Some(target), Some(calldata)) = (quote.to, &quote.calldata) {
+            return SwapRoute::Aggregator { target, calldata: Bytes::from(calldata.clone()) };
+        }
+        match quote.uniswap_path() {
+            Some(path) => SwapRoute::UniswapV3 { path },
+            None => SwapRoute::BestDex,
+        }
+    }
+}
+
 /// 1inch API response structures
 #[derive(Debug, Deserialize)]
 struct OneInchQuoteResponse {
@@ -110,9 +144,8 @@ struct OneInchSwapResponse {
 
 #[derive(Debug, Deserialize)]
 struct OneInchTx {
-    to: String,
-    data: String,
-    value: String,
+    to: Address,
+    data: Bytes,
 }
 
 /// Paraswap API response structures
@@ -130,6 +163,19 @@ struct ParaswapPriceRoute {
     gas_cost_usd: Option<String>,
 }
 
+/// Paraswap price route kept verbatim for `/transactions`
+#[derive(Debug, Deserialize)]
+struct ParaswapRawPriceResponse {
+    #[serde(rename = "priceRoute")]
+    price_route: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParaswapTxResponse {
+    to: Address,
+    data: Bytes,
+}
+
 /// Multi-DEX swap quoter
 pub struct MultiDexQuoter {
     http_client: Client,
@@ -154,18 +200,12 @@ impl MultiDexQuoter {
     pub async fn get_best_quote(
         &self,
         provider: &Provider<Http>,
-        chain: &str,
+        chain: &ChainConfig,
         token_in: Address,
         token_out: Address,
         amount_in: U256,
     ) -> anyhow::Result<Option<SwapQuote>> {
-        let chain_id = match get_chain_id(chain) {
-            Some(id) => id,
-            None => {
-                warn!("Unknown chain: {}", chain);
-                return Ok(None);
-            }
-        };
+        let chain_id = chain.chain_id;
         
         // Try sources in priority order, return first successful
         // This is fast-fail - we don't wait for all sources
@@ -207,15 +247,12 @@ impl MultiDexQuoter {
     pub async fn get_best_quote_parallel(
         &self,
         provider: &Provider<Http>,
-        chain: &str,
+        chain: &ChainConfig,
         token_in: Address,
         token_out: Address,
         amount_in: U256,
     ) -> anyhow::Result<Option<SwapQuote>> {
-        let chain_id = match get_chain_id(chain) {
-            Some(id) => id,
-            None => return Ok(None),
-        };
+        let chain_id = chain.chain_id;
         
         // Run all quotes in parallel
         let (oneinch, paraswap, uniswap) = tokio::join!(
@@ -294,6 +331,7 @@ impl MultiDexQuoter {
             amount_in,
             amount_out,
             fee: 0,
+            fees: Vec::new(),
             price_impact: 0.0,
             path: vec![token_in, token_out],
             source: DexSource::OneInch,
@@ -334,6 +372,7 @@ impl MultiDexQuoter {
             amount_in,
             amount_out,
             fee: 0,
+            fees: Vec::new(),
             price_impact: 0.0,
             path: vec![token_in, token_out],
             source: DexSource::Paraswap,
@@ -342,16 +381,122 @@ impl MultiDexQuoter {
         }))
     }
     
+    /// Attach executable swap calldata to an aggregator quote, with `from`
+    /// (the flash liquidator) as sender and recipient. `decimals` are those of
+    /// the input and output tokens. Uniswap quotes are returned unchanged
+    /// since their route is the path itself.
+    pub async fn with_swap_calldata(
+        &self,
+        chain_id: u64,
+        quote: SwapQuote,
+        from: Address,
+        decimals: (u8, u8),
+    ) -> anyhow::Result<SwapQuote> {
+        let (to, calldata, amount_out) = match quote.source {
+            DexSource::OneInch => self.swap_1inch(chain_id, &quote, from).await?,
+            DexSource::Paraswap => self.swap_paraswap(chain_id, &quote, from, decimals).await?,
+            DexSource::UniswapV3 => return Ok(quote),
+        };
+        
+        Ok(SwapQuote {
+            amount_out,
+            calldata: Some(calldata.to_vec()),
+            to: Some(to),
+            ..quote
+        })
+    }
+    
+    /// 1inch swap transaction for `quote`
+    async fn swap_1inch(
+        &self,
+        chain_id: u64,
+        quote: &SwapQuote,
+        from: Address,
+    ) -> anyhow::Result<(Address, Bytes, U256)> {
+        // The liquidator only holds the collateral mid-flash-loan, so 1inch
+        // can't check its balance or estimate gas up front
+        let url = format!(
+            "https://api.1inch.dev/swap/v6.0/{}/swap?src={:?}&dst={:?}&amount={}&from={:?}&origin={:?}&slippage={}&disableEstimate=true&allowPartialFill=false",
+            chain_id, quote.token_in, quote.token_out, quote.amount_in, from, from,
+            AGGREGATOR_SLIPPAGE_BPS as f64 / 100.0
+        );
+        
+        let mut request = self.http_client.get(&url);
+        
+        if let Some(ref api_key) = self.oneinch_api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            anyhow::bail!("1inch swap: HTTP {}", response.status());
+        }
+        
+        let swap: OneInchSwapResponse = response.json().await?;
+        Ok((swap.tx.to, swap.tx.data, U256::from_dec_str(&swap.dst_amount)?))
+    }
+    
+    /// Paraswap swap transaction for `quote`, built from a fresh price route
+    async fn swap_paraswap(
+        &self,
+        chain_id: u64,
+        quote: &SwapQuote,
+        from: Address,
+        (src_decimals, dest_decimals): (u8, u8),
+    ) -> anyhow::Result<(Address, Bytes, U256)> {
+        let url = format!(
+            "https://apiv5.paraswap.io/prices?srcToken={:?}&destToken={:?}&amount={}&srcDecimals={}&destDecimals={}&side=SELL&network={}",
+            quote.token_in, quote.token_out, quote.amount_in, src_decimals, dest_decimals, chain_id
+        );
+        
+        let response = self.http_client.get(&url).send().await?;
+        
+        if !response.status().is_success() {
+            anyhow::bail!("Paraswap prices: HTTP {}", response.status());
+        }
+        
+        let prices: ParaswapRawPriceResponse = response.json().await?;
+        let amount_out = prices.price_route
+            .get("destAmount")
+            .and_then(|a| a.as_str())
+            .map(U256::from_dec_str)
+            .transpose()?
+            .unwrap_or(quote.amount_out);
+        
+        let url = format!(
+            "https://apiv5.paraswap.io/transactions/{}?ignoreChecks=true&ignoreGasEstimate=true",
+            chain_id
+        );
+        let body = serde_json::json!({
+            "srcToken": format!("{:?}", quote.token_in),
+            "destToken": format!("{:?}", quote.token_out),
+            "srcAmount": quote.amount_in.to_string(),
+            "slippage": AGGREGATOR_SLIPPAGE_BPS,
+            "priceRoute": prices.price_route,
+            "userAddress": format!("{:?}", from),
+        });
+        
+        let response = self.http_client.post(&url).json(&body).send().await?;
+        
+        if !response.status().is_success() {
+            anyhow::bail!("Paraswap transactions: HTTP {}", response.status());
+        }
+        
+        let tx: ParaswapTxResponse = response.json().await?;
+        Ok((tx.to, tx.data, amount_out))
+    }
+    
     /// Quote from Uniswap V3 on-chain
     async fn quote_uniswap(
         &self,
         provider: &Provider<Http>,
-        chain: &str,
+        chain: &ChainConfig,
         token_in: Address,
         token_out: Address,
         amount_in: U256,
     ) -> anyhow::Result<Option<SwapQuote>> {
-        let quoter_addr = match get_quoter_address(chain) {
+        let quoter_addr = match chain.quoter_address {
             Some(a) => a,
             None => return Ok(None),
         };
@@ -380,6 +525,7 @@ impl MultiDexQuoter {
                         amount_in,
                         amount_out,
                         fee,
+                        fees: vec![fee],
                         price_impact: 0.0,
                         path: vec![token_in, token_out],
                         source: DexSource::UniswapV3,
@@ -392,9 +538,7 @@ impl MultiDexQuoter {
         }
         
         // Try multi-hop through intermediates
-        let intermediates = get_intermediate_tokens(chain);
-        
-        for intermediate in intermediates {
+        for &intermediate in &chain.intermediate_tokens {
             if intermediate == token_in || intermediate == token_out {
                 continue;
             }
@@ -446,6 +590,7 @@ impl MultiDexQuoter {
                             amount_in,
                             amount_out,
                             fee: first_fee.max(fee),
+                            fees: vec![first_fee, fee],
                             price_impact: 0.0,
                             path: vec![token_in, intermediate, token_out],
                             source: DexSource::UniswapV3,
@@ -461,34 +606,6 @@ impl MultiDexQuoter {
     }
 }
 
-fn get_intermediate_tokens(chain: &str) -> Vec<Address> {
-    match chain {
-        "base" => vec![
-            "0x4200000000000000000000000000000000000006".parse().unwrap(), // WETH
-            "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913".parse().unwrap(), // USDC
-        ],
-        "polygon" => vec![
-            "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619".parse().unwrap(), // WETH
-            "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270".parse().unwrap(), // WMATIC
-            "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174".parse().unwrap(), // USDC
-        ],
-        "arbitrum" => vec![
-            "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1".parse().unwrap(), // WETH
-            "0xaf88d065e77c8cC2239327C5EDb3A432268e5831".parse().unwrap(), // USDC
-        ],
-        "avalanche" => vec![
-            "0xB31f66AA3C1e785363F0875A1B74E27b85FD66c7".parse().unwrap(), // WAVAX
-            "0xB97EF9Ef8734C71904D8002F8b6Bc66Dd9c48a6E".parse().unwrap(), // USDC
-        ],
-        "bnb" => vec![
-            "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c".parse().unwrap(), // WBNB
-            "0x8AC76a51cc950d9822D68b83fE1Ad97B32Cd580d".parse().unwrap(), // USDC
-            "0x55d398326f99059fF775485246999027B3197955".parse().unwrap(), // USDT
-        ],
-        _ => vec![],
-    }
-}
-
 // ============================================================================
 // Legacy API compatibility
 // ============================================================================
@@ -496,7 +613,7 @@ fn get_intermediate_tokens(chain: &str) -> Vec<Address> {
 /// Simple quote function (uses sequential fallback)
 pub async fn quote_swap(
     provider: &Provider<Http>,
-    chain: &str,
+    chain: &ChainConfig,
     token_in: Address,
     token_out: Address,
     amount_in: U256,
@@ -508,7 +625,7 @@ pub async fn quote_swap(
 /// Validate that a liquidation can be profitably executed
 pub async fn validate_liquidation_swap(
     provider: &Provider<Http>,
-    chain: &str,
+    chain: &ChainConfig,
     collateral_token: Address,
     debt_token: Address,
     collateral_amount: U256,
@@ -553,7 +670,7 @@ pub async fn validate_liquidation_swap(
 /// Validate with parallel quotes (slower but finds best rate)
 pub async fn validate_liquidation_swap_best(
     provider: &Provider<Http>,
-    chain: &str,
+    chain: &ChainConfig,
     collateral_token: Address,
     debt_token: Address,
     collateral_amount: U256,
@@ -1,6 +1,6 @@
 //! Core types and data structures for the liquidator bot.
 
-use ethers::types::{Address, U256};
+use ethers::types::{Address, Bytes, TxHash};
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
 
@@ -10,6 +10,8 @@ pub struct Position {
     pub user: Address,
     pub chain: String,
     pub protocol: Protocol,
+    /// Aave pool, Compound Comet or Venus Comptroller the position is in
+    pub market: Address,
     pub collateral_usd: f64,
     pub debt_usd: f64,
     pub health_factor: f64,
@@ -71,49 +73,13 @@ impl PriceData {
     }
 }
 
-/// Profit simulation result
+/// Chainlink update transaction seen in the mempool but not mined yet
 #[derive(Debug, Clone)]
-pub struct ProfitSimulation {
-    pub profitable: bool,
-    pub debt_to_repay: f64,
-    pub collateral_received: f64,
-    pub gross_profit: f64,
-    pub flash_fee: f64,
-    pub gas_cost_usd: f64,
-    pub net_profit: f64,
-}
-
-impl ProfitSimulation {
-    pub fn calculate(
-        protocol: Protocol,
-        debt_usd: f64,
-        collateral_usd: f64,
-        gas_cost_usd: f64,
-        min_profit: f64,
-    ) -> Self {
-        let bonus = match protocol {
-            Protocol::Aave => 0.05,      // 5%
-            Protocol::Compound => 0.08,  // 8%
-            Protocol::Venus => 0.10,     // 10%
-        };
-        
-        let flash_loan_fee = 0.0009; // 0.09%
-        let debt_to_repay = (debt_usd / 2.0).min(collateral_usd * 0.9);
-        let collateral_received = debt_to_repay * (1.0 + bonus);
-        let gross_profit = collateral_received - debt_to_repay;
-        let flash_fee = debt_to_repay * flash_loan_fee;
-        let net_profit = gross_profit - flash_fee - gas_cost_usd;
-        
-        Self {
-            profitable: net_profit >= min_profit,
-            debt_to_repay,
-            collateral_received,
-            gross_profit,
-            flash_fee,
-            gas_cost_usd,
-            net_profit,
-        }
-    }
+pub struct PendingOracleUpdate {
+    pub hash: TxHash,
+    /// Signed transaction, replayed at the front of a backrun bundle
+    pub raw: Bytes,
+    pub seen_at: Instant,
 }
 
 /// Stats tracking
@@ -127,6 +93,22 @@ pub struct Stats {
     pub skipped_unprofitable: u64,
     pub competitor_beats: u64,
     pub bad_debt: u64,
+    /// Liquidations dropped because the pre-flight `eth_call` reverted
+    pub simulation_reverts: u64,
+    /// Aave attempts per close factor regime
+    pub close_factor_default: u64,
+    pub close_factor_low_hf: u64,
+    pub close_factor_small_position: u64,
+    /// Batched Aave liquidation transactions sent
+    pub batches: u64,
+    /// Batch items whose `liquidationCall` reverted inside a landed batch
+    pub batch_item_failures: u64,
+    /// Liquidations the pending tx sweep cancelled before they landed
+    pub cancelled: u64,
+    /// Scans replaced by a newer one before their chain's executor took them
+    pub dropped_scans: u64,
+    /// Sent transactions no longer followed because no receipt showed up
+    pub receipt_timeouts: u64,
 }
 
 /// Circuit breaker state
@@ -0,0 +1,136 @@
+//! Config validation mode (`liquidator validate`).
+//!
+//! Checks every address in the chain registry against the live chain:
+//! the RPC must report the expected chain ID and each contract must
+//! have deployed code. Nothing is sent on-chain.
+
+use ethers::providers::{Provider, Http, Middleware};
+use ethers::types::Address;
+use tracing::{info, warn, error};
+
+use crate::config::{ChainConfig, Config};
+
+/// Validate all enabled chains. Returns the number of problems found.
+pub async fn validate_config(config: &Config) -> usize {
+    info!("🔎 Validating {} chains...", config.chains.len());
+    
+    let mut problems = 0;
+    for chain in &config.chains {
+        problems += validate_chain(chain).await;
+    }
+    
+    if problems == 0 {
+        info!("✅ Config valid");
+    } else {
+        error!("❌ Config has {} problems", problems);
+    }
+    
+    problems
+}
+
+async fn validate_chain(chain: &ChainConfig) -> usize {
+    let rpc_url = chain.rpc_url.split(',').next().unwrap_or_default().trim();
+    let provider = match Provider::<Http>::try_from(rpc_url) {
+        Ok(p) => p,
+        Err(e) => {
+            error!("  ❌ {}: Invalid RPC URL - {}", chain.name, e);
+            return 1;
+        }
+    };
+    
+    let mut problems = 0;
+    
+    match provider.get_chainid().await {
+        Ok(id) if id.as_u64() == chain.chain_id => {
+            info!("  {}: chain ID {} ✓", chain.name, chain.chain_id);
+        }
+        Ok(id) => {
+            error!("  ❌ {}: RPC reports chain ID {}, config says {}", chain.name, id, chain.chain_id);
+            return 1;
+        }
+        Err(e) => {
+            error!("  ❌ {}: RPC unreachable - {}", chain.name, e);
+            return 1;
+        }
+    }
+    
+    for (label, address) in chain_addresses(chain) {
+        let address: Address = match address.parse() {
+            Ok(a) => a,
+            Err(_) => {
+                error!("  ❌ {}: {} is not an address: {}", chain.name, label, address);
+                problems += 1;
+                continue;
+            }
+        };
+        
+        match provider.get_code(address, None).await {
+            Ok(code) if !code.is_empty() => {
+                info!("  {}: {} {:?} ✓", chain.name, label, address);
+            }
+            Ok(_) => {
+                error!("  ❌ {}: {} {:?} has no code", chain.name, label, address);
+                problems += 1;
+            }
+            Err(e) => {
+                warn!("  ⚠️ {}: {} {:?} - get_code failed: {}", chain.name, label, address, e);
+                problems += 1;
+            }
+        }
+    }
+    
+    problems
+}
+
+/// Every contract address a chain config refers to, with a label for logs
+fn chain_addresses(chain: &ChainConfig) -> Vec<(String, String)> {
+    let mut addresses = Vec::new();
+    
+    for market in &chain.aave_markets {
+        addresses.push((format!("Aave {} pool", market.name), format!("{:?}", market.pool)));
+        if let Some(data_provider) = market.data_provider {
+            addresses.push((format!("Aave {} data provider", market.name), format!("{:?}", data_provider)));
+        }
+        if let Some(liquidator) = &market.liquidator_address {
+            addresses.push((format!("Aave {} liquidator", market.name), liquidator.clone()));
+        }
+    }
+    if let Some(venus) = &chain.venus {
+        addresses.push(("Venus Comptroller".to_string(), format!("{:?}", venus.comptroller)));
+    }
+    addresses.push(("Multicall3".to_string(), format!("{:?}", chain.multicall)));
+    if let Some(quoter) = chain.quoter_address {
+        addresses.push(("quoter".to_string(), format!("{:?}", quoter)));
+    }
+    let lenders = [
+        ("Balancer vault", chain.flash_loans.balancer_vault),
+        ("Morpho Blue", chain.flash_loans.morpho),
+        ("Uniswap V3 factory", chain.flash_loans.uniswap_v3_factory),
+    ];
+    for (label, address) in lenders {
+        if let Some(address) = address {
+            addresses.push((label.to_string(), format!("{:?}", address)));
+        }
+    }
+    for token in &chain.intermediate_tokens {
+        addresses.push(("intermediate token".to_string(), format!("{:?}", token)));
+    }
+    for market in &chain.comet_markets {
+        addresses.push((format!("Comet {} ({})", market.name, market.base_token), format!("{:?}", market.address)));
+    }
+    for feed in &chain.chainlink_feeds {
+        addresses.push((format!("Chainlink feed for {:?}", feed.token), format!("{:?}", feed.feed)));
+    }
+    
+    let liquidators = [
+        ("Compound liquidator", &chain.compound_liquidator_address),
+        ("Venus liquidator", &chain.venus_liquidator_address),
+    ];
+    for (label, address) in liquidators {
+        if let Some(address) = address {
+            addresses.push((label.to_string(), address.clone()));
+        }
+    }
+    
+    addresses
+}